@@ -0,0 +1,98 @@
+use super::expression::Expression;
+use super::program::Program;
+use super::statement::Statement;
+use super::visit::{walk_expression, walk_statement, Visitor};
+
+/// Renders `program` as an indented tree, one node per line, children two spaces under their parent.
+pub fn ast_dump(program: &Program) -> String {
+    let mut dump = Dump::default();
+    dump.visit_program(program);
+    dump.out
+}
+
+#[derive(Default)]
+struct Dump {
+    out: String,
+    depth: usize,
+}
+
+impl Dump {
+    fn line(&mut self, label: impl AsRef<str>) {
+        self.out.push_str(&"  ".repeat(self.depth));
+        self.out.push_str(label.as_ref());
+        self.out.push('\n');
+    }
+
+    fn nested(&mut self, label: impl AsRef<str>, children: impl FnOnce(&mut Self)) {
+        self.line(label);
+        self.depth += 1;
+        children(self);
+        self.depth -= 1;
+    }
+
+    fn statements(&mut self, label: &str, statements: &[Statement]) {
+        self.nested(label, |dump| {
+            for statement in statements {
+                dump.visit_statement(statement);
+            }
+        });
+    }
+}
+
+impl Visitor for Dump {
+    fn visit_statement(&mut self, statement: &Statement) {
+        let label = match statement {
+            Statement::Expression(_) => "Expression".to_string(),
+            Statement::Let { name, .. } => format!("Let {name}"),
+            Statement::Return(_) => "Return".to_string(),
+            Statement::Block(_) => "Block".to_string(),
+        };
+        self.nested(label, |dump| walk_statement(dump, statement));
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Int(int) => self.line(format!("Int {int}")),
+            Expression::Bool(bool) => self.line(format!("Bool {bool}")),
+            Expression::Identifier(name) => self.line(format!("Identifier {name}")),
+            Expression::String(string) => self.line(format!("String {string:?}")),
+            Expression::Prefix { operator, .. } => self
+                .nested(format!("Prefix {operator}"), |dump| {
+                    walk_expression(dump, expression)
+                }),
+            Expression::Infix { operator, .. } => self
+                .nested(format!("Infix {operator}"), |dump| {
+                    walk_expression(dump, expression)
+                }),
+            Expression::If {
+                condition,
+                consequence,
+                alternative,
+            } => self.nested("If", |dump| {
+                dump.visit_expression(condition);
+                dump.statements("Then", consequence);
+                if let Some(alternative) = alternative {
+                    dump.statements("Else", alternative);
+                }
+            }),
+            Expression::Fn {
+                parameters, body, ..
+            } => self.statements(&format!("Fn({})", parameters.join(", ")), body),
+            Expression::Call { .. } => {
+                self.nested("Call", |dump| walk_expression(dump, expression))
+            }
+            Expression::Array(_) => self.nested("Array", |dump| walk_expression(dump, expression)),
+            Expression::Index { .. } => {
+                self.nested("Index", |dump| walk_expression(dump, expression))
+            }
+            Expression::Hash(pairs) => self.nested("Hash", |dump| {
+                for (key, value) in pairs {
+                    dump.nested("Pair", |dump| {
+                        dump.visit_expression(key);
+                        dump.visit_expression(value);
+                    });
+                }
+            }),
+        }
+    }
+}
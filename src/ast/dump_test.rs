@@ -0,0 +1,46 @@
+use super::dump::ast_dump;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+
+#[test]
+fn test_ast_dump() {
+    let mut parser = Parser::new(Lexer::from(
+        "let f = fn(x) { if (!x) { -1 } else { x * (2 + 3) } }; f({\"a\": [1]}[\"a\"][0]);",
+    ));
+    let program = parser.parse_program();
+    parser.check_errors();
+
+    assert_eq!(
+        ast_dump(&program),
+        "Let f
+  Fn(x)
+    Expression
+      If
+        Prefix !
+          Identifier x
+        Then
+          Expression
+            Prefix -
+              Int 1
+        Else
+          Expression
+            Infix *
+              Identifier x
+              Infix +
+                Int 2
+                Int 3
+Expression
+  Call
+    Identifier f
+    Index
+      Index
+        Hash
+          Pair
+            String \"a\"
+            Array
+              Int 1
+        String \"a\"
+      Int 0
+"
+    );
+}
@@ -1,5 +1,6 @@
 use crate::ast::operator::*;
 use std::fmt::Display;
+use std::rc::Rc;
 
 use super::statement::Statement;
 
@@ -20,13 +21,14 @@ pub enum Expression {
     },
     If {
         condition: Box<Expression>,
-        consequence: Vec<Statement>,
-        alternative: Option<Vec<Statement>>,
+        consequence: Rc<[Statement]>,
+        alternative: Option<Rc<[Statement]>>,
     },
     Fn {
         name: String,
         parameters: Vec<String>,
-        body: Vec<Statement>,
+        /// Shared with the functions the evaluator creates from it.
+        body: Rc<[Statement]>,
     },
     Call {
         function: Box<Expression>,
@@ -73,14 +75,14 @@ impl Display for Expression {
             } => {
                 write!(f, "if {condition} {{")?;
 
-                for statement in consequence {
+                for statement in consequence.iter() {
                     write!(f, "{statement}")?;
                 }
 
                 write!(f, "}}")?;
 
                 if let Some(statements) = alternative {
-                    for statement in statements {
+                    for statement in statements.iter() {
                         write!(f, "{statement}")?
                     }
                 }
@@ -93,7 +95,7 @@ impl Display for Expression {
             } => {
                 write!(f, "fn {} ({}) {{", name, parameters.join(", "))?;
 
-                for statement in body {
+                for statement in body.iter() {
                     write!(f, "{statement}")?;
                 }
 
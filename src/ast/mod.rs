@@ -1,4 +1,11 @@
+pub mod dump;
+#[cfg(test)]
+mod dump_test;
 pub mod expression;
 pub mod operator;
 pub mod program;
 pub mod statement;
+pub mod trivia;
+pub mod visit;
+#[cfg(test)]
+mod visit_test;
@@ -1,10 +1,16 @@
 use std::fmt::Display;
 
 use crate::ast::statement::Statement;
+use crate::ast::trivia::{Comment, Trivia};
 
 #[derive(Debug, Default)]
 pub struct Program {
     pub statements: Vec<Statement>,
+    pub positions: Vec<(usize, usize)>,
+    /// The comments of each statement, `trivia[idx]` belongs to `statements[idx]`.
+    pub trivia: Vec<Trivia>,
+    /// Comments after the last statement.
+    pub dangling: Vec<Comment>,
 }
 
 impl Display for Program {
@@ -0,0 +1,24 @@
+use crate::lexer::token::Span;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    pub text: String,
+    pub span: Span,
+}
+
+/// The comments that belong to a top level statement.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Trivia {
+    /// Comments on the lines above the statement.
+    pub leading: Vec<Comment>,
+    /// Comments inside the statement, like the ones in a function body.
+    pub inner: Vec<Comment>,
+    /// A comment after the statement on its last line.
+    pub trailing: Option<Comment>,
+}
+
+impl Trivia {
+    pub fn is_empty(&self) -> bool {
+        self.leading.is_empty() && self.inner.is_empty() && self.trailing.is_none()
+    }
+}
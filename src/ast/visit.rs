@@ -0,0 +1,202 @@
+use super::expression::Expression;
+use super::program::Program;
+use super::statement::Statement;
+
+/// Walks the AST by reference. Override a method to handle a node and call the matching
+/// `walk_*` function inside it to keep visiting its children.
+pub trait Visitor {
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program);
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        walk_expression(self, expression);
+    }
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    for statement in &program.statements {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &Statement) {
+    match statement {
+        Statement::Expression(value) | Statement::Let { value, .. } | Statement::Return(value) => {
+            visitor.visit_expression(value)
+        }
+        Statement::Block(statements) => {
+            for statement in statements {
+                visitor.visit_statement(statement);
+            }
+        }
+    }
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &Expression) {
+    match expression {
+        Expression::Int(_)
+        | Expression::Bool(_)
+        | Expression::String(_)
+        | Expression::Identifier(_) => {}
+        Expression::Prefix { rhs, .. } => visitor.visit_expression(rhs),
+        Expression::Infix { lhs, rhs, .. } => {
+            visitor.visit_expression(lhs);
+            visitor.visit_expression(rhs);
+        }
+        Expression::If {
+            condition,
+            consequence,
+            alternative,
+        } => {
+            visitor.visit_expression(condition);
+            for statement in consequence
+                .iter()
+                .chain(alternative.iter().flat_map(|block| block.iter()))
+            {
+                visitor.visit_statement(statement);
+            }
+        }
+        Expression::Fn { body, .. } => {
+            for statement in body.iter() {
+                visitor.visit_statement(statement);
+            }
+        }
+        Expression::Call {
+            function,
+            arguments,
+        } => {
+            visitor.visit_expression(function);
+            for argument in arguments {
+                visitor.visit_expression(argument);
+            }
+        }
+        Expression::Array(elements) => {
+            for element in elements {
+                visitor.visit_expression(element);
+            }
+        }
+        Expression::Index { lhs, index } => {
+            visitor.visit_expression(lhs);
+            visitor.visit_expression(index);
+        }
+        Expression::Hash(pairs) => {
+            for (key, value) in pairs {
+                visitor.visit_expression(key);
+                visitor.visit_expression(value);
+            }
+        }
+    }
+}
+
+/// Rebuilds the AST by value, the default methods rebuild every node unchanged through the
+/// `fold_*` functions. Children are folded before their parent is rebuilt.
+pub trait Fold {
+    fn fold_program(&mut self, program: Program) -> Program {
+        fold_program(self, program)
+    }
+
+    fn fold_statement(&mut self, statement: Statement) -> Statement {
+        fold_statement(self, statement)
+    }
+
+    fn fold_expression(&mut self, expression: Expression) -> Expression {
+        fold_expression(self, expression)
+    }
+}
+
+/// Keeps `positions` and `trivia` as they are, so a fold should not add or drop top level statements.
+pub fn fold_program<F: Fold + ?Sized>(folder: &mut F, program: Program) -> Program {
+    Program {
+        statements: fold_statements(folder, program.statements),
+        ..program
+    }
+}
+
+fn fold_statements<F: Fold + ?Sized>(folder: &mut F, statements: Vec<Statement>) -> Vec<Statement> {
+    statements
+        .into_iter()
+        .map(|statement| folder.fold_statement(statement))
+        .collect()
+}
+
+fn fold_expressions<F: Fold + ?Sized>(
+    folder: &mut F,
+    expressions: Vec<Expression>,
+) -> Vec<Expression> {
+    expressions
+        .into_iter()
+        .map(|expression| folder.fold_expression(expression))
+        .collect()
+}
+
+pub fn fold_statement<F: Fold + ?Sized>(folder: &mut F, statement: Statement) -> Statement {
+    match statement {
+        Statement::Expression(value) => Statement::Expression(folder.fold_expression(value)),
+        Statement::Let { name, value } => Statement::Let {
+            name,
+            value: folder.fold_expression(value),
+        },
+        Statement::Return(value) => Statement::Return(folder.fold_expression(value)),
+        Statement::Block(statements) => Statement::Block(fold_statements(folder, statements)),
+    }
+}
+
+pub fn fold_expression<F: Fold + ?Sized>(folder: &mut F, expression: Expression) -> Expression {
+    match expression {
+        Expression::Int(_)
+        | Expression::Bool(_)
+        | Expression::String(_)
+        | Expression::Identifier(_) => expression,
+        Expression::Prefix { rhs, operator } => Expression::Prefix {
+            rhs: Box::new(folder.fold_expression(*rhs)),
+            operator,
+        },
+        Expression::Infix { lhs, operator, rhs } => Expression::Infix {
+            lhs: Box::new(folder.fold_expression(*lhs)),
+            operator,
+            rhs: Box::new(folder.fold_expression(*rhs)),
+        },
+        Expression::If {
+            condition,
+            consequence,
+            alternative,
+        } => Expression::If {
+            condition: Box::new(folder.fold_expression(*condition)),
+            consequence: fold_statements(folder, consequence.to_vec()).into(),
+            alternative: alternative
+                .map(|alternative| fold_statements(folder, alternative.to_vec()).into()),
+        },
+        Expression::Fn {
+            name,
+            parameters,
+            body,
+        } => Expression::Fn {
+            name,
+            parameters,
+            body: fold_statements(folder, body.to_vec()).into(),
+        },
+        Expression::Call {
+            function,
+            arguments,
+        } => Expression::Call {
+            function: Box::new(folder.fold_expression(*function)),
+            arguments: fold_expressions(folder, arguments),
+        },
+        Expression::Array(elements) => Expression::Array(fold_expressions(folder, elements)),
+        Expression::Index { lhs, index } => Expression::Index {
+            lhs: Box::new(folder.fold_expression(*lhs)),
+            index: Box::new(folder.fold_expression(*index)),
+        },
+        Expression::Hash(pairs) => Expression::Hash(
+            pairs
+                .into_iter()
+                .map(|(key, value)| (folder.fold_expression(key), folder.fold_expression(value)))
+                .collect(),
+        ),
+    }
+}
@@ -0,0 +1,67 @@
+use super::expression::Expression;
+use super::operator::InfixOperator;
+use super::program::Program;
+use super::visit::*;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+
+fn parse(input: &str) -> Program {
+    let mut parser = Parser::new(Lexer::from(input));
+    let program = parser.parse_program();
+    parser.check_errors();
+    program
+}
+
+#[derive(Default)]
+struct Identifiers(Vec<String>);
+
+impl Visitor for Identifiers {
+    fn visit_expression(&mut self, expression: &Expression) {
+        if let Expression::Identifier(name) = expression {
+            self.0.push(name.clone());
+        }
+        walk_expression(self, expression);
+    }
+}
+
+struct AddInts;
+
+impl Fold for AddInts {
+    fn fold_expression(&mut self, expression: Expression) -> Expression {
+        match fold_expression(self, expression) {
+            Expression::Infix {
+                lhs,
+                operator: InfixOperator::Add,
+                rhs,
+            } => match (*lhs, *rhs) {
+                (Expression::Int(lhs), Expression::Int(rhs)) => Expression::Int(lhs + rhs),
+                (lhs, rhs) => Expression::Infix {
+                    lhs: Box::new(lhs),
+                    operator: InfixOperator::Add,
+                    rhs: Box::new(rhs),
+                },
+            },
+            expression => expression,
+        }
+    }
+}
+
+#[test]
+fn test_visitor() {
+    let program =
+        parse("let f = fn(x) { if (x > a) { [b, {c: d[e]}] } else { g(h) } }; return -i;");
+    let mut identifiers = Identifiers::default();
+    identifiers.visit_program(&program);
+
+    assert_eq!(identifiers.0, ["x", "a", "b", "c", "d", "e", "g", "h", "i"]);
+}
+
+#[test]
+fn test_fold() {
+    let program = AddInts.fold_program(parse("let x = 1 + 2 + 3; fn() { [x + 1, 4 + 5] }"));
+
+    assert_eq!(
+        program.statements,
+        parse("let x = 6; fn() { [x + 1, 9] }").statements
+    );
+}
@@ -15,6 +15,36 @@ impl Instructions {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// One instruction per line, `Display` puts them all on one.
+    pub fn disassemble(&self) -> String {
+        self.decode()
+            .into_iter()
+            .map(|(offset, op, operands)| {
+                let operands = operands.iter().map(|operand| format!(" {operand}"));
+                let operands = operands.collect::<String>();
+                format!("{offset:04} {}{operands}\n", Definition::from(op).name())
+            })
+            .collect()
+    }
+
+    pub fn decode(&self) -> Vec<(usize, OpCode, Vec<i64>)> {
+        let mut decoded = vec![];
+        let mut idx = 0;
+        while idx < self.0.len() {
+            let op = match OpCode::try_from(self[idx]) {
+                Ok(op) => op,
+                Err(_) => {
+                    idx += 1;
+                    continue;
+                }
+            };
+            let (operands, read) = read_operands(&op.into(), &self[idx + 1..]);
+            decoded.push((idx, op, operands));
+            idx += 1 + read;
+        }
+        decoded
+    }
 }
 
 impl From<Vec<(OpCode, Vec<i64>)>> for Instructions {
@@ -81,7 +111,7 @@ impl Display for Instructions {
                     continue;
                 }
             };
-            let (operands, read) = read_operands(&definition, self[idx + 1..].to_vec());
+            let (operands, read) = read_operands(&definition, &self[idx + 1..]);
             write!(f, "{:04} {} ", idx, fmt_intruction(definition, &operands))?;
             idx += 1 + read;
         }
@@ -99,7 +129,7 @@ pub fn concat_instructions(instructions: &[Instructions]) -> Instructions {
     )
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum OpCode {
     OpConstant = 0,
     OpAdd,
@@ -131,6 +161,10 @@ pub enum OpCode {
     OpClosure,
     OpGetFree,
     OpCurrentClosure,
+    OpPushInt,
+    OpConst0,
+    OpConst1,
+    OpMod,
 }
 
 #[derive(Debug)]
@@ -153,6 +187,14 @@ impl Definition {
             operand_widths: width,
         }
     }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn operand_widths(&self) -> &[u8] {
+        &self.operand_widths
+    }
 }
 
 impl From<OpCode> for Definition {
@@ -188,6 +230,10 @@ impl From<OpCode> for Definition {
             OpCode::OpClosure => Definition::new("OpClosure").width(vec![2, 1]),
             OpCode::OpGetFree => Definition::new("OpGetFree").width(vec![1]),
             OpCode::OpCurrentClosure => Definition::new("OpCurrentClosure"),
+            OpCode::OpPushInt => Definition::new("OpPushInt").width(vec![2]),
+            OpCode::OpConst0 => Definition::new("OpConst0"),
+            OpCode::OpConst1 => Definition::new("OpConst1"),
+            OpCode::OpMod => Definition::new("OpMod"),
         }
     }
 }
@@ -227,12 +273,16 @@ impl TryFrom<u8> for OpCode {
             27 => OpCode::OpClosure,
             28 => OpCode::OpGetFree,
             29 => OpCode::OpCurrentClosure,
+            30 => OpCode::OpPushInt,
+            31 => OpCode::OpConst0,
+            32 => OpCode::OpConst1,
+            33 => OpCode::OpMod,
             _ => return Err(()),
         })
     }
 }
 
-pub fn read_operands(definition: &Definition, instruction: Vec<u8>) -> (Vec<i64>, usize) {
+pub fn read_operands(definition: &Definition, instruction: &[u8]) -> (Vec<i64>, usize) {
     let mut operands = vec![];
     let mut offset = 0;
 
@@ -254,6 +304,14 @@ pub fn read_operands(definition: &Definition, instruction: Vec<u8>) -> (Vec<i64>
     (operands.to_vec(), offset)
 }
 
+pub fn read_u16(instructions: &[u8]) -> u16 {
+    u16::from_be_bytes([instructions[0], instructions[1]])
+}
+
+pub fn read_u8(instructions: &[u8]) -> u8 {
+    instructions[0]
+}
+
 pub fn make(op: OpCode, operands: &[i64]) -> Instructions {
     let definition: Definition = op.into();
     let mut instruction = vec![];
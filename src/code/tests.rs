@@ -27,6 +27,11 @@ fn test_make() {
             operands: vec![65534, 255],
             expected: Instructions(vec![OpCode::OpClosure as u8, 255, 254, 255]),
         },
+        Test {
+            op: OpCode::OpPushInt,
+            operands: vec![-2],
+            expected: Instructions(vec![OpCode::OpPushInt as u8, 255, 254]),
+        },
     ];
 
     for test in tests {
@@ -50,6 +55,19 @@ pub fn test_instruction_string() {
     assert_eq!(concat_instructions(&instructions).to_string(), expected)
 }
 
+#[test]
+pub fn test_disassemble() {
+    let instructions = concat_instructions(&[
+        make(OpCode::OpAdd, &[]),
+        make(OpCode::OpGetLocal, &[1]),
+        make(OpCode::OpClosure, &[65535, 255]),
+    ]);
+
+    let expected = "0000 OpAdd\n0001 OpGetLocal 1\n0003 OpClosure 65535 255\n";
+
+    assert_eq!(instructions.disassemble(), expected)
+}
+
 #[test]
 pub fn test_read_operands() {
     struct Test {
@@ -79,8 +97,35 @@ pub fn test_read_operands() {
     for test in tests.iter() {
         let intruction = make(test.op, &test.operands);
         let definition: Definition = test.op.into();
-        let (operands_read, n) = read_operands(&definition, intruction.0[1..].to_vec());
+        let (operands_read, n) = read_operands(&definition, &intruction.0[1..]);
         assert_eq!(test.bytes_read, n);
         assert_eq!(test.operands, operands_read);
     }
 }
+
+#[test]
+pub fn test_decode() {
+    let instructions = concat_instructions(&[
+        make(OpCode::OpAdd, &[]),
+        make(OpCode::OpGetLocal, &[1]),
+        make(OpCode::OpConstant, &[65535]),
+        make(OpCode::OpClosure, &[2, 1]),
+    ]);
+
+    assert_eq!(
+        instructions.decode(),
+        vec![
+            (0, OpCode::OpAdd, vec![]),
+            (1, OpCode::OpGetLocal, vec![1]),
+            (3, OpCode::OpConstant, vec![65535]),
+            (6, OpCode::OpClosure, vec![2, 1]),
+        ]
+    );
+}
+
+#[test]
+pub fn test_read_helpers() {
+    let instructions = make(OpCode::OpClosure, &[65534, 255]);
+    assert_eq!(read_u16(&instructions[1..]), 65534);
+    assert_eq!(read_u8(&instructions[3..]), 255);
+}
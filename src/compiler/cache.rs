@@ -0,0 +1,153 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::code::OpCode;
+use crate::eval::builtin::BUILTINS;
+
+use super::constant::Constant;
+use super::mkc::{self, Reader, Writer};
+use super::source_map::SourceMap;
+use super::{ByteCode, Compiler};
+
+/// Compiled programs kept on disk by the hash of their source, `monkey run` reuses them while
+/// the source and the compiler don't change.
+///
+/// An entry holds the fingerprint of the compiler that wrote it and the whole source, a load
+/// checks both so a hash collision or a rebuilt compiler is a miss rather than a wrong program.
+pub struct BytecodeCache {
+    dir: PathBuf,
+}
+
+impl BytecodeCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        BytecodeCache { dir: dir.into() }
+    }
+
+    /// `$MONKEY_CACHE_DIR`, else `monkey` in `$XDG_CACHE_HOME` or `~/.cache`.
+    pub fn default_dir() -> Option<PathBuf> {
+        if let Some(dir) = std::env::var_os("MONKEY_CACHE_DIR") {
+            return Some(PathBuf::from(dir));
+        }
+        let cache = match std::env::var_os("XDG_CACHE_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => Path::new(&std::env::var_os("HOME")?).join(".cache"),
+        };
+        Some(cache.join("monkey"))
+    }
+
+    pub fn path(&self, source: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.mkc", source_hash(source)))
+    }
+
+    /// The bytecode of `source` and its source map, `None` when it isn't cached, the entry was
+    /// written by another compiler or for another source, or it doesn't decode.
+    pub fn load(&self, source: &str) -> Option<(ByteCode, Option<SourceMap>)> {
+        let bytes = std::fs::read(self.path(source)).ok()?;
+        let mut reader = Reader::new(&bytes);
+        if reader.u64().ok()? != compiler_fingerprint()? || reader.string().ok()? != source {
+            return None;
+        }
+        mkc::decode(reader.rest()).ok()
+    }
+
+    /// Writes to a temporary file first, a run reading the entry never sees half of it.
+    ///
+    /// Programs that call `eval` aren't stored, `eval` compiles against the symbol table of
+    /// the program and an entry doesn't keep it.
+    pub fn store(
+        &self,
+        source: &str,
+        byte_code: &ByteCode,
+        source_map: Option<&SourceMap>,
+    ) -> Result<(), String> {
+        let Some(fingerprint) = compiler_fingerprint() else {
+            return Ok(());
+        };
+        if uses_eval(byte_code) {
+            return Ok(());
+        }
+        let mut writer = Writer::default();
+        writer.u64(fingerprint);
+        writer.string(source);
+        writer.bytes(&mkc::encode(byte_code, source_map).map_err(|err| err.to_string())?);
+        std::fs::create_dir_all(&self.dir).map_err(|err| err.to_string())?;
+        let path = self.path(source);
+        let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+        std::fs::write(&tmp, writer.buffer)
+            .and_then(|()| std::fs::rename(&tmp, &path))
+            .map_err(|err| err.to_string())
+    }
+
+    /// The bytecode of `source` from the cache, else from the compiler `compile` returns,
+    /// which is then stored for the next run.
+    pub fn compile<E>(
+        &self,
+        file: &str,
+        source: &str,
+        compile: impl FnOnce() -> Result<Compiler, E>,
+    ) -> Result<(ByteCode, Option<SourceMap>), E> {
+        if let Some((byte_code, source_map)) = self.load(source) {
+            // The same source may have been cached from another path.
+            let source_map = source_map.map(|source_map| SourceMap {
+                file: file.to_string(),
+                ..source_map
+            });
+            return Ok((byte_code, source_map));
+        }
+        let mut compiler = compile()?;
+        let byte_code = compiler.bytecode();
+        let source_map = compiler.source_map(file, source);
+        // A cache that can't be written only costs the next run a compile.
+        let _ = self.store(source, &byte_code, Some(&source_map));
+        Ok((byte_code, Some(source_map)))
+    }
+}
+
+/// FNV-1a over the source, it names the entry, `load` compares the source itself.
+pub fn source_hash(source: &str) -> u64 {
+    source.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// What tells this compiler from another build of it: the versions and the size and
+/// modification time of the running executable. `None` when the executable can't be
+/// looked at, then nothing is cached.
+fn compiler_fingerprint() -> Option<u64> {
+    static FINGERPRINT: OnceLock<Option<u64>> = OnceLock::new();
+    *FINGERPRINT.get_or_init(|| {
+        let metadata = std::fs::metadata(std::env::current_exe().ok()?).ok()?;
+        let modified = metadata
+            .modified()
+            .ok()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?;
+        Some(source_hash(&format!(
+            "{}:{}:{}:{}",
+            env!("CARGO_PKG_VERSION"),
+            mkc::VERSION,
+            metadata.len(),
+            modified.as_nanos()
+        )))
+    })
+}
+
+fn uses_eval(byte_code: &ByteCode) -> bool {
+    let Some(eval) = BUILTINS
+        .lookup("eval")
+        .and_then(|eval| BUILTINS.index_of(eval))
+    else {
+        return false;
+    };
+    let functions = byte_code
+        .constants
+        .iter()
+        .filter_map(|constant| match constant {
+            Constant::CompiledFunction { instructions, .. } => Some(instructions.as_ref()),
+            _ => None,
+        });
+    std::iter::once(byte_code.instructions.as_ref())
+        .chain(functions)
+        .flat_map(|instructions| instructions.decode())
+        .any(|(_, op, operands)| op == OpCode::OpGetBuiltin && operands[0] as usize == eval)
+}
@@ -0,0 +1,101 @@
+use std::cell::Cell;
+
+use crate::eval::value::Value;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::vm::Vm;
+
+use super::cache::{source_hash, BytecodeCache};
+use super::Compiler;
+
+#[test]
+fn test_cache_round_trip() {
+    let dir = std::env::temp_dir().join(format!("monkey-cache-test-{}", std::process::id()));
+    let cache = BytecodeCache::new(&dir);
+    let source = "let double = fn(x) { x * 2 }; double(21)";
+    assert!(cache.load(source).is_none());
+
+    let mut compiler = Compiler::new();
+    compiler
+        .compile_program(Parser::new(Lexer::from(source)).parse_program())
+        .unwrap();
+    let byte_code = compiler.bytecode();
+    let source_map = compiler.source_map("double.mk", source);
+    cache.store(source, &byte_code, Some(&source_map)).unwrap();
+
+    let (cached, cached_map) = cache.load(source).expect("the bytecode should be cached");
+    assert_eq!(cached.instructions, byte_code.instructions);
+    assert_eq!(cached_map, Some(source_map));
+    assert!(cache.load("double(1)").is_none());
+
+    std::fs::write(cache.path(source), b"\x7fMKC garbage").unwrap();
+    assert!(cache.load(source).is_none());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_cache_checks_the_entry() {
+    let dir = std::env::temp_dir().join(format!("monkey-cache-check-{}", std::process::id()));
+    let cache = BytecodeCache::new(&dir);
+    let source = "1 + 2";
+    let byte_code = compile(source).bytecode();
+    cache.store(source, &byte_code, None).unwrap();
+    assert!(cache.load(source).is_some());
+
+    // An entry found under the hash of another source.
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy(cache.path(source), cache.path("3 + 4")).unwrap();
+    assert!(cache.load("3 + 4").is_none());
+
+    // An entry from another build of the compiler.
+    let mut bytes = std::fs::read(cache.path(source)).unwrap();
+    bytes[0] ^= 0xff;
+    std::fs::write(cache.path(source), bytes).unwrap();
+    assert!(cache.load(source).is_none());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_cached_runs() {
+    let dir = std::env::temp_dir().join(format!("monkey-cache-runs-{}", std::process::id()));
+    let cache = BytecodeCache::new(&dir);
+    for (source, expected, compiles) in [
+        ("let double = fn(x) { x * 2 }; double(21)", 42, 1),
+        // Not cached, `eval` needs the symbol table.
+        ("let a = 40; eval(\"a + 2\")", 42, 2),
+        ("let a = 40; let f = fn() { eval(\"a + 2\") }; f()", 42, 2),
+    ] {
+        let count = Cell::new(0);
+        for _ in 0..2 {
+            let (byte_code, _) = cache
+                .compile("run.mk", source, || -> Result<_, ()> {
+                    count.set(count.get() + 1);
+                    Ok(compile(source))
+                })
+                .unwrap();
+            let mut vm = Vm::new(byte_code);
+            vm.run().unwrap();
+            assert_eq!(
+                vm.last_popped_element,
+                Some(Value::Int(expected)),
+                "{source}"
+            );
+        }
+        assert_eq!(count.get(), compiles, "{source}");
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_source_hash() {
+    assert_eq!(source_hash("1 + 1"), source_hash("1 + 1"));
+    assert_ne!(source_hash("1 + 1"), source_hash("1 + 2"));
+}
+
+fn compile(source: &str) -> Compiler {
+    let mut compiler = Compiler::new();
+    compiler
+        .compile_program(Parser::new(Lexer::from(source)).parse_program())
+        .unwrap();
+    compiler
+}
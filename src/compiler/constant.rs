@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::code::Instructions;
+use crate::eval::builtin::Builtin;
+use crate::eval::value::{HashKey, Value};
+
+/// The values a compiled program holds as constants, the data subset of `Value` on `Arc`s so
+/// a `ByteCode` is `Send + Sync`. Strings and instructions are shared with the values a vm
+/// makes of them, only arrays and hashes, which just `.mkc` files hold, are rebuilt.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Int(i64),
+    Bool(bool),
+    Null,
+    String(Arc<str>),
+    Array(Arc<[Constant]>),
+    Hash(Arc<[(Constant, Constant)]>),
+    CompiledFunction {
+        instructions: Arc<Instructions>,
+        num_locals: usize,
+        num_parameters: usize,
+    },
+    Closure {
+        fun: Box<Constant>,
+        free: Vec<Constant>,
+    },
+    Builtin(&'static Builtin),
+}
+
+impl Constant {
+    /// The constants of a compiler, which only makes data.
+    pub(crate) fn all(values: &[Value]) -> Arc<[Constant]> {
+        values
+            .iter()
+            .map(|value| Constant::try_from(value).expect("a constant should be data"))
+            .collect()
+    }
+}
+
+impl TryFrom<&Value> for Constant {
+    type Error = String;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        Ok(match value {
+            Value::Int(int) => Constant::Int(*int),
+            Value::Bool(bool) => Constant::Bool(*bool),
+            Value::Null => Constant::Null,
+            Value::String(string) => Constant::String(Arc::clone(string)),
+            Value::Array(array) => Constant::Array(
+                array
+                    .iter()
+                    .map(Constant::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Hash(hash) => Constant::Hash(
+                hash.iter()
+                    .map(|(key, value)| {
+                        let key = Constant::try_from(&Value::from(key.clone()))?;
+                        Ok((key, Constant::try_from(value)?))
+                    })
+                    .collect::<Result<_, String>>()?,
+            ),
+            Value::CompiledFunction {
+                instructions,
+                num_locals,
+                num_parameters,
+            } => Constant::CompiledFunction {
+                instructions: Arc::clone(instructions),
+                num_locals: *num_locals,
+                num_parameters: *num_parameters,
+            },
+            Value::Closure { fun, free } => Constant::Closure {
+                fun: Box::new(Constant::try_from(fun.as_ref())?),
+                free: free
+                    .iter()
+                    .map(Constant::try_from)
+                    .collect::<Result<_, _>>()?,
+            },
+            Value::Builtin(builtin) => Constant::Builtin(builtin),
+            value => return Err(format!("a {} value can't be a constant", value.as_type())),
+        })
+    }
+}
+
+impl From<&Constant> for Value {
+    fn from(constant: &Constant) -> Self {
+        match constant {
+            Constant::Int(int) => Value::Int(*int),
+            Constant::Bool(bool) => Value::Bool(*bool),
+            Constant::Null => Value::Null,
+            Constant::String(string) => Value::String(Arc::clone(string)),
+            Constant::Array(array) => {
+                Value::Array(Rc::new(array.iter().map(Value::from).collect()))
+            }
+            Constant::Hash(hash) => {
+                let hash = hash.iter().map(|(key, value)| {
+                    // Only hashable values became keys on the way in.
+                    let key = HashKey::try_from(Value::from(key)).expect("a hash key");
+                    (key, Value::from(value))
+                });
+                Value::Hash(Rc::new(hash.collect::<HashMap<_, _>>()))
+            }
+            Constant::CompiledFunction {
+                instructions,
+                num_locals,
+                num_parameters,
+            } => Value::CompiledFunction {
+                instructions: Arc::clone(instructions),
+                num_locals: *num_locals,
+                num_parameters: *num_parameters,
+            },
+            Constant::Closure { fun, free } => Value::Closure {
+                fun: Box::new(Value::from(fun.as_ref())),
+                free: free.iter().map(Value::from).collect(),
+            },
+            Constant::Builtin(builtin) => Value::Builtin(builtin),
+        }
+    }
+}
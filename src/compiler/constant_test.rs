@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread;
+
+use crate::code::Instructions;
+use crate::engine::run_source;
+use crate::eval::value::Value;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::vm::Vm;
+
+use super::constant::Constant;
+use super::{ByteCode, Compiler};
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_shared_across_threads() {
+    assert_send_sync::<ByteCode>();
+
+    let input = r#"let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } };
+        let name = "fib"; name + ": " + str(fib(15))"#;
+    let mut compiler = Compiler::new();
+    compiler
+        .compile_program(Parser::new(Lexer::from(input)).parse_program())
+        .unwrap();
+    let byte_code = compiler.bytecode();
+    let constants = Arc::as_ptr(&byte_code.constants) as *const u8 as usize;
+
+    let threads = (0..4).map(|_| {
+        let byte_code = byte_code.clone();
+        thread::spawn(move || {
+            // Every vm reads the one copy of the constants.
+            let shared = Arc::as_ptr(&byte_code.constants) as *const u8 as usize;
+            let mut vm = Vm::new(byte_code);
+            vm.run().unwrap();
+            (
+                vm.last_popped_element.map(|value| value.to_string()),
+                shared,
+            )
+        })
+    });
+    for thread in threads.collect::<Vec<_>>() {
+        let (result, shared) = thread.join().unwrap();
+        assert_eq!(result.as_deref(), Some("\"fib: 610\""));
+        assert_eq!(shared, constants);
+    }
+}
+
+#[test]
+fn test_values_share_constants() {
+    let string: Arc<str> = Arc::from("monkey");
+    match Value::from(&Constant::String(string.clone())) {
+        Value::String(value) => assert!(Arc::ptr_eq(&value, &string)),
+        value => panic!("expected a string, got {value}"),
+    }
+    let instructions = Arc::new(Instructions::default());
+    let function = Constant::CompiledFunction {
+        instructions: instructions.clone(),
+        num_locals: 0,
+        num_parameters: 0,
+    };
+    match Value::from(&function) {
+        Value::CompiledFunction {
+            instructions: value,
+            ..
+        } => assert!(Arc::ptr_eq(&value, &instructions)),
+        value => panic!("expected a function, got {value}"),
+    }
+}
+
+#[test]
+fn test_constants() {
+    let value = run_source(r#"[1, true, if (false) { 1 }, "a", {"k": [2]}, len]"#).unwrap();
+    let constant = Constant::try_from(&value).unwrap();
+    assert_eq!(Value::from(&constant), value);
+
+    let hash = Value::from(HashMap::from([("x".to_string(), 1)]));
+    assert_eq!(Value::from(&Constant::try_from(&hash).unwrap()), hash);
+
+    let function = run_source("fn(x) { x }").unwrap();
+    assert_eq!(
+        Value::from(&Constant::try_from(&function).unwrap()),
+        function
+    );
+    assert_eq!(
+        Constant::try_from(&Value::Exit(1)),
+        Err("a EXIT value can't be a constant".to_string())
+    );
+}
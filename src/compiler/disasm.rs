@@ -0,0 +1,109 @@
+use crate::code::{Definition, Instructions, OpCode};
+use crate::eval::builtin::json::stringify_string;
+use crate::eval::value::Value;
+
+use super::constant::Constant;
+use super::source_map::SourceMap;
+use super::ByteCode;
+
+/// The main instructions, then every compiled function among the constants.
+pub fn disassemble(byte_code: &ByteCode) -> String {
+    let mut text = byte_code.instructions.disassemble();
+    for (idx, constant) in byte_code.constants.iter().enumerate() {
+        if let Constant::CompiledFunction {
+            instructions,
+            num_parameters,
+            num_locals,
+        } = constant
+        {
+            text.push_str(&format!(
+                "\nconstant {idx}: {num_parameters} parameters, {num_locals} locals\n"
+            ));
+            text.push_str(&instructions.disassemble());
+        }
+    }
+    text
+}
+
+/// The same as `disassemble` as one JSON object, for tools. Instructions that load a constant
+/// name it in `constant`, and those of the main function carry the `line` and `column` of
+/// their statement when there is a source map.
+pub fn disassemble_json(byte_code: &ByteCode, source_map: Option<&SourceMap>) -> String {
+    let mut json = String::from("{");
+    if let Some(source_map) = source_map {
+        json.push_str("\"file\":");
+        stringify_string(&source_map.file, &mut json);
+        json.push(',');
+    }
+
+    json.push_str("\"constants\":[");
+    for (idx, constant) in byte_code.constants.iter().enumerate() {
+        if idx > 0 {
+            json.push(',');
+        }
+        json.push_str(&format!(
+            "{{\"index\":{idx},\"type\":\"{}\"",
+            Value::from(constant).as_type()
+        ));
+        match constant {
+            Constant::Int(int) => json.push_str(&format!(",\"value\":{int}")),
+            Constant::String(string) => {
+                json.push_str(",\"value\":");
+                stringify_string(string, &mut json);
+            }
+            _ => {}
+        }
+        json.push('}');
+    }
+
+    json.push_str("],\"functions\":[");
+    json.push_str("{\"constant\":null,\"parameters\":0,\"locals\":0,\"instructions\":");
+    instructions_json(&byte_code.instructions, source_map, &mut json);
+    json.push('}');
+    for (idx, constant) in byte_code.constants.iter().enumerate() {
+        if let Constant::CompiledFunction {
+            instructions,
+            num_parameters,
+            num_locals,
+        } = constant
+        {
+            json.push_str(&format!(
+                ",{{\"constant\":{idx},\"parameters\":{num_parameters},\"locals\":{num_locals},\"instructions\":"
+            ));
+            instructions_json(instructions, None, &mut json);
+            json.push('}');
+        }
+    }
+    json.push_str("]}");
+    json
+}
+
+fn instructions_json(
+    instructions: &Instructions,
+    source_map: Option<&SourceMap>,
+    json: &mut String,
+) {
+    json.push('[');
+    for (idx, (offset, op, operands)) in instructions.decode().into_iter().enumerate() {
+        if idx > 0 {
+            json.push(',');
+        }
+        let operands = operands.iter().map(i64::to_string).collect::<Vec<_>>();
+        json.push_str(&format!(
+            "{{\"offset\":{offset},\"op\":\"{}\",\"operands\":[{}]",
+            Definition::from(op).name(),
+            operands.join(",")
+        ));
+        if let OpCode::OpConstant | OpCode::OpClosure = op {
+            json.push_str(&format!(",\"constant\":{}", operands[0]));
+        }
+        if let Some(entry) = source_map.and_then(|source_map| source_map.lookup(offset)) {
+            json.push_str(&format!(
+                ",\"line\":{},\"column\":{}",
+                entry.line, entry.column
+            ));
+        }
+        json.push('}');
+    }
+    json.push(']');
+}
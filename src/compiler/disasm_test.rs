@@ -0,0 +1,48 @@
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+
+use super::disasm::{disassemble, disassemble_json};
+use super::Compiler;
+
+fn compile(input: &str) -> Compiler {
+    let mut compiler = Compiler::new();
+    if let Err(err) = compiler.compile_program(Parser::new(Lexer::from(input)).parse_program()) {
+        panic!("compiler error: {err}");
+    }
+    compiler
+}
+
+#[test]
+fn test_disassemble() {
+    let mut compiler = compile("let f = fn(x) { x };\nf(\"a\")");
+    assert_eq!(
+        disassemble(&compiler.bytecode()),
+        "0000 OpClosure 0 0\n0004 OpSetGlobal 0\n0007 OpGetGlobal 0\n0010 OpConstant 1\n\
+         0013 OpCall 1\n0015 OpPop\n\nconstant 0: 1 parameters, 1 locals\n\
+         0000 OpGetLocal 0\n0002 OpReturnValue\n"
+    );
+}
+
+#[test]
+fn test_disassemble_json() {
+    let source = "let f = fn(x) { x };\nf(\"a\")";
+    let mut compiler = compile(source);
+    let source_map = compiler.source_map("f.mk", source);
+    assert_eq!(
+        disassemble_json(&compiler.bytecode(), Some(&source_map)),
+        concat!(
+            r#"{"file":"f.mk","constants":[{"index":0,"type":"COMPILED_FUNCTION_OBJ"},"#,
+            r#"{"index":1,"type":"STRING","value":"a"}],"functions":["#,
+            r#"{"constant":null,"parameters":0,"locals":0,"instructions":["#,
+            r#"{"offset":0,"op":"OpClosure","operands":[0,0],"constant":0,"line":1,"column":1},"#,
+            r#"{"offset":4,"op":"OpSetGlobal","operands":[0],"line":1,"column":1},"#,
+            r#"{"offset":7,"op":"OpGetGlobal","operands":[0],"line":2,"column":1},"#,
+            r#"{"offset":10,"op":"OpConstant","operands":[1],"constant":1,"line":2,"column":1},"#,
+            r#"{"offset":13,"op":"OpCall","operands":[1],"line":2,"column":1},"#,
+            r#"{"offset":15,"op":"OpPop","operands":[],"line":2,"column":1}]},"#,
+            r#"{"constant":0,"parameters":1,"locals":1,"instructions":["#,
+            r#"{"offset":0,"op":"OpGetLocal","operands":[0]},"#,
+            r#"{"offset":2,"op":"OpReturnValue","operands":[]}]}]}"#,
+        )
+    );
+}
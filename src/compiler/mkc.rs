@@ -0,0 +1,430 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+use crate::code::{read_operands, Definition, Instructions, OpCode};
+use crate::eval::builtin::BUILTINS;
+use crate::eval::value::{HashKey, Value};
+
+use super::constant::Constant;
+use super::interner::Interner;
+use super::source_map::SourceMap;
+use super::ByteCode;
+
+pub const MAGIC: &[u8; 4] = b"\x7fMKC";
+pub const VERSION: u8 = 1;
+
+const FLAG_SOURCE_MAP: u8 = 0b0000_0001;
+
+const TAG_INT: u8 = 0;
+const TAG_STRING: u8 = 1;
+const TAG_COMPILED_FUNCTION: u8 = 2;
+const TAG_BOOL: u8 = 3;
+const TAG_NULL: u8 = 4;
+const TAG_ARRAY: u8 = 5;
+const TAG_HASH: u8 = 6;
+const TAG_CLOSURE: u8 = 7;
+const TAG_BUILTIN: u8 = 8;
+
+#[derive(Debug)]
+pub struct MkcError {
+    msg: String,
+}
+
+impl MkcError {
+    pub fn new(msg: impl Into<String>) -> Self {
+        MkcError { msg: msg.into() }
+    }
+}
+
+impl Display for MkcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+pub fn is_mkc(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+pub fn encode(byte_code: &ByteCode, source_map: Option<&SourceMap>) -> Result<Vec<u8>, MkcError> {
+    let mut writer = Writer::default();
+    writer.bytes(MAGIC);
+    writer.u8(VERSION);
+    writer.u8(if source_map.is_some() {
+        FLAG_SOURCE_MAP
+    } else {
+        0
+    });
+
+    writer.instructions(&byte_code.instructions);
+    writer.u32(byte_code.constants.len());
+    for constant in byte_code.constants.iter() {
+        writer.value(&Value::from(constant))?;
+    }
+
+    if let Some(source_map) = source_map {
+        writer.string(&source_map.file);
+        writer.u64(source_map.source_hash);
+        writer.u32(source_map.entries.len());
+        for entry in &source_map.entries {
+            writer.u32(entry.offset);
+            writer.u32(entry.line);
+            writer.u32(entry.column);
+        }
+    }
+
+    Ok(writer.buffer)
+}
+
+pub fn decode(bytes: &[u8]) -> Result<(ByteCode, Option<SourceMap>), MkcError> {
+    let mut reader = Reader::new(bytes);
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(MkcError::new("not a monkey bytecode file"));
+    }
+    let version = reader.u8()?;
+    if version != VERSION {
+        return Err(MkcError::new(format!(
+            "unsupported bytecode version: {version}, expected {VERSION}"
+        )));
+    }
+    let flags = reader.u8()?;
+
+    let instructions = reader.instructions()?;
+    let constants = (0..reader.u32()?)
+        .map(|_| Constant::try_from(&reader.value()?).map_err(MkcError::new))
+        .collect::<Result<_, _>>()?;
+
+    let source_map = if flags & FLAG_SOURCE_MAP != 0 {
+        let mut source_map = SourceMap {
+            file: reader.string()?,
+            source_hash: reader.u64()?,
+            entries: vec![],
+        };
+        for _ in 0..reader.u32()? {
+            let offset = reader.u32()?;
+            let line = reader.u32()?;
+            let column = reader.u32()?;
+            source_map.add(offset, line, column);
+        }
+        Some(source_map)
+    } else {
+        None
+    };
+
+    if !reader.is_empty() {
+        return Err(MkcError::new("trailing bytes after the bytecode"));
+    }
+
+    let byte_code = ByteCode {
+        instructions: Arc::new(instructions),
+        constants,
+        interner: Arc::new(Mutex::new(reader.interner)),
+        symbol_table: None,
+    };
+    verify(&byte_code)?;
+    Ok((byte_code, source_map))
+}
+
+/// Checks what the vm trusts the compiler for, so a damaged or handmade file fails here
+/// instead of panicking the vm: every opcode is known with all of its operands, jumps land on
+/// an instruction, and constants, builtins and locals exist.
+pub fn verify(byte_code: &ByteCode) -> Result<(), MkcError> {
+    verify_instructions(&byte_code.instructions, &byte_code.constants, 0)?;
+    for constant in byte_code.constants.iter() {
+        if let Constant::CompiledFunction {
+            instructions,
+            num_locals,
+            ..
+        } = constant
+        {
+            verify_instructions(instructions, &byte_code.constants, *num_locals)?;
+        }
+    }
+    Ok(())
+}
+
+fn verify_instructions(
+    instructions: &Instructions,
+    constants: &[Constant],
+    num_locals: usize,
+) -> Result<(), MkcError> {
+    let mut starts = vec![];
+    let mut jumps = vec![];
+    let mut offset = 0;
+    while offset < instructions.len() {
+        let op = OpCode::try_from(instructions[offset]).map_err(|()| {
+            MkcError::new(format!(
+                "unknown opcode {} at {offset}",
+                instructions[offset]
+            ))
+        })?;
+        let definition = Definition::from(op);
+        let width = definition
+            .operand_widths()
+            .iter()
+            .map(|&width| usize::from(width))
+            .sum::<usize>();
+        if offset + 1 + width > instructions.len() {
+            return Err(MkcError::new(format!("truncated instruction at {offset}")));
+        }
+        let (operands, read) = read_operands(&definition, &instructions[offset + 1..]);
+        let operand = operands.first().map_or(0, |&operand| operand as usize);
+        match op {
+            OpCode::OpJump | OpCode::OpJumpNotTruthy => jumps.push((offset, operand)),
+            OpCode::OpConstant if operand >= constants.len() => {
+                return Err(MkcError::new(format!(
+                    "missing constant {operand} at {offset}"
+                )));
+            }
+            OpCode::OpClosure
+                if !matches!(
+                    constants.get(operand),
+                    Some(Constant::CompiledFunction { .. })
+                ) =>
+            {
+                return Err(MkcError::new(format!(
+                    "constant {operand} at {offset} is not a function"
+                )));
+            }
+            OpCode::OpGetBuiltin if operand >= BUILTINS.len() => {
+                return Err(MkcError::new(format!("unknown builtin: {operand}")));
+            }
+            OpCode::OpGetLocal | OpCode::OpSetLocal if operand >= num_locals => {
+                return Err(MkcError::new(format!(
+                    "missing local {operand} at {offset}"
+                )));
+            }
+            _ => {}
+        }
+        starts.push(offset);
+        offset += 1 + read;
+    }
+    for (offset, target) in jumps {
+        if target != instructions.len() && starts.binary_search(&target).is_err() {
+            return Err(MkcError::new(format!(
+                "jump at {offset} to {target} is not an instruction"
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+pub(crate) struct Writer {
+    pub buffer: Vec<u8>,
+}
+
+impl Writer {
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.buffer.push(value);
+    }
+
+    pub fn u32(&mut self, value: usize) {
+        self.bytes(&(value as u32).to_be_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_be_bytes());
+    }
+
+    pub fn i64(&mut self, value: i64) {
+        self.bytes(&value.to_be_bytes());
+    }
+
+    pub fn string(&mut self, value: &str) {
+        self.u32(value.len());
+        self.bytes(value.as_bytes());
+    }
+
+    pub fn instructions(&mut self, instructions: &Instructions) {
+        self.u32(instructions.len());
+        self.bytes(&instructions.0);
+    }
+
+    pub fn value(&mut self, value: &Value) -> Result<(), MkcError> {
+        match value {
+            Value::Int(int) => {
+                self.u8(TAG_INT);
+                self.i64(*int);
+            }
+            Value::String(string) => {
+                self.u8(TAG_STRING);
+                self.string(string);
+            }
+            Value::CompiledFunction {
+                instructions,
+                num_locals,
+                num_parameters,
+            } => {
+                self.u8(TAG_COMPILED_FUNCTION);
+                self.u32(*num_locals);
+                self.u32(*num_parameters);
+                self.instructions(instructions);
+            }
+            Value::Bool(bool) => {
+                self.u8(TAG_BOOL);
+                self.u8(*bool as u8);
+            }
+            Value::Null => self.u8(TAG_NULL),
+            Value::Array(values) => {
+                self.u8(TAG_ARRAY);
+                self.u32(values.len());
+                for value in values.iter() {
+                    self.value(value)?;
+                }
+            }
+            Value::Hash(hash) => {
+                self.u8(TAG_HASH);
+                self.u32(hash.len());
+                for (key, value) in hash.iter() {
+                    self.value(&key.clone().into())?;
+                    self.value(value)?;
+                }
+            }
+            Value::Closure { fun, free } => {
+                self.u8(TAG_CLOSURE);
+                self.value(fun)?;
+                self.u32(free.len());
+                for value in free {
+                    self.value(value)?;
+                }
+            }
+            Value::Builtin(builtin) => {
+                let idx = BUILTINS
+                    .index_of(builtin)
+                    .ok_or(MkcError::new("can't serialize an unknown builtin"))?;
+                self.u8(TAG_BUILTIN);
+                self.u8(idx as u8);
+            }
+            value => {
+                return Err(MkcError::new(format!(
+                    "can't serialize value of type {}",
+                    value.as_type()
+                )))
+            }
+        };
+        Ok(())
+    }
+}
+
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    pub interner: Interner,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Reader {
+            bytes,
+            position: 0,
+            interner: Interner::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.position == self.bytes.len()
+    }
+
+    /// The bytes not read yet, all of them count as read after.
+    pub fn rest(&mut self) -> &'a [u8] {
+        let rest = &self.bytes[self.position..];
+        self.position = self.bytes.len();
+        rest
+    }
+
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8], MkcError> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + len)
+            .ok_or(MkcError::new("unexpected end of bytecode"))?;
+        self.position += len;
+        Ok(bytes)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, MkcError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn u32(&mut self) -> Result<usize, MkcError> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as usize)
+    }
+
+    pub fn u64(&mut self) -> Result<u64, MkcError> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    pub fn i64(&mut self) -> Result<i64, MkcError> {
+        Ok(i64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    pub fn string(&mut self) -> Result<String, MkcError> {
+        let len = self.u32()?;
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|_| MkcError::new("invalid utf-8 in string"))
+    }
+
+    pub fn instructions(&mut self) -> Result<Instructions, MkcError> {
+        let len = self.u32()?;
+        Ok(Instructions(self.take(len)?.to_vec()))
+    }
+
+    pub fn value(&mut self) -> Result<Value, MkcError> {
+        Ok(match self.u8()? {
+            TAG_INT => Value::Int(self.i64()?),
+            TAG_STRING => {
+                let string = self.string()?;
+                Value::String(self.interner.intern(&string))
+            }
+            TAG_COMPILED_FUNCTION => {
+                let num_locals = self.u32()?;
+                let num_parameters = self.u32()?;
+                Value::CompiledFunction {
+                    instructions: self.instructions()?.into(),
+                    num_locals,
+                    num_parameters,
+                }
+            }
+            TAG_BOOL => Value::Bool(self.u8()? != 0),
+            TAG_NULL => Value::Null,
+            TAG_ARRAY => Value::Array(Rc::new(
+                (0..self.u32()?)
+                    .map(|_| self.value())
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+            TAG_HASH => {
+                let hash = (0..self.u32()?)
+                    .map(|_| {
+                        let key = HashKey::try_from(self.value()?).map_err(MkcError::new)?;
+                        Ok((key, self.value()?))
+                    })
+                    .collect::<Result<HashMap<_, _>, MkcError>>()?;
+                Value::Hash(Rc::new(hash))
+            }
+            TAG_CLOSURE => {
+                let fun = self.value()?;
+                let free = (0..self.u32()?)
+                    .map(|_| self.value())
+                    .collect::<Result<Vec<_>, _>>()?;
+                Value::Closure {
+                    fun: Box::new(fun),
+                    free,
+                }
+            }
+            TAG_BUILTIN => {
+                let idx = self.u8()?;
+                let builtin = BUILTINS
+                    .get(idx as usize)
+                    .ok_or(MkcError::new(format!("unknown builtin: {idx}")))?;
+                Value::Builtin(builtin)
+            }
+            tag => return Err(MkcError::new(format!("unknown value tag: {tag}"))),
+        })
+    }
+}
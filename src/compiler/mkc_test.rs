@@ -0,0 +1,222 @@
+use crate::eval::value::Value;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::vm::Vm;
+
+use std::sync::{Arc, Mutex};
+
+use crate::code::{Instructions, OpCode};
+
+use super::constant::Constant;
+use super::interner::Interner;
+use super::mkc::{decode, encode, is_mkc, verify};
+use super::source_map::{SourceMap, SourceMapEntry};
+use super::{ByteCode, Compiler};
+
+fn compile(input: &str) -> Compiler {
+    let lexer = Lexer::from(input);
+    let mut parser = Parser::new(lexer);
+    let mut compiler = Compiler::new();
+    if let Err(err) = compiler.compile_program(parser.parse_program()) {
+        panic!("compiler error: {err}");
+    }
+    compiler
+}
+
+#[test]
+fn test_round_trip() {
+    let input = r#"let add = fn(a, b) { a + b }; add(1, 2) + len("four")"#;
+    let mut compiler = compile(input);
+    let byte_code = compiler.bytecode();
+
+    let bytes = encode(&byte_code, None).expect("the bytecode should be encoded");
+    assert!(is_mkc(&bytes));
+
+    let (decoded, source_map) = decode(&bytes).expect("the bytecode should be decoded");
+    assert_eq!(decoded.instructions, byte_code.instructions);
+    assert_eq!(decoded.constants, byte_code.constants);
+    assert_eq!(source_map, None);
+
+    let mut vm = Vm::new(decoded);
+    if let Err(err) = vm.run() {
+        panic!("{err}");
+    }
+    assert_eq!(vm.last_popped_element, Some(Value::Int(7)));
+}
+
+#[test]
+fn test_source_map_section() {
+    let input = "let a = 1;\nlet b = 2;\na + b;";
+    let mut compiler = compile(input);
+    let source_map = compiler.source_map("main.mk", input);
+
+    assert_eq!(
+        source_map
+            .entries
+            .iter()
+            .map(|entry| entry.offset)
+            .collect::<Vec<_>>(),
+        vec![0, 4, 10]
+    );
+    assert_eq!(
+        source_map
+            .entries
+            .iter()
+            .map(|entry| entry.line)
+            .collect::<Vec<_>>(),
+        vec![1, 2, 3]
+    );
+    assert!(source_map.matches(input));
+    assert!(!source_map.matches("a + b;"));
+
+    let bytes = encode(&compiler.bytecode(), Some(&source_map)).unwrap();
+    let (_, decoded) = decode(&bytes).unwrap();
+    assert_eq!(decoded, Some(source_map));
+}
+
+#[test]
+fn test_source_map_lookup() {
+    let mut source_map = SourceMap::new("main.mk", "");
+    source_map.add(0, 1, 1);
+    source_map.add(10, 2, 1);
+
+    assert_eq!(
+        source_map.lookup(4),
+        Some(&SourceMapEntry {
+            offset: 0,
+            line: 1,
+            column: 1
+        })
+    );
+    assert_eq!(source_map.lookup(10).map(|entry| entry.line), Some(2));
+    assert_eq!(source_map.lookup(99).map(|entry| entry.line), Some(2));
+}
+
+#[test]
+fn test_decode_errors() {
+    let tests: Vec<(&[u8], &str)> = vec![
+        (b"MKC", "unexpected end of bytecode"),
+        (b"monkey", "not a monkey bytecode file"),
+        (
+            b"\x7fMKC\x09\x00",
+            "unsupported bytecode version: 9, expected 1",
+        ),
+        (
+            b"\x7fMKC\x01\x00\x00\x00\x00\x00\x00\x00\x00\x01\x09",
+            "unknown value tag: 9",
+        ),
+    ];
+
+    for (bytes, expected) in tests {
+        match decode(bytes) {
+            Err(err) => assert_eq!(err.to_string(), expected),
+            Ok(_) => panic!("expected a decode error for {bytes:?}"),
+        }
+    }
+}
+
+fn byte_code(instructions: Instructions, constants: Vec<Value>) -> ByteCode {
+    ByteCode {
+        instructions: Arc::new(instructions),
+        constants: Constant::all(&constants),
+        interner: Arc::new(Mutex::new(Interner::new())),
+        symbol_table: None,
+    }
+}
+
+#[test]
+fn test_verify() {
+    let mut compiler = compile("let f = fn(x) { if (x > 1) { x } else { len([x]) } }; f(2)");
+    assert!(verify(&compiler.bytecode()).is_ok());
+
+    let function = Value::CompiledFunction {
+        instructions: Arc::new(Instructions::from(vec![(OpCode::OpGetLocal, vec![1])])),
+        num_locals: 1,
+        num_parameters: 1,
+    };
+    let tests = [
+        (Instructions(vec![200]), vec![], "unknown opcode 200 at 0"),
+        (
+            Instructions(vec![OpCode::OpConstant as u8, 0]),
+            vec![],
+            "truncated instruction at 0",
+        ),
+        (
+            Instructions::from(vec![(OpCode::OpConstant, vec![1])]),
+            vec![Value::Int(1)],
+            "missing constant 1 at 0",
+        ),
+        (
+            Instructions::from(vec![(OpCode::OpClosure, vec![0, 0])]),
+            vec![Value::Int(1)],
+            "constant 0 at 0 is not a function",
+        ),
+        (
+            Instructions::from(vec![(OpCode::OpGetBuiltin, vec![200])]),
+            vec![],
+            "unknown builtin: 200",
+        ),
+        (
+            Instructions::from(vec![(OpCode::OpNull, vec![]), (OpCode::OpJump, vec![2])]),
+            vec![],
+            "jump at 1 to 2 is not an instruction",
+        ),
+        (
+            Instructions::default(),
+            vec![function],
+            "missing local 1 at 0",
+        ),
+    ];
+    for (instructions, constants, expected) in tests {
+        let byte_code = byte_code(instructions, constants);
+        match verify(&byte_code) {
+            Err(err) => assert_eq!(err.to_string(), expected),
+            Ok(()) => panic!("expected {expected}"),
+        }
+        if let Ok(bytes) = encode(&byte_code, None) {
+            assert!(decode(&bytes).is_err(), "{expected}");
+        }
+    }
+}
+
+#[test]
+fn test_verified_files_fail_at_runtime() {
+    let function = Value::CompiledFunction {
+        instructions: Arc::new(Instructions::from(vec![
+            (OpCode::OpGetFree, vec![3]),
+            (OpCode::OpReturnValue, vec![]),
+        ])),
+        num_locals: 0,
+        num_parameters: 0,
+    };
+    let tests = [
+        (
+            vec![(OpCode::OpArray, vec![5])],
+            "not enough values on the stack",
+        ),
+        (
+            vec![(OpCode::OpHash, vec![4])],
+            "not enough values on the stack",
+        ),
+        (
+            vec![(OpCode::OpCall, vec![0])],
+            "not enough values on the stack",
+        ),
+        (
+            vec![(OpCode::OpClosure, vec![0, 2])],
+            "not enough values on the stack",
+        ),
+        (
+            vec![(OpCode::OpClosure, vec![0, 0]), (OpCode::OpCall, vec![0])],
+            "missing free variable 3",
+        ),
+    ];
+    for (instructions, expected) in tests {
+        let byte_code = byte_code(Instructions::from(instructions), vec![function.clone()]);
+        let (byte_code, _) = decode(&encode(&byte_code, None).unwrap()).unwrap();
+        match Vm::new(byte_code).run() {
+            Err(err) => assert_eq!(err.msg, expected),
+            Ok(_) => panic!("expected {expected}"),
+        }
+    }
+}
@@ -1,3 +1,21 @@
+pub mod cache;
+#[cfg(test)]
+mod cache_test;
+pub mod constant;
+#[cfg(test)]
+mod constant_test;
+pub mod disasm;
+#[cfg(test)]
+mod disasm_test;
+pub mod interner;
+pub mod mkc;
+#[cfg(test)]
+mod mkc_test;
+pub mod report;
+pub mod session;
+#[cfg(test)]
+mod session_test;
+pub mod source_map;
 pub mod symbol_table;
 #[cfg(test)]
 mod symbol_table_test;
@@ -9,22 +27,42 @@ use crate::ast::operator::InfixOperator;
 use crate::ast::program::Program;
 use crate::ast::statement::Statement;
 use crate::code::{concat_instructions, make, Instructions, OpCode};
+use crate::diagnostics::suggest;
 use crate::eval::value::Value;
 use crate::{code, eval::value};
 use std::cell::RefCell;
 use std::fmt::Display;
 use std::rc::Rc;
+use std::sync::{Arc, Mutex, RwLock};
 
+use self::constant::Constant;
+use self::interner::Interner;
+use self::report::Report;
+use self::source_map::{SourceMap, SourceMapEntry};
 use self::symbol_table::{Symbol, SymbolTable};
 
+/// Upper bound on the constants pool, `OpConstant` and `OpClosure` address it with a u16 operand.
+pub const MAX_CONSTANTS: usize = u16::MAX as usize + 1;
+/// Upper bound on the global store, `OpGetGlobal` and `OpSetGlobal` address it with a u16 operand.
+pub const MAX_GLOBALS: usize = u16::MAX as usize + 1;
+
 #[derive(Debug)]
 pub struct CompilerError {
     msg: String,
+    position: Option<(usize, usize)>,
 }
 
 impl CompilerError {
     pub fn new(msg: impl Into<String>) -> Self {
-        CompilerError { msg: msg.into() }
+        CompilerError {
+            msg: msg.into(),
+            position: None,
+        }
+    }
+
+    /// The line and column of the statement that failed to compile.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        self.position
     }
 }
 
@@ -34,11 +72,15 @@ impl Display for CompilerError {
     }
 }
 
+impl std::error::Error for CompilerError {}
+
 pub struct Compiler {
     constants: Rc<RefCell<Vec<value::Value>>>,
-    symbol_table: Rc<RefCell<SymbolTable>>,
+    symbol_table: Arc<RwLock<SymbolTable>>,
+    interner: Arc<Mutex<Interner>>,
     scopes: Vec<CompilationScope>,
     scope_idx: usize,
+    source_positions: Vec<SourceMapEntry>,
 }
 
 #[derive(Default)]
@@ -48,9 +90,14 @@ pub struct CompilationScope {
     previous_instruction: Option<EmittedInstruction>,
 }
 
+/// A compiled program, it is `Send + Sync` so vms on several threads can run one.
+#[derive(Clone)]
 pub struct ByteCode {
-    pub instructions: code::Instructions,
-    pub constants: Vec<value::Value>,
+    pub instructions: Arc<code::Instructions>,
+    pub constants: Arc<[Constant]>,
+    pub interner: Arc<Mutex<Interner>>,
+    /// The globals the instructions were compiled against, `eval` compiles into it.
+    pub symbol_table: Option<Arc<RwLock<SymbolTable>>>,
 }
 
 #[derive(Clone, Debug)]
@@ -67,7 +114,8 @@ impl Compiler {
         let instructions = self.current_instructions();
         let outer_symbol_table = self
             .symbol_table
-            .borrow()
+            .read()
+            .unwrap()
             .outer
             .clone()
             .expect("should exist an outer symbol table");
@@ -79,23 +127,18 @@ impl Compiler {
     pub fn enter_scope(&mut self) {
         let scope = CompilationScope::default();
         let enclosed_symbol_table = SymbolTable::new_with_enclosed(self.symbol_table.clone());
-        self.symbol_table = Rc::new(RefCell::new(enclosed_symbol_table));
+        self.symbol_table = Arc::new(RwLock::new(enclosed_symbol_table));
         self.scopes.push(scope);
         self.scope_idx += 1;
     }
     pub fn new() -> Self {
-        let mut symbol_table = SymbolTable::new();
-        for (idx, builtin) in ["len", "first", "last", "rest", "push", "puts"]
-            .iter()
-            .enumerate()
-        {
-            symbol_table.define_builtin(idx, builtin.to_string());
-        }
         Compiler {
             constants: Rc::new(RefCell::new(vec![])),
-            symbol_table: Rc::new(RefCell::new(symbol_table)),
+            symbol_table: Arc::new(RwLock::new(SymbolTable::new_with_builtins())),
+            interner: Arc::new(Mutex::new(Interner::new())),
             scope_idx: 0,
             scopes: vec![CompilationScope::default()],
+            source_positions: vec![],
         }
     }
 
@@ -112,7 +155,7 @@ impl Compiler {
     }
 
     pub fn new_with_state(
-        symbol_table: Rc<RefCell<SymbolTable>>,
+        symbol_table: Arc<RwLock<SymbolTable>>,
         constatns: Rc<RefCell<Vec<Value>>>,
     ) -> Self {
         let mut compiler = Compiler::new();
@@ -121,14 +164,35 @@ impl Compiler {
         compiler
     }
 
+    /// Interns the string constants into `interner`, so programs compiled one after another
+    /// share their strings.
+    pub fn with_interner(self, interner: Arc<Mutex<Interner>>) -> Self {
+        Compiler { interner, ..self }
+    }
+
     pub fn compile_program(&mut self, program: Program) -> Result<(), CompilerError> {
-        for statement in program.statements {
-            let some = self.compile_statement(statement);
-            some?;
+        for (idx, statement) in program.statements.into_iter().enumerate() {
+            let position = program.positions.get(idx).copied();
+            if let Some((line, column)) = position {
+                let offset = self.current_scope().instructions.len();
+                self.source_positions.push(SourceMapEntry {
+                    offset,
+                    line,
+                    column,
+                });
+            }
+            self.compile_statement(statement)
+                .map_err(|err| CompilerError { position, ..err })?;
         }
         Ok(())
     }
 
+    pub fn source_map(&self, file: impl Into<String>, source: &str) -> SourceMap {
+        let mut source_map = SourceMap::new(file, source);
+        source_map.entries = self.source_positions.clone();
+        source_map
+    }
+
     fn compile_statement(&mut self, statement: Statement) -> Result<(), CompilerError> {
         match statement {
             Statement::Expression(expression) => {
@@ -136,8 +200,23 @@ impl Compiler {
                 self.emit(OpCode::OpPop, &[]);
             }
             Statement::Let { name, value } => {
+                // A new global is defined up front, functions in its value can then call it
+                // through the global, like `let fib = memoize(fn(n) { ... fib(n - 1) ... })`.
+                let global = {
+                    let mut symbol_table = self.symbol_table.write().unwrap();
+                    (symbol_table.outer.is_none() && symbol_table.resolve(&name).is_none())
+                        .then(|| symbol_table.define(&name))
+                };
                 self.compile_expression(value)?;
-                let symbol = self.symbol_table.borrow_mut().define(name);
+                let symbol =
+                    global.unwrap_or_else(|| self.symbol_table.write().unwrap().define(name));
+                if symbol.scope == symbol_table::SymbolScope::GlobalScope
+                    && symbol.index >= MAX_GLOBALS
+                {
+                    return Err(CompilerError::new(format!(
+                        "too many globals: the limit is {MAX_GLOBALS}"
+                    )));
+                }
 
                 let scope = match symbol.scope {
                     symbol_table::SymbolScope::GlobalScope => OpCode::OpSetGlobal,
@@ -161,22 +240,39 @@ impl Compiler {
 
     fn compile_expression(&mut self, expression: Expression) -> Result<(), CompilerError> {
         match expression {
-            Expression::Int(value) => {
-                let int = Value::Int(value);
-                let operands = vec![self.add_constant(int)];
-                self.emit(OpCode::OpConstant, &operands);
-            }
+            Expression::Int(value) => match value {
+                0 => {
+                    self.emit(OpCode::OpConst0, &[]);
+                }
+                1 => {
+                    self.emit(OpCode::OpConst1, &[]);
+                }
+                value if i16::try_from(value).is_ok() => {
+                    self.emit(OpCode::OpPushInt, &[value]);
+                }
+                value => {
+                    let operands = vec![self.add_constant(Value::Int(value))?];
+                    self.emit(OpCode::OpConstant, &operands);
+                }
+            },
             Expression::Identifier(name) => {
-                let symbol = self.symbol_table.borrow_mut().resolve(&name);
+                let symbol = self.symbol_table.write().unwrap().resolve(&name);
                 if let Some(symbol) = symbol {
                     self.load_symbol(symbol);
                 } else {
-                    return Err(CompilerError::new(format!("undefined variable: {}", name)));
+                    let names = self.symbol_table.read().unwrap().names();
+                    let msg = match suggest(&name, names.iter().map(String::as_str)) {
+                        Some(suggestion) => {
+                            format!("undefined variable: {name}, did you mean `{suggestion}`?")
+                        }
+                        None => format!("undefined variable: {}", name),
+                    };
+                    return Err(CompilerError::new(msg));
                 };
             }
             Expression::String(value) => {
-                let string = Value::String(value);
-                let operands = vec![self.add_constant(string)];
+                let string = Value::String(self.interner.lock().unwrap().intern(&value));
+                let operands = vec![self.add_constant(string)?];
                 self.emit(OpCode::OpConstant, &operands);
             }
             Expression::Prefix { rhs, operator } => {
@@ -210,10 +306,15 @@ impl Compiler {
                     InfixOperator::Sub => self.emit(OpCode::OpSub, &[]),
                     InfixOperator::Mul => self.emit(OpCode::OpMul, &[]),
                     InfixOperator::Div => self.emit(OpCode::OpDiv, &[]),
+                    InfixOperator::Modulo => self.emit(OpCode::OpMod, &[]),
                     InfixOperator::Equal => self.emit(OpCode::OpEqual, &[]),
                     InfixOperator::NotEqual => self.emit(OpCode::OpNotEqual, &[]),
                     InfixOperator::GreaterThan => self.emit(OpCode::OpGreatherThan, &[]),
-                    _ => unreachable!(),
+                    operator => {
+                        return Err(CompilerError::new(format!(
+                            "unsupported operator: {operator}"
+                        )))
+                    }
                 };
             }
             Expression::If {
@@ -223,7 +324,7 @@ impl Compiler {
             } => {
                 self.compile_expression(*condition)?;
                 let jump_not_truthy_pos = self.emit(OpCode::OpJumpNotTruthy, &[9999]);
-                self.compile_statement(Statement::Block(consequence))?;
+                self.compile_statement(Statement::Block(consequence.to_vec()))?;
                 if self.last_instruction_is(OpCode::OpPop) {
                     self.remove_last_pop();
                 }
@@ -233,7 +334,7 @@ impl Compiler {
                 self.change_operand(jump_not_truthy_pos, &[after_consequence_pos as i64]);
 
                 if let Some(alternative) = alternative {
-                    self.compile_statement(Statement::Block(alternative))?;
+                    self.compile_statement(Statement::Block(alternative.to_vec()))?;
                     if self.last_instruction_is(OpCode::OpPop) {
                         self.remove_last_pop();
                     }
@@ -250,23 +351,23 @@ impl Compiler {
             } => {
                 self.enter_scope();
                 if !name.is_empty() {
-                    self.symbol_table.borrow_mut().define_function(&name);
+                    self.symbol_table.write().unwrap().define_function(&name);
                 }
                 let num_parameters = parameters.len();
 
                 for parameter in parameters {
-                    self.symbol_table.borrow_mut().define(parameter);
+                    self.symbol_table.write().unwrap().define(parameter);
                 }
 
-                self.compile_statement(Statement::Block(body))?;
+                self.compile_statement(Statement::Block(body.to_vec()))?;
                 if self.last_instruction_is(OpCode::OpPop) {
                     self.replace_last_pop_with_return();
                 }
                 if !self.last_instruction_is(OpCode::OpReturnValue) {
                     self.emit(OpCode::OpReturn, &[]);
                 }
-                let free_symbols = self.symbol_table.clone().borrow().free_symbols.clone();
-                let num_locals = self.symbol_table.borrow_mut().num_definitions;
+                let free_symbols = self.symbol_table.read().unwrap().free_symbols.clone();
+                let num_locals = self.symbol_table.read().unwrap().num_definitions;
                 let instructions = self.leave_scope();
 
                 for symbol in free_symbols.iter() {
@@ -274,11 +375,11 @@ impl Compiler {
                 }
 
                 let compiled_fn = Value::CompiledFunction {
-                    instructions,
+                    instructions: Arc::new(instructions),
                     num_locals,
                     num_parameters,
                 };
-                let operands = self.add_constant(compiled_fn);
+                let operands = self.add_constant(compiled_fn)?;
                 self.emit(OpCode::OpClosure, &[operands, free_symbols.len() as i64]);
             }
             Expression::Call {
@@ -386,9 +487,14 @@ impl Compiler {
         self.current_scope().last_instruction = self.current_scope().previous_instruction.clone();
     }
 
-    fn add_constant(&mut self, value: Value) -> i64 {
+    fn add_constant(&mut self, value: Value) -> Result<i64, CompilerError> {
+        if self.constants.borrow().len() >= MAX_CONSTANTS {
+            return Err(CompilerError::new(format!(
+                "too many constants: the limit is {MAX_CONSTANTS}"
+            )));
+        }
         self.constants.borrow_mut().push(value);
-        self.constants.borrow().len() as i64 - 1
+        Ok(self.constants.borrow().len() as i64 - 1)
     }
 
     fn emit(&mut self, op: OpCode, operands: &[i64]) -> usize {
@@ -405,10 +511,19 @@ impl Compiler {
         self.current_scope().last_instruction = last;
     }
 
+    pub fn report(&self) -> Report {
+        Report::new(
+            &self.scopes[self.scope_idx].instructions,
+            &self.constants.borrow(),
+        )
+    }
+
     pub fn bytecode(&mut self) -> ByteCode {
         ByteCode {
-            instructions: self.current_scope().instructions.clone(),
-            constants: self.constants.borrow().clone(),
+            instructions: Arc::new(self.current_scope().instructions.clone()),
+            constants: Constant::all(&self.constants.borrow()),
+            interner: self.interner.clone(),
+            symbol_table: Some(self.symbol_table.clone()),
         }
     }
 }
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::code::{Instructions, OpCode};
+use crate::eval::value::Value;
+
+#[derive(Debug, PartialEq)]
+pub struct FunctionReport {
+    pub name: String,
+    pub size: usize,
+    pub num_locals: usize,
+    pub num_parameters: usize,
+}
+
+#[derive(Debug)]
+pub struct Report {
+    pub opcode_counts: Vec<(OpCode, usize)>,
+    pub functions: Vec<FunctionReport>,
+    pub constants: Vec<Value>,
+}
+
+impl Report {
+    pub fn new(main: &Instructions, constants: &[Value]) -> Self {
+        let mut counts: HashMap<OpCode, usize> = HashMap::new();
+        let mut count = |instructions: &Instructions| {
+            for (_, op, _) in instructions.decode() {
+                *counts.entry(op).or_default() += 1;
+            }
+        };
+
+        count(main);
+        let mut functions = vec![FunctionReport {
+            name: "main".into(),
+            size: main.len(),
+            num_locals: 0,
+            num_parameters: 0,
+        }];
+
+        for (idx, constant) in constants.iter().enumerate() {
+            if let Value::CompiledFunction {
+                instructions,
+                num_locals,
+                num_parameters,
+            } = constant
+            {
+                count(instructions);
+                functions.push(FunctionReport {
+                    name: format!("constant {idx}"),
+                    size: instructions.len(),
+                    num_locals: *num_locals,
+                    num_parameters: *num_parameters,
+                });
+            }
+        }
+
+        let mut opcode_counts = counts.into_iter().collect::<Vec<_>>();
+        opcode_counts
+            .sort_by(|(a_op, a), (b_op, b)| b.cmp(a).then((*a_op as u8).cmp(&(*b_op as u8))));
+
+        Report {
+            opcode_counts,
+            functions,
+            constants: constants.to_vec(),
+        }
+    }
+
+    pub fn total_size(&self) -> usize {
+        self.functions.iter().map(|function| function.size).sum()
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "instructions:")?;
+        for (op, count) in &self.opcode_counts {
+            writeln!(f, "  {:<20} {count}", format!("{op:?}"))?;
+        }
+
+        writeln!(f, "functions ({} bytes):", self.total_size())?;
+        for function in &self.functions {
+            writeln!(
+                f,
+                "  {:<20} {} bytes, {} parameters, {} locals",
+                function.name, function.size, function.num_parameters, function.num_locals
+            )?;
+        }
+
+        writeln!(f, "constants:")?;
+        for (idx, constant) in self.constants.iter().enumerate() {
+            match constant {
+                Value::CompiledFunction { .. } => writeln!(f, "  {idx:04} {}", constant.as_type())?,
+                constant => writeln!(f, "  {idx:04} {} {constant}", constant.as_type())?,
+            }
+        }
+        Ok(())
+    }
+}
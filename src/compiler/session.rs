@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::eval::value::Value;
+
+use super::mkc::{MkcError, Reader, Writer};
+use super::symbol_table::{Symbol, SymbolScope, SymbolTable};
+
+pub const SESSION_MAGIC: &[u8; 4] = b"\x7fMKS";
+pub const SESSION_VERSION: u8 = 1;
+
+#[derive(Debug, PartialEq, Default)]
+pub struct Session {
+    pub symbol_table: SymbolTable,
+    pub constants: Vec<Value>,
+    pub globals: Vec<Value>,
+}
+
+impl Session {
+    pub fn encode(&self) -> Result<Vec<u8>, MkcError> {
+        let mut writer = Writer::default();
+        writer.bytes(SESSION_MAGIC);
+        writer.u8(SESSION_VERSION);
+
+        let mut symbols = self.symbol_table.store.values().collect::<Vec<_>>();
+        symbols.sort_by(|a, b| a.name.cmp(&b.name));
+        writer.u32(self.symbol_table.num_definitions);
+        writer.u32(symbols.len());
+        for symbol in symbols {
+            writer.string(&symbol.name);
+            writer.u8(match symbol.scope {
+                SymbolScope::GlobalScope => 0,
+                SymbolScope::BuiltinScope => 1,
+                _ => {
+                    return Err(MkcError::new(format!(
+                        "can't save the {} symbol {}",
+                        symbol.scope, symbol.name
+                    )))
+                }
+            });
+            writer.u32(symbol.index);
+        }
+
+        for values in [&self.constants, &self.globals] {
+            writer.u32(values.len());
+            for value in values {
+                writer.value(value)?;
+            }
+        }
+
+        Ok(writer.buffer)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, MkcError> {
+        let mut reader = Reader::new(bytes);
+        if reader.take(SESSION_MAGIC.len())? != SESSION_MAGIC {
+            return Err(MkcError::new("not a monkey session file"));
+        }
+        let version = reader.u8()?;
+        if version != SESSION_VERSION {
+            return Err(MkcError::new(format!(
+                "unsupported session version: {version}, expected {SESSION_VERSION}"
+            )));
+        }
+
+        let num_definitions = reader.u32()?;
+        let mut store = HashMap::new();
+        for _ in 0..reader.u32()? {
+            let name = reader.string()?;
+            let scope = match reader.u8()? {
+                0 => SymbolScope::GlobalScope,
+                1 => SymbolScope::BuiltinScope,
+                scope => return Err(MkcError::new(format!("unknown symbol scope: {scope}"))),
+            };
+            let index = reader.u32()?;
+            store.insert(name.clone(), Symbol::new(name, scope, index));
+        }
+        let symbol_table = SymbolTable {
+            store,
+            num_definitions,
+            ..SymbolTable::new()
+        };
+
+        let constants = (0..reader.u32()?)
+            .map(|_| reader.value())
+            .collect::<Result<Vec<_>, _>>()?;
+        let globals = (0..reader.u32()?)
+            .map(|_| reader.value())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if !reader.is_empty() {
+            return Err(MkcError::new("trailing bytes after the session"));
+        }
+
+        Ok(Session {
+            symbol_table,
+            constants,
+            globals,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), MkcError> {
+        std::fs::write(path, self.encode()?).map_err(|err| MkcError::new(err.to_string()))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MkcError> {
+        let bytes = std::fs::read(path).map_err(|err| MkcError::new(err.to_string()))?;
+        Session::decode(&bytes)
+    }
+}
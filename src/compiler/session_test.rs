@@ -0,0 +1,66 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+
+use crate::eval::value::Value;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::vm::Vm;
+
+use super::session::Session;
+use super::symbol_table::SymbolTable;
+use super::Compiler;
+
+fn run(input: &str, session: &Session) -> (Session, Option<Value>) {
+    let symbol_table = Arc::new(RwLock::new(session.symbol_table.clone()));
+    let constants = Rc::new(RefCell::new(session.constants.clone()));
+    let globals = Rc::new(RefCell::new(session.globals.clone()));
+
+    let lexer = Lexer::from(input);
+    let mut parser = Parser::new(lexer);
+    let mut compiler = Compiler::new_with_state(symbol_table.clone(), constants.clone());
+    if let Err(err) = compiler.compile_program(parser.parse_program()) {
+        panic!("compiler error: {err}");
+    }
+    let mut vm = Vm::new_with_global_store(compiler.bytecode(), globals.clone());
+    if let Err(err) = vm.run() {
+        panic!("vm error: {err}");
+    }
+
+    let session = Session {
+        symbol_table: symbol_table.read().unwrap().clone(),
+        constants: constants.borrow().clone(),
+        globals: globals.borrow().clone(),
+    };
+    (session, vm.last_popped_element)
+}
+
+#[test]
+fn test_session_round_trip() {
+    let mut symbol_table = SymbolTable::new();
+    symbol_table.define_builtin(0, "len".to_string());
+    let fresh = Session {
+        symbol_table,
+        ..Session::default()
+    };
+
+    let (session, _) = run(
+        r#"let a = [1, "two", true]; let h = {"k": a}; let l = len; let newAdder = fn(x) { fn(y) { x + y } }; let addTwo = newAdder(2);"#,
+        &fresh,
+    );
+
+    let bytes = session.encode().expect("the session should be encoded");
+    let restored = Session::decode(&bytes).expect("the session should be decoded");
+    assert_eq!(restored, session);
+
+    let (_, result) = run(r#"addTwo(l(h["k"])) + len(a)"#, &restored);
+    assert_eq!(result, Some(Value::Int(8)));
+}
+
+#[test]
+fn test_session_decode_errors() {
+    match Session::decode(b"\x7fMKC\x01") {
+        Err(err) => assert_eq!(err.to_string(), "not a monkey session file"),
+        Ok(_) => panic!("expected a session error"),
+    }
+}
@@ -0,0 +1,49 @@
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceMapEntry {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SourceMap {
+    pub file: String,
+    pub source_hash: u64,
+    pub entries: Vec<SourceMapEntry>,
+}
+
+impl SourceMap {
+    pub fn new(file: impl Into<String>, source: &str) -> Self {
+        SourceMap {
+            file: file.into(),
+            source_hash: hash_source(source),
+            entries: vec![],
+        }
+    }
+
+    pub fn add(&mut self, offset: usize, line: usize, column: usize) {
+        self.entries.push(SourceMapEntry {
+            offset,
+            line,
+            column,
+        });
+    }
+
+    pub fn lookup(&self, offset: usize) -> Option<&SourceMapEntry> {
+        self.entries
+            .iter()
+            .take_while(|entry| entry.offset <= offset)
+            .last()
+    }
+
+    pub fn matches(&self, source: &str) -> bool {
+        self.source_hash == hash_source(source)
+    }
+}
+
+// FNV-1a, the hash has to stay stable across builds because it's written to .mkc files.
+pub fn hash_source(source: &str) -> u64 {
+    source.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -1,7 +1,8 @@
-use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt::Display;
-use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+
+use crate::eval::builtin::BUILTINS;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum SymbolScope {
@@ -41,9 +42,9 @@ impl Symbol {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub struct SymbolTable {
-    pub outer: Option<Rc<RefCell<SymbolTable>>>,
+    pub outer: Option<Arc<RwLock<SymbolTable>>>,
     pub store: HashMap<String, Symbol>,
     pub num_definitions: usize,
     pub free_symbols: Vec<Symbol>,
@@ -60,7 +61,15 @@ impl SymbolTable {
         }
     }
 
-    pub fn new_with_enclosed(outer: Rc<RefCell<SymbolTable>>) -> Self {
+    pub fn new_with_builtins() -> Self {
+        let mut symbol_table = SymbolTable::new();
+        for (idx, builtin) in BUILTINS.iter().enumerate() {
+            symbol_table.define_builtin(idx, builtin.name.to_string());
+        }
+        symbol_table
+    }
+
+    pub fn new_with_enclosed(outer: Arc<RwLock<SymbolTable>>) -> Self {
         let store = HashMap::new();
         SymbolTable {
             outer: Some(outer),
@@ -109,7 +118,7 @@ impl SymbolTable {
             .get(name)
             .cloned()
             .or(self.outer.clone().and_then(|store| {
-                store.borrow_mut().resolve(name).map(|symbol| {
+                store.write().unwrap().resolve(name).map(|symbol| {
                     if symbol.scope == SymbolScope::GlobalScope
                         || symbol.scope == SymbolScope::BuiltinScope
                         || symbol.scope == SymbolScope::FunctionScope
@@ -122,6 +131,15 @@ impl SymbolTable {
             }))
     }
 
+    /// Every name visible from this scope, including the enclosing ones.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = self.store.keys().cloned().collect::<Vec<_>>();
+        if let Some(outer) = &self.outer {
+            names.extend(outer.read().unwrap().names());
+        }
+        names
+    }
+
     pub fn define_function(&mut self, name: &str) -> Option<Symbol> {
         let symbol = Symbol::new(name, SymbolScope::FunctionScope, 0);
         self.store.insert(name.into(), symbol.clone());
@@ -129,6 +147,21 @@ impl SymbolTable {
     }
 }
 
+impl PartialEq for SymbolTable {
+    fn eq(&self, other: &Self) -> bool {
+        let outer = match (&self.outer, &other.outer) {
+            (Some(outer), Some(other)) => {
+                Arc::ptr_eq(outer, other) || *outer.read().unwrap() == *other.read().unwrap()
+            }
+            (outer, other) => outer.is_none() && other.is_none(),
+        };
+        outer
+            && self.store == other.store
+            && self.num_definitions == other.num_definitions
+            && self.free_symbols == other.free_symbols
+    }
+}
+
 impl Default for SymbolTable {
     fn default() -> Self {
         Self::new()
@@ -1,8 +1,9 @@
-use std::cell::RefCell;
 use std::collections::HashMap;
-use std::rc::Rc;
+use std::sync::{Arc, RwLock};
 
 use super::symbol_table::{Symbol, SymbolScope, SymbolTable};
+use crate::eval::builtin::{new_builtins, BUILTINS};
+use crate::eval::value::Value;
 
 #[test]
 fn test_define() {
@@ -33,10 +34,16 @@ fn test_define() {
         ),
     ]);
 
-    let global = Rc::new(RefCell::new(SymbolTable::new()));
+    let global = Arc::new(RwLock::new(SymbolTable::new()));
 
-    assert_eq!(Some(&global.borrow_mut().define("a")), expected.get("a"));
-    assert_eq!(Some(&global.borrow_mut().define("b")), expected.get("b"));
+    assert_eq!(
+        Some(&global.write().unwrap().define("a")),
+        expected.get("a")
+    );
+    assert_eq!(
+        Some(&global.write().unwrap().define("b")),
+        expected.get("b")
+    );
 
     let mut first_local = SymbolTable::new_with_enclosed(global.clone());
 
@@ -76,9 +83,9 @@ fn test_resolve_global() {
 
 #[test]
 fn test_resolve_local() {
-    let global = Rc::new(RefCell::new(SymbolTable::new()));
-    global.borrow_mut().define("a");
-    global.borrow_mut().define("b");
+    let global = Arc::new(RwLock::new(SymbolTable::new()));
+    global.write().unwrap().define("a");
+    global.write().unwrap().define("b");
 
     let mut local = SymbolTable::new_with_enclosed(global.clone());
     local.define("c");
@@ -101,19 +108,19 @@ fn test_resolve_nested_local() {
         table: SymbolTable,
         expected: Vec<Symbol>,
     }
-    let global = Rc::new(RefCell::new(SymbolTable::new()));
-    global.borrow_mut().define("a");
-    global.borrow_mut().define("b");
+    let global = Arc::new(RwLock::new(SymbolTable::new()));
+    global.write().unwrap().define("a");
+    global.write().unwrap().define("b");
 
-    let first_local = Rc::new(RefCell::new(SymbolTable::new_with_enclosed(global.clone())));
-    first_local.borrow_mut().define("c");
-    first_local.borrow_mut().define("d");
+    let first_local = Arc::new(RwLock::new(SymbolTable::new_with_enclosed(global.clone())));
+    first_local.write().unwrap().define("c");
+    first_local.write().unwrap().define("d");
     let mut second_local = SymbolTable::new_with_enclosed(first_local.clone());
     second_local.define("e");
     second_local.define("f");
     let tests = [
         Test {
-            table: first_local.borrow().clone(),
+            table: first_local.read().unwrap().clone(),
             expected: vec![
                 Symbol::new("a", SymbolScope::GlobalScope, 0),
                 Symbol::new("b", SymbolScope::GlobalScope, 1),
@@ -145,9 +152,9 @@ fn test_resolve_nested_local() {
 
 #[test]
 fn test_define_resolve_builtins() {
-    let global = Rc::new(RefCell::new(SymbolTable::new()));
-    let first_local = Rc::new(RefCell::new(SymbolTable::new_with_enclosed(global.clone())));
-    let second_local = Rc::new(RefCell::new(SymbolTable::new_with_enclosed(
+    let global = Arc::new(RwLock::new(SymbolTable::new()));
+    let first_local = Arc::new(RwLock::new(SymbolTable::new_with_enclosed(global.clone())));
+    let second_local = Arc::new(RwLock::new(SymbolTable::new_with_enclosed(
         first_local.clone(),
     )));
 
@@ -159,13 +166,16 @@ fn test_define_resolve_builtins() {
     ];
 
     for (idx, expect) in expected.iter().enumerate() {
-        global.borrow_mut().define_builtin(idx, expect.name.clone());
+        global
+            .write()
+            .unwrap()
+            .define_builtin(idx, expect.name.clone());
     }
 
     for table in [global, first_local, second_local] {
         for symbol in expected.iter() {
             assert_eq!(
-                table.borrow_mut().resolve(&symbol.name),
+                table.write().unwrap().resolve(&symbol.name),
                 Some(symbol.clone())
             );
         }
@@ -175,23 +185,23 @@ fn test_define_resolve_builtins() {
 #[test]
 fn test_resolve_free() {
     struct Test {
-        table: Rc<RefCell<SymbolTable>>,
+        table: Arc<RwLock<SymbolTable>>,
         expected_symbols: Vec<Symbol>,
         expected_free: Vec<Symbol>,
     }
-    let global = Rc::new(RefCell::new(SymbolTable::new()));
-    global.borrow_mut().define("a");
-    global.borrow_mut().define("b");
+    let global = Arc::new(RwLock::new(SymbolTable::new()));
+    global.write().unwrap().define("a");
+    global.write().unwrap().define("b");
 
-    let first_local = Rc::new(RefCell::new(SymbolTable::new_with_enclosed(global.clone())));
-    first_local.borrow_mut().define("c");
-    first_local.borrow_mut().define("d");
+    let first_local = Arc::new(RwLock::new(SymbolTable::new_with_enclosed(global.clone())));
+    first_local.write().unwrap().define("c");
+    first_local.write().unwrap().define("d");
 
-    let second_local = Rc::new(RefCell::new(SymbolTable::new_with_enclosed(
+    let second_local = Arc::new(RwLock::new(SymbolTable::new_with_enclosed(
         first_local.clone(),
     )));
-    second_local.borrow_mut().define("e");
-    second_local.borrow_mut().define("f");
+    second_local.write().unwrap().define("e");
+    second_local.write().unwrap().define("f");
     let tests = vec![
         Test {
             table: first_local,
@@ -224,27 +234,27 @@ fn test_resolve_free() {
         for expected in test.expected_symbols {
             assert_eq!(
                 Some(expected.clone()),
-                test.table.borrow_mut().resolve(&expected.name)
+                test.table.write().unwrap().resolve(&expected.name)
             )
         }
 
-        assert_eq!(test.expected_free, test.table.borrow_mut().free_symbols);
+        assert_eq!(test.expected_free, test.table.write().unwrap().free_symbols);
     }
 }
 
 #[test]
 fn test_resolve_unresolvable_free() {
-    let global = Rc::new(RefCell::new(SymbolTable::new()));
-    global.borrow_mut().define("a");
+    let global = Arc::new(RwLock::new(SymbolTable::new()));
+    global.write().unwrap().define("a");
 
-    let first_local = Rc::new(RefCell::new(SymbolTable::new_with_enclosed(global.clone())));
-    first_local.borrow_mut().define("c");
+    let first_local = Arc::new(RwLock::new(SymbolTable::new_with_enclosed(global.clone())));
+    first_local.write().unwrap().define("c");
 
-    let second_local = Rc::new(RefCell::new(SymbolTable::new_with_enclosed(
+    let second_local = Arc::new(RwLock::new(SymbolTable::new_with_enclosed(
         first_local.clone(),
     )));
-    second_local.borrow_mut().define("e");
-    second_local.borrow_mut().define("f");
+    second_local.write().unwrap().define("e");
+    second_local.write().unwrap().define("f");
     let expected = vec![
         Symbol::new("a", SymbolScope::GlobalScope, 0),
         Symbol::new("c", SymbolScope::FreeScope, 0),
@@ -255,30 +265,71 @@ fn test_resolve_unresolvable_free() {
     for symbol in expected {
         assert_eq!(
             Some(symbol.clone()),
-            second_local.borrow_mut().resolve(&symbol.name)
+            second_local.write().unwrap().resolve(&symbol.name)
         );
     }
     let expected_unresolvable = vec!["b", "d"];
     for name in expected_unresolvable {
-        assert!(second_local.borrow_mut().resolve(name).is_none());
+        assert!(second_local.write().unwrap().resolve(name).is_none());
     }
 }
 
 #[test]
 fn test_define_and_resolve_function_name() {
-    let global = Rc::new(RefCell::new(SymbolTable::new()));
-    global.borrow_mut().define_function("a");
+    let global = Arc::new(RwLock::new(SymbolTable::new()));
+    global.write().unwrap().define_function("a");
 
     let expected = Symbol::new("a", SymbolScope::FunctionScope, 0);
 
-    assert_eq!(global.borrow_mut().resolve(&expected.name), Some(expected));
+    assert_eq!(
+        global.write().unwrap().resolve(&expected.name),
+        Some(expected)
+    );
 }
 
 #[test]
 fn test_shadow_function_name() {
-    let global = Rc::new(RefCell::new(SymbolTable::new()));
-    global.borrow_mut().define_function("a");
-    global.borrow_mut().define("a");
+    let global = Arc::new(RwLock::new(SymbolTable::new()));
+    global.write().unwrap().define_function("a");
+    global.write().unwrap().define("a");
     let expected = Symbol::new("a", SymbolScope::GlobalScope, 0);
-    assert_eq!(global.borrow_mut().resolve(&expected.name), Some(expected));
+    assert_eq!(
+        global.write().unwrap().resolve(&expected.name),
+        Some(expected)
+    );
+}
+
+#[test]
+fn test_new_with_builtins() {
+    let mut global = SymbolTable::new_with_builtins();
+
+    for (idx, name) in ["len", "first", "last", "rest", "push", "puts"]
+        .iter()
+        .enumerate()
+    {
+        assert_eq!(
+            global.resolve(name),
+            Some(Symbol::new(*name, SymbolScope::BuiltinScope, idx))
+        );
+    }
+    assert_eq!(global.num_definitions, 0);
+}
+
+#[test]
+fn test_builtins_match_registry() {
+    let mut global = SymbolTable::new_with_builtins();
+    let env = new_builtins();
+
+    assert_eq!(env.len(), BUILTINS.len());
+    for (idx, builtin) in BUILTINS.iter().enumerate() {
+        assert_eq!(
+            global.resolve(builtin.name),
+            Some(Symbol::new(builtin.name, SymbolScope::BuiltinScope, idx))
+        );
+        assert_eq!(BUILTINS.index_of(builtin), Some(idx));
+        assert_eq!(BUILTINS.lookup(builtin.name), Some(builtin));
+        assert_eq!(env.get(builtin.name), Some(&Value::Builtin(builtin)));
+        assert!(builtin.signature.starts_with(builtin.name));
+        assert!(!builtin.doc.is_empty() && !builtin.example.is_empty());
+    }
 }
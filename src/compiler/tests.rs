@@ -4,7 +4,13 @@ use crate::eval::value::Value;
 use crate::lexer::Lexer;
 use crate::parser::Parser;
 
-use super::Compiler;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+
+use super::constant::Constant;
+use super::symbol_table::SymbolTable;
+use super::{Compiler, MAX_CONSTANTS, MAX_GLOBALS};
 
 struct CompilerTestCase {
     input: String,
@@ -33,7 +39,7 @@ impl CompilerTestCase {
 }
 
 fn parse(input: String) -> Program {
-    let lexer = Lexer::new(input.chars().collect());
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
     parser.parse_program()
 }
@@ -58,70 +64,92 @@ fn run_compiler_test(tests: &[CompilerTestCase]) {
         test.expected_constants
             .iter()
             .zip(byte_code.constants.iter())
-            .for_each(|(expected, value)| {
-                assert_eq!(expected, value, "\nexpected: {}\ngot: {}", expected, value)
+            .for_each(|(expected, constant)| {
+                let value = Value::from(constant);
+                assert_eq!(expected, &value, "\nexpected: {}\ngot: {}", expected, value)
             });
     }
 }
 
 #[test]
 pub fn test_integer_arithmetic() {
+    let empty: Vec<&str> = Vec::new();
     let tests = &[
         CompilerTestCase::new(
             "1 + 2",
-            &[1, 2],
+            &empty,
             &[
-                (OpCode::OpConstant, &[0]),
-                (OpCode::OpConstant, &[1]),
+                (OpCode::OpConst1, &[]),
+                (OpCode::OpPushInt, &[2]),
                 (OpCode::OpAdd, &[]),
                 (OpCode::OpPop, &[]),
             ],
         ),
         CompilerTestCase::new(
             "1; 2",
-            &[1, 2],
+            &empty,
             &[
-                (OpCode::OpConstant, &[0]),
+                (OpCode::OpConst1, &[]),
                 (OpCode::OpPop, &[]),
-                (OpCode::OpConstant, &[1]),
+                (OpCode::OpPushInt, &[2]),
                 (OpCode::OpPop, &[]),
             ],
         ),
         CompilerTestCase::new(
             "1 - 2",
-            &[1, 2],
+            &empty,
             &[
-                (OpCode::OpConstant, &[0]),
-                (OpCode::OpConstant, &[1]),
+                (OpCode::OpConst1, &[]),
+                (OpCode::OpPushInt, &[2]),
                 (OpCode::OpSub, &[]),
                 (OpCode::OpPop, &[]),
             ],
         ),
         CompilerTestCase::new(
             "1 * 2",
-            &[1, 2],
+            &empty,
             &[
-                (OpCode::OpConstant, &[0]),
-                (OpCode::OpConstant, &[1]),
+                (OpCode::OpConst1, &[]),
+                (OpCode::OpPushInt, &[2]),
                 (OpCode::OpMul, &[]),
                 (OpCode::OpPop, &[]),
             ],
         ),
         CompilerTestCase::new(
             "2 / 1",
-            &[2, 1],
+            &empty,
             &[
-                (OpCode::OpConstant, &[0]),
-                (OpCode::OpConstant, &[1]),
+                (OpCode::OpPushInt, &[2]),
+                (OpCode::OpConst1, &[]),
                 (OpCode::OpDiv, &[]),
                 (OpCode::OpPop, &[]),
             ],
         ),
         CompilerTestCase::new(
-            "-1",
-            &[1],
+            "40000 + 2",
+            &[40000],
             &[
                 (OpCode::OpConstant, &[0]),
+                (OpCode::OpPushInt, &[2]),
+                (OpCode::OpAdd, &[]),
+                (OpCode::OpPop, &[]),
+            ],
+        ),
+        CompilerTestCase::new(
+            "5 % 2",
+            &empty,
+            &[
+                (OpCode::OpPushInt, &[5]),
+                (OpCode::OpPushInt, &[2]),
+                (OpCode::OpMod, &[]),
+                (OpCode::OpPop, &[]),
+            ],
+        ),
+        CompilerTestCase::new(
+            "-1",
+            &empty,
+            &[
+                (OpCode::OpConst1, &[]),
                 (OpCode::OpMinus, &[]),
                 (OpCode::OpPop, &[]),
             ],
@@ -146,40 +174,40 @@ pub fn test_boolean_expression() {
         ),
         CompilerTestCase::new(
             "1 > 2",
-            &[1, 2],
+            &empty,
             &[
-                (OpCode::OpConstant, &[0]),
-                (OpCode::OpConstant, &[1]),
+                (OpCode::OpConst1, &[]),
+                (OpCode::OpPushInt, &[2]),
                 (OpCode::OpGreatherThan, &[]),
                 (OpCode::OpPop, &[]),
             ],
         ),
         CompilerTestCase::new(
             "1 < 2",
-            &[2, 1],
+            &empty,
             &[
-                (OpCode::OpConstant, &[0]),
-                (OpCode::OpConstant, &[1]),
+                (OpCode::OpPushInt, &[2]),
+                (OpCode::OpConst1, &[]),
                 (OpCode::OpGreatherThan, &[]),
                 (OpCode::OpPop, &[]),
             ],
         ),
         CompilerTestCase::new(
             "1 == 2",
-            &[1, 2],
+            &empty,
             &[
-                (OpCode::OpConstant, &[0]),
-                (OpCode::OpConstant, &[1]),
+                (OpCode::OpConst1, &[]),
+                (OpCode::OpPushInt, &[2]),
                 (OpCode::OpEqual, &[]),
                 (OpCode::OpPop, &[]),
             ],
         ),
         CompilerTestCase::new(
             "1 != 2",
-            &[1, 2],
+            &empty,
             &[
-                (OpCode::OpConstant, &[0]),
-                (OpCode::OpConstant, &[1]),
+                (OpCode::OpConst1, &[]),
+                (OpCode::OpPushInt, &[2]),
                 (OpCode::OpNotEqual, &[]),
                 (OpCode::OpPop, &[]),
             ],
@@ -219,17 +247,18 @@ pub fn test_boolean_expression() {
 
 #[test]
 pub fn test_conditional() {
+    let empty: Vec<&str> = Vec::new();
     let tests = &[
         CompilerTestCase::new(
             "if (true) { 10 }; 3333;",
-            &[10, 3333],
+            &empty,
             &[
                 // 0000
                 (OpCode::OpTrue, &[]),
                 // 0001
                 (OpCode::OpJumpNotTruthy, &[10]),
                 // 0004
-                (OpCode::OpConstant, &[0]),
+                (OpCode::OpPushInt, &[10]),
                 // 0007
                 (OpCode::OpJump, &[11]),
                 // 0010
@@ -237,29 +266,29 @@ pub fn test_conditional() {
                 // 0011
                 (OpCode::OpPop, &[]),
                 // 0012
-                (OpCode::OpConstant, &[1]),
+                (OpCode::OpPushInt, &[3333]),
                 // 0015
                 (OpCode::OpPop, &[]),
             ],
         ),
         CompilerTestCase::new(
             "if (true) { 10 } else { 20 }; 3333;",
-            &[10, 20, 3333],
+            &empty,
             &[
                 // 0000
                 (OpCode::OpTrue, &[]),
                 // 0001
                 (OpCode::OpJumpNotTruthy, &[10]),
                 // 0004
-                (OpCode::OpConstant, &[0]),
+                (OpCode::OpPushInt, &[10]),
                 // 0007
                 (OpCode::OpJump, &[13]),
                 // 0010
-                (OpCode::OpConstant, &[1]),
+                (OpCode::OpPushInt, &[20]),
                 // 0013
                 (OpCode::OpPop, &[]),
                 // 0014
-                (OpCode::OpConstant, &[2]),
+                (OpCode::OpPushInt, &[3333]),
                 // 0017
                 (OpCode::OpPop, &[]),
             ],
@@ -270,22 +299,23 @@ pub fn test_conditional() {
 
 #[test]
 fn test_global_let_statements() {
+    let empty: Vec<&str> = Vec::new();
     let tests = &[
         CompilerTestCase::new(
             " let one = 1; let two = 2; ",
-            &[1, 2],
+            &empty,
             &[
-                (OpCode::OpConstant, &[0]),
+                (OpCode::OpConst1, &[]),
                 (OpCode::OpSetGlobal, &[0]),
-                (OpCode::OpConstant, &[1]),
+                (OpCode::OpPushInt, &[2]),
                 (OpCode::OpSetGlobal, &[1]),
             ],
         ),
         CompilerTestCase::new(
             " let one = 1; one; ",
-            &[1],
+            &empty,
             &[
-                (OpCode::OpConstant, &[0]),
+                (OpCode::OpConst1, &[]),
                 (OpCode::OpSetGlobal, &[0]),
                 (OpCode::OpGetGlobal, &[0]),
                 (OpCode::OpPop, &[]),
@@ -293,9 +323,9 @@ fn test_global_let_statements() {
         ),
         CompilerTestCase::new(
             " let one = 1; let two = one; two;",
-            &[1],
+            &empty,
             &[
-                (OpCode::OpConstant, &[0]),
+                (OpCode::OpConst1, &[]),
                 (OpCode::OpSetGlobal, &[0]),
                 (OpCode::OpGetGlobal, &[0]),
                 (OpCode::OpSetGlobal, &[1]),
@@ -341,11 +371,11 @@ fn test_array_literals() {
         ),
         CompilerTestCase::new(
             "[[1,2,3]]",
-            &[1, 2, 3],
+            &empty,
             &[
-                (OpCode::OpConstant, &[0]),
-                (OpCode::OpConstant, &[1]),
-                (OpCode::OpConstant, &[2]),
+                (OpCode::OpConst1, &[]),
+                (OpCode::OpPushInt, &[2]),
+                (OpCode::OpPushInt, &[3]),
                 (OpCode::OpArray, &[3]),
                 (OpCode::OpArray, &[1]),
                 (OpCode::OpPop, &[]),
@@ -353,27 +383,27 @@ fn test_array_literals() {
         ),
         CompilerTestCase::new(
             "[1,2,3]",
-            &[1, 2, 3],
+            &empty,
             &[
-                (OpCode::OpConstant, &[0]),
-                (OpCode::OpConstant, &[1]),
-                (OpCode::OpConstant, &[2]),
+                (OpCode::OpConst1, &[]),
+                (OpCode::OpPushInt, &[2]),
+                (OpCode::OpPushInt, &[3]),
                 (OpCode::OpArray, &[3]),
                 (OpCode::OpPop, &[]),
             ],
         ),
         CompilerTestCase::new(
             "[1 + 2, 3 - 4, 5 * 6]",
-            &[1, 2, 3, 4, 5, 6],
+            &empty,
             &[
-                (OpCode::OpConstant, &[0]),
-                (OpCode::OpConstant, &[1]),
+                (OpCode::OpConst1, &[]),
+                (OpCode::OpPushInt, &[2]),
                 (OpCode::OpAdd, &[]),
-                (OpCode::OpConstant, &[2]),
-                (OpCode::OpConstant, &[3]),
+                (OpCode::OpPushInt, &[3]),
+                (OpCode::OpPushInt, &[4]),
                 (OpCode::OpSub, &[]),
-                (OpCode::OpConstant, &[4]),
-                (OpCode::OpConstant, &[5]),
+                (OpCode::OpPushInt, &[5]),
+                (OpCode::OpPushInt, &[6]),
                 (OpCode::OpMul, &[]),
                 (OpCode::OpArray, &[3]),
                 (OpCode::OpPop, &[]),
@@ -395,29 +425,29 @@ fn test_hash_literals() {
         ),
         CompilerTestCase::new(
             "{1:2, 3:4, 5:6}",
-            &[1, 2, 3, 4, 5, 6],
+            &empty,
             &[
-                (OpCode::OpConstant, &[0]),
-                (OpCode::OpConstant, &[1]),
-                (OpCode::OpConstant, &[2]),
-                (OpCode::OpConstant, &[3]),
-                (OpCode::OpConstant, &[4]),
-                (OpCode::OpConstant, &[5]),
+                (OpCode::OpConst1, &[]),
+                (OpCode::OpPushInt, &[2]),
+                (OpCode::OpPushInt, &[3]),
+                (OpCode::OpPushInt, &[4]),
+                (OpCode::OpPushInt, &[5]),
+                (OpCode::OpPushInt, &[6]),
                 (OpCode::OpHash, &[6]),
                 (OpCode::OpPop, &[]),
             ],
         ),
         CompilerTestCase::new(
             "{1:2+3, 4:5*6}",
-            &[1, 2, 3, 4, 5, 6],
+            &empty,
             &[
-                (OpCode::OpConstant, &[0]),
-                (OpCode::OpConstant, &[1]),
-                (OpCode::OpConstant, &[2]),
+                (OpCode::OpConst1, &[]),
+                (OpCode::OpPushInt, &[2]),
+                (OpCode::OpPushInt, &[3]),
                 (OpCode::OpAdd, &[]),
-                (OpCode::OpConstant, &[3]),
-                (OpCode::OpConstant, &[4]),
-                (OpCode::OpConstant, &[5]),
+                (OpCode::OpPushInt, &[4]),
+                (OpCode::OpPushInt, &[5]),
+                (OpCode::OpPushInt, &[6]),
                 (OpCode::OpMul, &[]),
                 (OpCode::OpHash, &[4]),
                 (OpCode::OpPop, &[]),
@@ -430,17 +460,18 @@ fn test_hash_literals() {
 
 #[test]
 fn test_index_expression() {
+    let empty: Vec<&str> = Vec::new();
     let tests = &[
         CompilerTestCase::new(
             "[1,2,3][1+1]",
-            &[1, 2, 3, 1, 1],
+            &empty,
             &[
-                (OpCode::OpConstant, &[0]),
-                (OpCode::OpConstant, &[1]),
-                (OpCode::OpConstant, &[2]),
+                (OpCode::OpConst1, &[]),
+                (OpCode::OpPushInt, &[2]),
+                (OpCode::OpPushInt, &[3]),
                 (OpCode::OpArray, &[3]),
-                (OpCode::OpConstant, &[3]),
-                (OpCode::OpConstant, &[4]),
+                (OpCode::OpConst1, &[]),
+                (OpCode::OpConst1, &[]),
                 (OpCode::OpAdd, &[]),
                 (OpCode::OpIndex, &[]),
                 (OpCode::OpPop, &[]),
@@ -448,13 +479,13 @@ fn test_index_expression() {
         ),
         CompilerTestCase::new(
             "{1: 2}[2 - 1]",
-            &[1, 2, 2, 1],
+            &empty,
             &[
-                (OpCode::OpConstant, &[0]),
-                (OpCode::OpConstant, &[1]),
+                (OpCode::OpConst1, &[]),
+                (OpCode::OpPushInt, &[2]),
                 (OpCode::OpHash, &[2]),
-                (OpCode::OpConstant, &[2]),
-                (OpCode::OpConstant, &[3]),
+                (OpCode::OpPushInt, &[2]),
+                (OpCode::OpConst1, &[]),
                 (OpCode::OpSub, &[]),
                 (OpCode::OpIndex, &[]),
                 (OpCode::OpPop, &[]),
@@ -488,14 +519,15 @@ fn test_compiler_scopes() {
             .is_some_and(|last_instruction| last_instruction.op == OpCode::OpDiv)));
     assert!(compiler
         .symbol_table
-        .borrow()
+        .read()
+        .unwrap()
         .outer
         .clone()
-        .is_some_and(|table| table == global_symbl_table));
+        .is_some_and(|table| Arc::ptr_eq(&table, &global_symbl_table)));
     compiler.leave_scope();
     assert_eq!(0, compiler.scope_idx);
-    assert!(compiler.symbol_table.clone() == global_symbl_table);
-    assert!(compiler.symbol_table.borrow().outer.clone().is_none());
+    assert!(Arc::ptr_eq(&compiler.symbol_table, &global_symbl_table));
+    assert!(compiler.symbol_table.read().unwrap().outer.is_none());
     compiler.emit(OpCode::OpAdd, &[]);
     assert!(compiler
         .scopes
@@ -522,57 +554,48 @@ fn test_functions() {
     let tests = &[
         CompilerTestCase::new(
             "fn() {return 5 + 10}",
-            &[
-                Value::Int(5),
-                Value::Int(10),
-                Value::CompiledFunction {
-                    instructions: Instructions::from(Vec::from([
-                        (OpCode::OpConstant, vec![0]),
-                        (OpCode::OpConstant, vec![1]),
-                        (OpCode::OpAdd, vec![]),
-                        (OpCode::OpReturnValue, vec![]),
-                    ])),
-                    num_locals: 0,
-                    num_parameters: 0,
-                },
-            ],
-            &[(OpCode::OpClosure, &[2, 0]), (OpCode::OpPop, &[])],
+            &[Value::CompiledFunction {
+                instructions: Instructions::from(Vec::from([
+                    (OpCode::OpPushInt, vec![5]),
+                    (OpCode::OpPushInt, vec![10]),
+                    (OpCode::OpAdd, vec![]),
+                    (OpCode::OpReturnValue, vec![]),
+                ]))
+                .into(),
+                num_locals: 0,
+                num_parameters: 0,
+            }],
+            &[(OpCode::OpClosure, &[0, 0]), (OpCode::OpPop, &[])],
         ),
         CompilerTestCase::new(
             "fn() { 5 + 10}",
-            &[
-                Value::Int(5),
-                Value::Int(10),
-                Value::CompiledFunction {
-                    instructions: Instructions::from(Vec::from([
-                        (OpCode::OpConstant, vec![0]),
-                        (OpCode::OpConstant, vec![1]),
-                        (OpCode::OpAdd, vec![]),
-                        (OpCode::OpReturnValue, vec![]),
-                    ])),
-                    num_locals: 0,
-                    num_parameters: 0,
-                },
-            ],
-            &[(OpCode::OpClosure, &[2, 0]), (OpCode::OpPop, &[])],
+            &[Value::CompiledFunction {
+                instructions: Instructions::from(Vec::from([
+                    (OpCode::OpPushInt, vec![5]),
+                    (OpCode::OpPushInt, vec![10]),
+                    (OpCode::OpAdd, vec![]),
+                    (OpCode::OpReturnValue, vec![]),
+                ]))
+                .into(),
+                num_locals: 0,
+                num_parameters: 0,
+            }],
+            &[(OpCode::OpClosure, &[0, 0]), (OpCode::OpPop, &[])],
         ),
         CompilerTestCase::new(
             "fn() { 1; 2 }",
-            &[
-                Value::Int(1),
-                Value::Int(2),
-                Value::CompiledFunction {
-                    instructions: Instructions::from(Vec::from([
-                        (OpCode::OpConstant, vec![0]),
-                        (OpCode::OpPop, vec![]),
-                        (OpCode::OpConstant, vec![1]),
-                        (OpCode::OpReturnValue, vec![]),
-                    ])),
-                    num_locals: 0,
-                    num_parameters: 0,
-                },
-            ],
-            &[(OpCode::OpClosure, &[2, 0]), (OpCode::OpPop, &[])],
+            &[Value::CompiledFunction {
+                instructions: Instructions::from(Vec::from([
+                    (OpCode::OpConst1, vec![]),
+                    (OpCode::OpPop, vec![]),
+                    (OpCode::OpPushInt, vec![2]),
+                    (OpCode::OpReturnValue, vec![]),
+                ]))
+                .into(),
+                num_locals: 0,
+                num_parameters: 0,
+            }],
+            &[(OpCode::OpClosure, &[0, 0]), (OpCode::OpPop, &[])],
         ),
     ];
 
@@ -584,7 +607,7 @@ fn test_functions_without_return_value() {
     let tests = &[CompilerTestCase::new(
         "fn() {}",
         &[Value::CompiledFunction {
-            instructions: Instructions::from(Vec::from([(OpCode::OpReturn, vec![])])),
+            instructions: Instructions::from(Vec::from([(OpCode::OpReturn, vec![])])).into(),
             num_locals: 0,
             num_parameters: 0,
         }],
@@ -599,19 +622,17 @@ fn test_functions_calls() {
     let tests = &[
         CompilerTestCase::new(
             "fn() { 24 }();",
+            &[Value::CompiledFunction {
+                instructions: Instructions::from(Vec::from([
+                    (OpCode::OpPushInt, vec![24]),
+                    (OpCode::OpReturnValue, vec![]),
+                ]))
+                .into(),
+                num_locals: 0,
+                num_parameters: 0,
+            }],
             &[
-                Value::Int(24),
-                Value::CompiledFunction {
-                    instructions: Instructions::from(Vec::from([
-                        (OpCode::OpConstant, vec![0]),
-                        (OpCode::OpReturnValue, vec![]),
-                    ])),
-                    num_locals: 0,
-                    num_parameters: 0,
-                },
-            ],
-            &[
-                (OpCode::OpClosure, &[1, 0]),
+                (OpCode::OpClosure, &[0, 0]),
                 (OpCode::OpCall, &[0]),
                 (OpCode::OpPop, &[]),
             ],
@@ -619,19 +640,17 @@ fn test_functions_calls() {
         CompilerTestCase::new(
             r#"let noArg = fn() { 24 };
 noArg();"#,
+            &[Value::CompiledFunction {
+                instructions: Instructions::from(Vec::from([
+                    (OpCode::OpPushInt, vec![24]),
+                    (OpCode::OpReturnValue, vec![]),
+                ]))
+                .into(),
+                num_locals: 0,
+                num_parameters: 0,
+            }],
             &[
-                Value::Int(24),
-                Value::CompiledFunction {
-                    instructions: Instructions::from(Vec::from([
-                        (OpCode::OpConstant, vec![0]),
-                        (OpCode::OpReturnValue, vec![]),
-                    ])),
-                    num_locals: 0,
-                    num_parameters: 0,
-                },
-            ],
-            &[
-                (OpCode::OpClosure, &[1, 0]),
+                (OpCode::OpClosure, &[0, 0]),
                 (OpCode::OpSetGlobal, &[0]),
                 (OpCode::OpGetGlobal, &[0]),
                 (OpCode::OpCall, &[0]),
@@ -642,22 +661,20 @@ noArg();"#,
             r#"let oneArg = fn(a) { a };
 oneArg(24);
             "#,
-            &[
-                Value::CompiledFunction {
-                    instructions: Instructions::from(vec![
-                        (OpCode::OpGetLocal, vec![0]),
-                        (OpCode::OpReturnValue, vec![]),
-                    ]),
-                    num_locals: 1,
-                    num_parameters: 1,
-                },
-                Value::Int(24),
-            ],
+            &[Value::CompiledFunction {
+                instructions: Instructions::from(vec![
+                    (OpCode::OpGetLocal, vec![0]),
+                    (OpCode::OpReturnValue, vec![]),
+                ])
+                .into(),
+                num_locals: 1,
+                num_parameters: 1,
+            }],
             &[
                 (OpCode::OpClosure, &[0, 0]),
                 (OpCode::OpSetGlobal, &[0]),
                 (OpCode::OpGetGlobal, &[0]),
-                (OpCode::OpConstant, &[1]),
+                (OpCode::OpPushInt, &[24]),
                 (OpCode::OpCall, &[1]),
                 (OpCode::OpPop, &[]),
             ],
@@ -666,30 +683,26 @@ oneArg(24);
             r#"let manyArg = fn(a, b, c) { a; b; c;};
 manyArg(24, 25, 26);
 "#,
-            &[
-                Value::CompiledFunction {
-                    instructions: Instructions::from(vec![
-                        (OpCode::OpGetLocal, vec![0]),
-                        (OpCode::OpPop, vec![]),
-                        (OpCode::OpGetLocal, vec![1]),
-                        (OpCode::OpPop, vec![]),
-                        (OpCode::OpGetLocal, vec![2]),
-                        (OpCode::OpReturnValue, vec![]),
-                    ]),
-                    num_locals: 3,
-                    num_parameters: 3,
-                },
-                Value::Int(24),
-                Value::Int(25),
-                Value::Int(26),
-            ],
+            &[Value::CompiledFunction {
+                instructions: Instructions::from(vec![
+                    (OpCode::OpGetLocal, vec![0]),
+                    (OpCode::OpPop, vec![]),
+                    (OpCode::OpGetLocal, vec![1]),
+                    (OpCode::OpPop, vec![]),
+                    (OpCode::OpGetLocal, vec![2]),
+                    (OpCode::OpReturnValue, vec![]),
+                ])
+                .into(),
+                num_locals: 3,
+                num_parameters: 3,
+            }],
             &[
                 (OpCode::OpClosure, &[0, 0]),
                 (OpCode::OpSetGlobal, &[0]),
                 (OpCode::OpGetGlobal, &[0]),
-                (OpCode::OpConstant, &[1]),
-                (OpCode::OpConstant, &[2]),
-                (OpCode::OpConstant, &[3]),
+                (OpCode::OpPushInt, &[24]),
+                (OpCode::OpPushInt, &[25]),
+                (OpCode::OpPushInt, &[26]),
                 (OpCode::OpCall, &[3]),
                 (OpCode::OpPop, &[]),
             ],
@@ -705,21 +718,19 @@ fn test_let_statements_scopes() {
             r#"let num = 55;
 fn() { num }
 "#,
+            &[Value::CompiledFunction {
+                instructions: Instructions::from(vec![
+                    (OpCode::OpGetGlobal, vec![0]),
+                    (OpCode::OpReturnValue, vec![]),
+                ])
+                .into(),
+                num_locals: 0,
+                num_parameters: 0,
+            }],
             &[
-                Value::Int(55),
-                Value::CompiledFunction {
-                    instructions: Instructions::from(vec![
-                        (OpCode::OpGetGlobal, vec![0]),
-                        (OpCode::OpReturnValue, vec![]),
-                    ]),
-                    num_locals: 0,
-                    num_parameters: 0,
-                },
-            ],
-            &[
-                (OpCode::OpConstant, &[0]),
+                (OpCode::OpPushInt, &[55]),
                 (OpCode::OpSetGlobal, &[0]),
-                (OpCode::OpClosure, &[1, 0]),
+                (OpCode::OpClosure, &[0, 0]),
                 (OpCode::OpPop, &[]),
             ],
         ),
@@ -728,20 +739,18 @@ fn() { num }
 let num = 55;
 num
 }"#,
-            &[
-                Value::Int(55),
-                Value::CompiledFunction {
-                    instructions: Instructions::from(vec![
-                        (OpCode::OpConstant, vec![0]),
-                        (OpCode::OpSetLocal, vec![0]),
-                        (OpCode::OpGetLocal, vec![0]),
-                        (OpCode::OpReturnValue, vec![]),
-                    ]),
-                    num_locals: 1,
-                    num_parameters: 0,
-                },
-            ],
-            &[(OpCode::OpClosure, &[1, 0]), (OpCode::OpPop, &[])],
+            &[Value::CompiledFunction {
+                instructions: Instructions::from(vec![
+                    (OpCode::OpPushInt, vec![55]),
+                    (OpCode::OpSetLocal, vec![0]),
+                    (OpCode::OpGetLocal, vec![0]),
+                    (OpCode::OpReturnValue, vec![]),
+                ])
+                .into(),
+                num_locals: 1,
+                num_parameters: 0,
+            }],
+            &[(OpCode::OpClosure, &[0, 0]), (OpCode::OpPop, &[])],
         ),
         CompilerTestCase::new(
             r#"fn() {
@@ -749,25 +758,22 @@ let a = 55;
 let b = 77;
 a + b
 }"#,
-            &[
-                Value::Int(55),
-                Value::Int(77),
-                Value::CompiledFunction {
-                    instructions: Instructions::from(vec![
-                        (OpCode::OpConstant, vec![0]),
-                        (OpCode::OpSetLocal, vec![0]),
-                        (OpCode::OpConstant, vec![1]),
-                        (OpCode::OpSetLocal, vec![1]),
-                        (OpCode::OpGetLocal, vec![0]),
-                        (OpCode::OpGetLocal, vec![1]),
-                        (OpCode::OpAdd, vec![]),
-                        (OpCode::OpReturnValue, vec![]),
-                    ]),
-                    num_locals: 2,
-                    num_parameters: 0,
-                },
-            ],
-            &[(OpCode::OpClosure, &[2, 0]), (OpCode::OpPop, &[])],
+            &[Value::CompiledFunction {
+                instructions: Instructions::from(vec![
+                    (OpCode::OpPushInt, vec![55]),
+                    (OpCode::OpSetLocal, vec![0]),
+                    (OpCode::OpPushInt, vec![77]),
+                    (OpCode::OpSetLocal, vec![1]),
+                    (OpCode::OpGetLocal, vec![0]),
+                    (OpCode::OpGetLocal, vec![1]),
+                    (OpCode::OpAdd, vec![]),
+                    (OpCode::OpReturnValue, vec![]),
+                ])
+                .into(),
+                num_locals: 2,
+                num_parameters: 0,
+            }],
+            &[(OpCode::OpClosure, &[0, 0]), (OpCode::OpPop, &[])],
         ),
     ];
 
@@ -776,12 +782,13 @@ a + b
 
 #[test]
 fn test_builtin() {
+    let empty: Vec<&str> = Vec::new();
     let tests = &[
         CompilerTestCase::new(
             r#"len([]);
         push([], 1);
         "#,
-            &[Value::Int(1)],
+            &empty,
             &[
                 (OpCode::OpGetBuiltin, &[0]),
                 (OpCode::OpArray, &[0]),
@@ -789,7 +796,7 @@ fn test_builtin() {
                 (OpCode::OpPop, &[]),
                 (OpCode::OpGetBuiltin, &[4]),
                 (OpCode::OpArray, &[0]),
-                (OpCode::OpConstant, &[0]),
+                (OpCode::OpConst1, &[]),
                 (OpCode::OpCall, &[2]),
                 (OpCode::OpPop, &[]),
             ],
@@ -803,7 +810,8 @@ fn test_builtin() {
                     (OpCode::OpArray, vec![0]),
                     (OpCode::OpCall, vec![1]),
                     (OpCode::OpReturnValue, vec![]),
-                ]),
+                ])
+                .into(),
                 num_locals: 0,
                 num_parameters: 0,
             }],
@@ -826,7 +834,7 @@ fn test_closures() {
                         (OpCode::OpGetLocal, vec![0]),
                         (OpCode::OpAdd, vec![]),
                         (OpCode::OpReturnValue, vec![]),
-                    ]),
+                    ]).into(),
                     num_locals: 1,
                     num_parameters: 1,
                 },
@@ -835,7 +843,7 @@ fn test_closures() {
                         (OpCode::OpGetLocal, vec![0]),
                         (OpCode::OpClosure, vec![0, 1]),
                         (OpCode::OpReturnValue, vec![]),
-                    ]),
+                    ]).into(),
                     num_locals: 1,
                     num_parameters: 1,
                 },
@@ -853,7 +861,7 @@ fn test_closures() {
                         (OpCode::OpGetLocal, vec![0]),
                         (OpCode::OpAdd, vec![]),
                         (OpCode::OpReturnValue, vec![]),
-                    ]),
+                    ]).into(),
                     num_locals: 1,
                     num_parameters: 1,
                 },
@@ -863,7 +871,7 @@ fn test_closures() {
                         (OpCode::OpGetLocal, vec![0]),
                         (OpCode::OpClosure, vec![0, 2]),
                         (OpCode::OpReturnValue, vec![]),
-                    ]),
+                    ]).into(),
                     num_locals: 1,
                     num_parameters: 1,
                 },
@@ -872,7 +880,7 @@ fn test_closures() {
                         (OpCode::OpGetLocal, vec![0]),
                         (OpCode::OpClosure, vec![1, 1]),
                         (OpCode::OpReturnValue, vec![]),
-                    ]),
+                    ]).into(),
                     num_locals: 1,
                     num_parameters: 1,
                 },
@@ -880,9 +888,8 @@ fn test_closures() {
             &[(OpCode::OpClosure, &[2, 0]), (OpCode::OpPop, &[])],
         ),
         CompilerTestCase::new("let global = 55; fn() { let a = 66; fn() { let b = 77; fn() { let c = 88; global + a + b + c; } } }",
-            &[Value::Int(55), Value::Int(66), Value::Int(77), Value::Int(88),
-                Value::CompiledFunction { instructions: Instructions::from(vec![
-                (OpCode::OpConstant, vec![3]),
+            &[Value::CompiledFunction { instructions: Instructions::from(vec![
+                (OpCode::OpPushInt, vec![88]),
                 (OpCode::OpSetLocal, vec![0]),
                 (OpCode::OpGetGlobal, vec![0]),
                 (OpCode::OpGetFree, vec![0]),
@@ -892,27 +899,24 @@ fn test_closures() {
                 (OpCode::OpGetLocal, vec![0]),
                 (OpCode::OpAdd, vec![]),
                 (OpCode::OpReturnValue, vec![]),
-                ]), num_locals: 1, num_parameters: 0 },
-                Value::CompiledFunction { instructions: Instructions::from(vec![
-                (OpCode::OpConstant, vec![2]),
+                ]).into(), num_locals: 1, num_parameters: 0 }, Value::CompiledFunction { instructions: Instructions::from(vec![
+                (OpCode::OpPushInt, vec![77]),
                 (OpCode::OpSetLocal, vec![0]),
                 (OpCode::OpGetFree, vec![0]),
                 (OpCode::OpGetLocal, vec![0]),
-                (OpCode::OpClosure, vec![4,2]),
+                (OpCode::OpClosure, vec![0, 2]),
                 (OpCode::OpReturnValue, vec![]),
-                ]), num_locals: 1, num_parameters: 0 },
-                Value::CompiledFunction { instructions: Instructions::from(vec![
-                (OpCode::OpConstant, vec![1]),
+                ]).into(), num_locals: 1, num_parameters: 0 }, Value::CompiledFunction { instructions: Instructions::from(vec![
+                (OpCode::OpPushInt, vec![66]),
                 (OpCode::OpSetLocal, vec![0]),
                 (OpCode::OpGetLocal, vec![0]),
-                (OpCode::OpClosure, vec![5,1]),
+                (OpCode::OpClosure, vec![1, 1]),
                 (OpCode::OpReturnValue, vec![]),
-                ]), num_locals: 1, num_parameters: 0 }
-            ],
+                ]).into(), num_locals: 1, num_parameters: 0 }],
             &[
-                (OpCode::OpConstant, &[0]),
+                (OpCode::OpPushInt, &[55]),
                 (OpCode::OpSetGlobal, &[0]),
-                (OpCode::OpClosure, &[6,0]),
+                (OpCode::OpClosure, &[2, 0]),
                 (OpCode::OpPop, &[]),
             ]
         )
@@ -926,59 +930,49 @@ fn test_recursive_functions() {
     let tests = &[
         CompilerTestCase::new(
             "let countDown = fn(x) { countDown(x - 1); }; countDown(1);",
-            &[
-                Value::Int(1),
-                Value::CompiledFunction {
+            &[Value::CompiledFunction {
                     instructions: Instructions::from(vec![
                         (OpCode::OpCurrentClosure, vec![]),
                         (OpCode::OpGetLocal, vec![0]),
-                        (OpCode::OpConstant, vec![0]),
+                        (OpCode::OpConst1, vec![]),
                         (OpCode::OpSub, vec![]),
                         (OpCode::OpCall, vec![1]),
                         (OpCode::OpReturnValue, vec![]),
-                    ]),
+                    ]).into(),
                     num_locals: 1,
                     num_parameters: 1,
-                },
-                Value::Int(1),
-            ],
+                }],
             &[
-                (OpCode::OpClosure, &[1, 0]),
+                (OpCode::OpClosure, &[0, 0]),
                 (OpCode::OpSetGlobal, &[0]),
                 (OpCode::OpGetGlobal, &[0]),
-                (OpCode::OpConstant, &[2]),
+                (OpCode::OpConst1, &[]),
                 (OpCode::OpCall, &[1]),
                 (OpCode::OpPop, &[]),
-            ],
-        ),
-        CompilerTestCase::new("let wrapper = fn() { let countDown = fn(x) { countDown(x - 1); }; countDown(1); }; wrapper();", &[
-            Value::Int(1),
-            Value::CompiledFunction {
+            ]),
+        CompilerTestCase::new("let wrapper = fn() { let countDown = fn(x) { countDown(x - 1); }; countDown(1); }; wrapper();", &[Value::CompiledFunction {
                 instructions: Instructions::from(vec![
                     (OpCode::OpCurrentClosure, vec![]),
                     (OpCode::OpGetLocal, vec![0]),
-                    (OpCode::OpConstant, vec![0]),
+                    (OpCode::OpConst1, vec![]),
                     (OpCode::OpSub, vec![]),
                     (OpCode::OpCall, vec![1]),
                     (OpCode::OpReturnValue, vec![]),
-                ]),
+                ]).into(),
                 num_locals: 1,
                 num_parameters: 1,
-            },
-            Value::Int(1),
-            Value::CompiledFunction { instructions: Instructions::from(
+            }, Value::CompiledFunction { instructions: Instructions::from(
                 vec![
-                    (OpCode::OpClosure, vec![1, 0]),
+                    (OpCode::OpClosure, vec![0, 0]),
                     (OpCode::OpSetLocal, vec![0]),
                     (OpCode::OpGetLocal, vec![0]),
-                    (OpCode::OpConstant, vec![2]),
+                    (OpCode::OpConst1, vec![]),
                     (OpCode::OpCall, vec![1]),
                     (OpCode::OpReturnValue, vec![]),
                 ]
 
-            ), num_locals: 1, num_parameters: 0 }
-        ], &[
-                (OpCode::OpClosure, &[3,0]),
+            ).into(), num_locals: 1, num_parameters: 0 }], &[
+                (OpCode::OpClosure, &[1, 0]),
                 (OpCode::OpSetGlobal, &[0]),
                 (OpCode::OpGetGlobal, &[0]),
                 (OpCode::OpCall, &[0]),
@@ -988,3 +982,113 @@ fn test_recursive_functions() {
 
     run_compiler_test(tests);
 }
+
+#[test]
+fn test_constants_limit() {
+    let constants = Rc::new(RefCell::new(vec![Value::Null; MAX_CONSTANTS]));
+    let symbol_table = Arc::new(RwLock::new(SymbolTable::new()));
+    let mut compiler = Compiler::new_with_state(symbol_table, constants);
+
+    match compiler.compile_program(parse(r#""monkey""#.to_string())) {
+        Err(err) => assert_eq!(err.to_string(), "too many constants: the limit is 65536"),
+        Ok(_) => panic!("expected a compiler error"),
+    }
+}
+
+#[test]
+fn test_unsupported_operator() {
+    let mut compiler = Compiler::new();
+
+    match compiler.compile_program(parse("1 >= 2".to_string())) {
+        Err(err) => assert_eq!(err.to_string(), "unsupported operator: >="),
+        Ok(_) => panic!("expected a compiler error"),
+    }
+}
+
+#[test]
+fn test_error_position() {
+    let mut compiler = Compiler::new();
+
+    match compiler.compile_program(parse("let x = 1;\n\nx + y;".to_string())) {
+        Err(err) => {
+            assert_eq!(err.to_string(), "undefined variable: y");
+            assert_eq!(err.position(), Some((3, 1)));
+        }
+        Ok(_) => panic!("expected a compiler error"),
+    }
+}
+
+#[test]
+fn test_undefined_variable_suggestion() {
+    let tests = [
+        (
+            "let counter = 1; countr + 1",
+            "undefined variable: countr, did you mean `counter`?",
+        ),
+        ("lenn([1])", "undefined variable: lenn, did you mean `len`?"),
+        (
+            "let f = fn(value) { fn() { valeu } };",
+            "undefined variable: valeu, did you mean `value`?",
+        ),
+        ("let x = 1; y", "undefined variable: y"),
+    ];
+
+    for (input, expected) in tests {
+        match Compiler::new().compile_program(parse(input.to_string())) {
+            Err(err) => assert_eq!(err.to_string(), expected),
+            Ok(_) => panic!("expected a compiler error for {input}"),
+        }
+    }
+}
+
+#[test]
+fn test_globals_limit() {
+    let constants = Rc::new(RefCell::new(vec![]));
+    let mut symbol_table = SymbolTable::new();
+    symbol_table.num_definitions = MAX_GLOBALS;
+    let mut compiler = Compiler::new_with_state(Arc::new(RwLock::new(symbol_table)), constants);
+
+    match compiler.compile_program(parse("let x = true;".to_string())) {
+        Err(err) => assert_eq!(err.to_string(), "too many globals: the limit is 65536"),
+        Ok(_) => panic!("expected a compiler error"),
+    }
+}
+
+#[test]
+fn test_report() {
+    let mut compiler = Compiler::new();
+    if let Err(err) =
+        compiler.compile_program(parse("let f = fn(a) { a + 100000 }; f(100000);".into()))
+    {
+        panic!("Compile program fail: {:?}", err);
+    }
+    let report = compiler.report();
+
+    assert_eq!(report.opcode_counts[0], (OpCode::OpConstant, 2));
+    assert!(report.opcode_counts.contains(&(OpCode::OpClosure, 1)));
+    assert!(report.opcode_counts.contains(&(OpCode::OpReturnValue, 1)));
+    assert_eq!(report.functions.len(), 2);
+    assert_eq!(report.functions[0].name, "main");
+    assert_eq!(report.functions[0].size, 16);
+    assert_eq!(report.functions[1].name, "constant 1");
+    assert_eq!(report.functions[1].size, 7);
+    assert_eq!(report.functions[1].num_parameters, 1);
+    assert_eq!(report.total_size(), 23);
+    assert_eq!(report.constants.len(), 3);
+    assert!(report.to_string().contains("  0002 INTEGER 100000\n"));
+}
+
+#[test]
+fn test_interned_string_constants() {
+    let mut compiler = Compiler::new();
+    if let Err(err) = compiler.compile_program(parse(r#""monkey"; "mon"; "monkey""#.into())) {
+        panic!("Compile program fail: {:?}", err);
+    }
+    let byte_code = compiler.bytecode();
+
+    match (&byte_code.constants[0], &byte_code.constants[2]) {
+        (Constant::String(first), Constant::String(second)) => assert!(Arc::ptr_eq(first, second)),
+        constants => panic!("expected two string constants, got {constants:?}"),
+    }
+    assert_eq!(byte_code.interner.lock().unwrap().len(), 2);
+}
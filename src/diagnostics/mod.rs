@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod tests;
+
+use crate::parser::ParserError;
+
+/// A message pointing at a place in the source, rendered with the offending line
+/// and a `^^^` underline below it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub msg: String,
+    pub line: usize,
+    pub column: usize,
+    /// How many bytes of the source the underline covers.
+    pub len: usize,
+    /// The file the source was read from, shown before the position.
+    pub file: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(msg: impl Into<String>, line: usize, column: usize) -> Self {
+        Diagnostic {
+            msg: msg.into(),
+            line,
+            column,
+            len: 1,
+            file: None,
+        }
+    }
+
+    pub fn with_len(mut self, len: usize) -> Self {
+        self.len = len.max(1);
+        self
+    }
+
+    pub fn with_file(mut self, file: impl Into<String>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+
+    /// `line` and `column` start at 1, a position past the end of the source only prints the message.
+    pub fn render(&self, source: &str) -> String {
+        let mut rendered = format!("{}\n", self.msg);
+        let Some(text) = source.lines().nth(self.line.wrapping_sub(1)) else {
+            return rendered;
+        };
+
+        let gutter = self.line.to_string().len();
+        let file = self
+            .file
+            .as_ref()
+            .map_or(String::new(), |file| format!("{file}:"));
+        rendered.push_str(&format!(
+            "{:gutter$}--> {file}{}:{}\n",
+            "", self.line, self.column
+        ));
+        rendered.push_str(&format!("{:gutter$} |\n", ""));
+        rendered.push_str(&format!("{} | {text}\n", self.line));
+
+        // Tabs stay tabs so the carets line up however wide the terminal draws them.
+        let padding = text
+            .chars()
+            .chain(std::iter::repeat(' '))
+            .take(self.column.saturating_sub(1))
+            .map(|char| if char == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        let mut bytes = 0;
+        let width = text
+            .chars()
+            .skip(self.column.saturating_sub(1))
+            .take_while(|char| {
+                bytes += char.len_utf8();
+                bytes <= self.len
+            })
+            .count();
+        rendered.push_str(&format!(
+            "{:gutter$} | {padding}{}\n",
+            "",
+            "^".repeat(width.max(1))
+        ));
+        rendered
+    }
+}
+
+impl From<&ParserError> for Diagnostic {
+    fn from(err: &ParserError) -> Self {
+        Diagnostic::new(
+            format!("parser error: {}", err.msg),
+            err.span.line,
+            err.span.column,
+        )
+        .with_len(err.span.len())
+    }
+}
+
+/// The candidate closest to `name`, if it is within a third of its length in edits, so
+/// names shorter than three chars get none. Swapping two neighbouring chars is one edit.
+pub fn suggest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let max = name.chars().count() / 3;
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Optimal string alignment distance between `a` and `b`, counted in chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    let mut rows = vec![(0..=b.len()).collect::<Vec<_>>()];
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (rows[i - 1][j] + 1)
+                .min(row[j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+    rows[a.len()][b.len()]
+}
@@ -0,0 +1,108 @@
+use super::*;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+
+#[test]
+fn test_render() {
+    let source = "let x = 1;\nlet y = x +* 2;";
+    let diagnostic = Diagnostic::new("unexpected Asterisk", 2, 12);
+
+    assert_eq!(
+        diagnostic.render(source),
+        "unexpected Asterisk
+ --> 2:12
+  |
+2 | let y = x +* 2;
+  |            ^
+"
+    );
+    assert_eq!(
+        diagnostic.with_len(3).render(source),
+        "unexpected Asterisk
+ --> 2:12
+  |
+2 | let y = x +* 2;
+  |            ^^^
+"
+    );
+    assert_eq!(
+        Diagnostic::new("unexpected Asterisk", 2, 12)
+            .with_file("main.mk")
+            .render(source),
+        "unexpected Asterisk
+ --> main.mk:2:12
+  |
+2 | let y = x +* 2;
+  |            ^
+"
+    );
+}
+
+#[test]
+fn test_render_keeps_tabs_and_wide_gutters() {
+    let source = format!("{}\tlet = 1;", "\n".repeat(9));
+    let diagnostic = Diagnostic::new("bad let", 10, 6);
+
+    assert_eq!(
+        diagnostic.render(&source),
+        "bad let
+  --> 10:6
+   |
+10 | \tlet = 1;
+   | \t    ^
+"
+    );
+}
+
+#[test]
+fn test_render_out_of_range() {
+    assert_eq!(Diagnostic::new("oops", 3, 1).render("1;"), "oops\n");
+    assert_eq!(Diagnostic::new("oops", 0, 1).render("1;"), "oops\n");
+}
+
+#[test]
+fn test_render_parser_error() {
+    let source = "let x foo;";
+    let mut parser = Parser::new(Lexer::from(source));
+    parser.parse_program();
+
+    let rendered = Diagnostic::from(&parser.errors()[0]).render(source);
+    assert_eq!(
+        rendered,
+        "parser error: expected Assign, got Ident: foo instead
+ --> 1:7
+  |
+1 | let x foo;
+  |       ^^^
+"
+    );
+}
+
+#[test]
+fn test_render_underlines_chars() {
+    let source = r#"let s = "äö" x;"#;
+    let diagnostic = Diagnostic::new("bad string", 1, 9).with_len("\"äö\"".len());
+
+    assert_eq!(
+        diagnostic.render(source),
+        r#"bad string
+ --> 1:9
+  |
+1 | let s = "äö" x;
+  |         ^^^^
+"#
+    );
+}
+
+#[test]
+fn test_suggest() {
+    let names = ["let", "return", "fn", "counter", "count"];
+
+    assert_eq!(suggest("lte", names), Some("let"));
+    assert_eq!(suggest("retrun", names), Some("return"));
+    assert_eq!(suggest("counte", names), Some("count"));
+    assert_eq!(suggest("countre", names), Some("counter"));
+    assert_eq!(suggest("fn", names), None);
+    assert_eq!(suggest("x", names), None);
+    assert_eq!(suggest("banana", names), None);
+}
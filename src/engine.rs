@@ -0,0 +1,148 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::compiler::constant::Constant;
+use crate::compiler::interner::Interner;
+use crate::compiler::source_map::SourceMap;
+use crate::compiler::symbol_table::{SymbolScope, SymbolTable};
+use crate::compiler::{ByteCode, Compiler};
+use crate::error::MonkeyError;
+use crate::eval::value::Value;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::vm::{Completion, Vm};
+
+/// Compiles and runs Monkey on the vm for programs that embed the language. What a source
+/// defines stays for the next ones, like in the REPL.
+pub struct Engine {
+    symbol_table: Arc<RwLock<SymbolTable>>,
+    constants: Rc<RefCell<Vec<Value>>>,
+    globals: Rc<RefCell<Vec<Value>>>,
+    interner: Arc<Mutex<Interner>>,
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        Engine {
+            symbol_table: Arc::new(RwLock::new(SymbolTable::new_with_builtins())),
+            constants: Rc::new(RefCell::new(vec![])),
+            globals: Rc::new(RefCell::new(vec![])),
+            interner: Arc::new(Mutex::new(Interner::new())),
+        }
+    }
+
+    /// Compiles against the engine's globals, the names `source` defines are kept for the
+    /// sources compiled after it.
+    pub fn compile(&mut self, source: &str) -> Result<ByteCode, MonkeyError> {
+        self.compile_with_map(source)
+            .map(|(byte_code, _)| byte_code)
+    }
+
+    fn compile_with_map(&mut self, source: &str) -> Result<(ByteCode, SourceMap), MonkeyError> {
+        let mut parser = Parser::new(Lexer::from(source));
+        let program = parser.parse_program();
+        if !parser.errors().is_empty() {
+            return Err(MonkeyError::Parser(parser.errors().to_vec()));
+        }
+
+        let symbol_table = Arc::new(RwLock::new(self.symbol_table.read().unwrap().clone()));
+        let constants = Rc::new(RefCell::new(self.constants.borrow().clone()));
+        let mut compiler = Compiler::new_with_state(symbol_table.clone(), constants.clone())
+            .with_interner(self.interner.clone());
+        compiler.compile_program(program)?;
+        self.symbol_table = symbol_table;
+        self.constants = constants;
+        Ok((compiler.bytecode(), compiler.source_map("", source)))
+    }
+
+    /// Runs bytecode from `compile`, or one decoded from a `.mkc` file. The value is the last
+    /// expression statement's, `Value::Exit` when the program called `exit`.
+    pub fn run_bytecode(&mut self, byte_code: ByteCode) -> Result<Option<Value>, MonkeyError> {
+        let globals = Rc::new(RefCell::new(self.globals.borrow().clone()));
+        let mut vm = Vm::new_with_global_store(byte_code, globals.clone());
+        let value = match vm.run()? {
+            Completion::Finished => vm.last_popped_element,
+            Completion::Exit(code) => Some(Value::Exit(code)),
+        };
+        self.globals = globals;
+        Ok(value)
+    }
+
+    /// `compile` and `run_bytecode`, a source that fails either way leaves nothing defined.
+    /// Runtime errors know the line and column of their statement.
+    pub fn run(&mut self, source: &str) -> Result<Option<Value>, MonkeyError> {
+        let symbol_table = self.symbol_table.clone();
+        let constants = self.constants.clone();
+        let (byte_code, source_map) = self.compile_with_map(source)?;
+        let result = self.run_bytecode(byte_code).map_err(|err| match err {
+            MonkeyError::Vm(err) => MonkeyError::Vm(err.locate(&source_map)),
+            err => err,
+        });
+        if result.is_err() {
+            self.symbol_table = symbol_table;
+            self.constants = constants;
+        }
+        result
+    }
+
+    /// Calls a function a script left, like an event handler, with arguments from the host. It
+    /// runs on a fresh vm that shares the engine's globals and constants.
+    pub fn call(&mut self, function: Value, args: Vec<Value>) -> Result<Value, MonkeyError> {
+        let byte_code = ByteCode {
+            instructions: Arc::default(),
+            constants: Constant::all(&self.constants.borrow()),
+            interner: self.interner.clone(),
+            symbol_table: Some(self.symbol_table.clone()),
+        };
+        let mut vm = Vm::new_with_global_store(byte_code, self.globals.clone());
+        Ok(vm.call(function, args)?)
+    }
+
+    /// Defines a global in the symbol table and seeds its slot, the sources compiled after it
+    /// can read it, like configuration passed in by the host.
+    pub fn set_global(&mut self, name: &str, value: impl Into<Value>) {
+        let mut symbol_table = self.symbol_table.write().unwrap();
+        let symbol = match symbol_table.resolve(name) {
+            Some(symbol) if symbol.scope == SymbolScope::GlobalScope => symbol,
+            _ => symbol_table.define(name),
+        };
+        let mut globals = self.globals.borrow_mut();
+        if globals.len() <= symbol.index {
+            globals.resize(symbol.index + 1, Value::Null);
+        }
+        globals[symbol.index] = value.into();
+    }
+
+    /// The value a run left in a global, scripts can hand results back with `let`. Builtins
+    /// aren't globals.
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        let symbol = self.symbol_table.write().unwrap().resolve(name)?;
+        if symbol.scope != SymbolScope::GlobalScope {
+            return None;
+        }
+        self.globals.borrow().get(symbol.index).cloned()
+    }
+
+    /// Forgets every global.
+    pub fn reset(&mut self) {
+        *self = Engine::new();
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Lexes, parses, compiles and runs `source` on a fresh engine. The value is the last
+/// expression statement's, `null` when there is none.
+pub fn run_source(source: &str) -> Result<Value, MonkeyError> {
+    run_source_with(&mut Engine::new(), source)
+}
+
+/// `run_source` on an engine that keeps what the sources before defined.
+pub fn run_source_with(engine: &mut Engine, source: &str) -> Result<Value, MonkeyError> {
+    Ok(engine.run(source)?.unwrap_or(Value::Null))
+}
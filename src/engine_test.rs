@@ -0,0 +1,147 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::eval::builtin::new_builtins;
+use crate::eval::environment::Environment;
+use crate::eval::value::Value;
+use crate::eval::Eval;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+
+use super::engine::{run_source, run_source_with, Engine};
+
+#[test]
+fn test_engine_state() {
+    let mut engine = Engine::new();
+    assert_eq!(engine.run("let add = fn(a, b) { a + b };").unwrap(), None);
+    assert_eq!(engine.run("add(1, 2)").unwrap(), Some(Value::Int(3)));
+
+    engine.set_global("x", 10);
+    assert_eq!(engine.run("add(x, 5)").unwrap(), Some(Value::Int(15)));
+    assert_eq!(engine.get_global("x"), Some(Value::Int(10)));
+    assert_eq!(engine.get_global("len"), None);
+    assert_eq!(engine.run("exit(2)").unwrap(), Some(Value::Exit(2)));
+
+    assert!(engine.run("let y = 1 + true;").is_err());
+    assert!(engine.run("y").is_err());
+    assert!(engine.run("let z = ;").is_err());
+
+    engine.reset();
+    assert!(engine.run("add(1, 2)").is_err());
+}
+
+#[test]
+fn test_engine_bytecode() {
+    let mut engine = Engine::new();
+    let byte_code = engine.compile("let n = 20; n + 1").unwrap();
+    assert_eq!(engine.get_global("n"), None);
+    assert_eq!(
+        engine.run_bytecode(byte_code).unwrap(),
+        (Some(Value::Int(21)))
+    );
+    assert_eq!(engine.get_global("n"), Some(Value::Int(20)));
+
+    let Err(err) = engine.compile("missing") else {
+        panic!("expected a compiler error");
+    };
+    assert!(
+        err.to_string().contains("undefined variable: missing"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_run_source() {
+    assert_eq!(run_source("let x = 2; x * 21").unwrap(), Value::Int(42));
+    assert_eq!(run_source("let x = 2;").unwrap(), Value::Null);
+    assert!(run_source("x").is_err());
+
+    let mut engine = Engine::new();
+    run_source_with(&mut engine, "let x = 2;").unwrap();
+    assert_eq!(
+        run_source_with(&mut engine, "x + 1").unwrap(),
+        Value::Int(3)
+    );
+}
+
+#[test]
+fn test_set_global() {
+    let config = HashMap::from([("name".to_string(), "monkey"), ("lang".to_string(), "en")]);
+    let mut engine = Engine::new();
+    engine.set_global("config", config.clone());
+    engine.set_global("limit", 3);
+    let source = r#"if (len(config["name"]) > limit) { config["lang"] } else { "" }"#;
+    assert_eq!(
+        run_source_with(&mut engine, source).unwrap(),
+        Value::from("en")
+    );
+
+    engine.set_global("limit", 10);
+    assert_eq!(
+        run_source_with(&mut engine, source).unwrap(),
+        Value::from("")
+    );
+
+    let env = Environment::from(new_builtins());
+    let mut eval = Eval::new(Rc::new(RefCell::new(env)));
+    eval.set_global("config", config);
+    eval.set_global("limit", 3);
+    let program = Parser::new(Lexer::from(source)).parse_program();
+    assert_eq!(eval.eval_program(program).unwrap(), Value::from("en"));
+}
+
+#[test]
+fn test_get_global() {
+    let source = "let double = fn(x) { x * 2 }; let result = [double(1), double(2)]; 0";
+    let mut engine = Engine::new();
+    run_source_with(&mut engine, source).unwrap();
+    let result = engine.get_global("result").map(Vec::<i64>::try_from);
+    assert_eq!(result, Some(Ok(vec![2, 4])));
+    assert_eq!(engine.get_global("missing"), None);
+
+    let env = Environment::from(new_builtins());
+    let mut eval = Eval::new(Rc::new(RefCell::new(env)));
+    let program = Parser::new(Lexer::from(source)).parse_program();
+    eval.eval_program(program).unwrap();
+    let result = eval.get_global("result").map(Vec::<i64>::try_from);
+    assert_eq!(result, Some(Ok(vec![2, 4])));
+    assert_eq!(eval.get_global("len"), None);
+}
+
+#[test]
+fn test_call() {
+    let mut engine = Engine::new();
+    let source = r#"
+        let prefix = "clicked ";
+        let on_click = fn(button, count) { prefix + button + ": " + str(count * 2) };
+        let quit = fn() { exit(3) };
+    "#;
+    run_source_with(&mut engine, source).unwrap();
+
+    let on_click = engine.get_global("on_click").unwrap();
+    let result = engine.call(on_click.clone(), vec!["ok".into(), 21.into()]);
+    assert_eq!(result.unwrap(), Value::from("clicked ok: 42"));
+    assert!(engine.call(on_click, vec![]).is_err());
+
+    let quit = engine.get_global("quit").unwrap();
+    assert_eq!(engine.call(quit, vec![]).unwrap(), Value::Exit(3));
+
+    let len = run_source("len").unwrap();
+    assert_eq!(
+        engine.call(len, vec!["four".into()]).unwrap(),
+        Value::Int(4)
+    );
+    assert!(engine.call(Value::Int(1), vec![]).is_err());
+}
+
+#[test]
+fn test_call_eval() {
+    let mut engine = Engine::new();
+    let source = r#"let base = 10; let handler = fn(expr) { eval("base + " + expr) };"#;
+    run_source_with(&mut engine, source).unwrap();
+
+    let handler = engine.get_global("handler").unwrap();
+    let result = engine.call(handler, vec!["5".into()]);
+    assert_eq!(result.unwrap(), Value::Int(15));
+}
@@ -0,0 +1,126 @@
+use std::fmt::Display;
+
+use crate::compiler::CompilerError;
+use crate::diagnostics::Diagnostic;
+use crate::eval::EvalError;
+use crate::parser::ParserError;
+use crate::vm::VmError;
+
+/// Any error between reading a source and running it, so code embedding Monkey has one type
+/// to use `?` with.
+#[derive(Debug)]
+pub enum MonkeyError {
+    /// Every error the parser recovered from, not only the first.
+    Parser(Vec<ParserError>),
+    Compiler(CompilerError),
+    Eval(EvalError),
+    Vm(VmError),
+}
+
+impl MonkeyError {
+    /// The line and column of the first error, a vm error only has one once it was located.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        match self {
+            MonkeyError::Parser(errors) => {
+                let span = errors.first()?.span;
+                Some((span.line, span.column))
+            }
+            MonkeyError::Compiler(err) => err.position(),
+            MonkeyError::Eval(err) => err.position(),
+            MonkeyError::Vm(err) => err.position(),
+        }
+    }
+
+    /// The errors with the lines of `source` they point at, like the CLI prints them.
+    pub fn render(&self, source: &str) -> String {
+        match self {
+            MonkeyError::Parser(errors) => errors
+                .iter()
+                .map(|err| Diagnostic::from(err).render(source))
+                .collect(),
+            err => match err.position() {
+                Some((line, column)) => Diagnostic::new(err.message(), line, column).render(source),
+                None => format!("{}\n", err.message()),
+            },
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            MonkeyError::Parser(errors) => {
+                let errors = errors
+                    .iter()
+                    .map(|err| format!("parser error: {}", err.msg));
+                errors.collect::<Vec<_>>().join("\n")
+            }
+            MonkeyError::Compiler(err) => format!("Compiler error: {err}"),
+            MonkeyError::Eval(err) => format!("Err: {err}"),
+            MonkeyError::Vm(err) => format!("Executing bytecode error: {err}"),
+        }
+    }
+}
+
+impl Display for MonkeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MonkeyError::Parser(errors) => {
+                for (idx, err) in errors.iter().enumerate() {
+                    if idx > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(
+                        f,
+                        "{}:{}: parser error: {}",
+                        err.span.line, err.span.column, err.msg
+                    )?;
+                }
+                Ok(())
+            }
+            err => match err.position() {
+                Some((line, column)) => write!(f, "{line}:{column}: {}", err.message()),
+                None => write!(f, "{}", err.message()),
+            },
+        }
+    }
+}
+
+impl std::error::Error for MonkeyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MonkeyError::Parser(errors) => errors.first().map(|err| err as _),
+            MonkeyError::Compiler(err) => Some(err),
+            MonkeyError::Eval(err) => Some(err),
+            MonkeyError::Vm(err) => Some(err),
+        }
+    }
+}
+
+impl From<Vec<ParserError>> for MonkeyError {
+    fn from(errors: Vec<ParserError>) -> Self {
+        MonkeyError::Parser(errors)
+    }
+}
+
+impl From<ParserError> for MonkeyError {
+    fn from(err: ParserError) -> Self {
+        MonkeyError::Parser(vec![err])
+    }
+}
+
+impl From<CompilerError> for MonkeyError {
+    fn from(err: CompilerError) -> Self {
+        MonkeyError::Compiler(err)
+    }
+}
+
+impl From<EvalError> for MonkeyError {
+    fn from(err: EvalError) -> Self {
+        MonkeyError::Eval(err)
+    }
+}
+
+impl From<VmError> for MonkeyError {
+    fn from(err: VmError) -> Self {
+        MonkeyError::Vm(err)
+    }
+}
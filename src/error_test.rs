@@ -0,0 +1,58 @@
+use std::error::Error;
+
+use crate::engine::Engine;
+
+use super::error::MonkeyError;
+
+fn run(source: &str) -> MonkeyError {
+    match Engine::new().run(source) {
+        Err(err) => err,
+        Ok(value) => panic!("expected an error from {source:?}, got {value:?}"),
+    }
+}
+
+#[test]
+fn test_monkey_error() {
+    let err = run("let x = ;\nlet = 1;");
+    assert!(matches!(&err, MonkeyError::Parser(errors) if errors.len() == 2));
+    assert_eq!(err.position(), Some((1, 9)));
+    assert_eq!(
+        err.to_string(),
+        "1:9: parser error: i dont now what is this: Semicolon\n\
+         2:5: parser error: expected Token::Ident, got Assign instead"
+    );
+    assert!(err.source().is_some());
+
+    let err = run("1;\nmissing");
+    assert!(matches!(err, MonkeyError::Compiler(_)));
+    assert_eq!(
+        err.to_string(),
+        "2:1: Compiler error: undefined variable: missing"
+    );
+    assert_eq!(
+        err.render("1;\nmissing"),
+        "Compiler error: undefined variable: missing\n --> 2:1\n  |\n2 | missing\n  | ^\n"
+    );
+
+    let source = "let f = fn(x) { x + true };\n\nf(1)";
+    let err = run(source);
+    assert!(matches!(err, MonkeyError::Vm(_)));
+    assert_eq!(err.position(), Some((3, 1)));
+    assert_eq!(
+        err.render(source),
+        "Executing bytecode error: unsupported values for binary operation: true 1\n --> 3:1\n  |\n3 | f(1)\n  | ^\n"
+    );
+}
+
+#[test]
+fn test_question_mark() {
+    fn add(engine: &mut Engine) -> Result<(), MonkeyError> {
+        engine.run("let a = 1;")?;
+        engine.run("a + ")?;
+        Ok(())
+    }
+    assert!(matches!(
+        add(&mut Engine::new()),
+        Err(MonkeyError::Parser(_))
+    ));
+}
@@ -0,0 +1,187 @@
+use std::rc::Rc;
+
+use super::Context;
+use crate::eval::value::Value;
+
+/// Strings are measured in chars (Unicode scalar values), the unit indexing and `slice` use.
+pub fn builtin_len(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    match &args[0] {
+        Value::String(arg) => Ok(Value::Int(arg.chars().count() as i64)),
+        Value::Array(array) => Ok(Value::Int(array.len() as i64)),
+        arg => Err(format!(
+            r#"argument to "len" not supported: got {}"#,
+            arg.as_type()
+        )),
+    }
+}
+
+pub fn builtin_first(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    match &args[0] {
+        Value::Array(array) => match array.first() {
+            Some(value) => Ok(value.clone()),
+            None => Ok(Value::Null),
+        },
+        arg => Err(format!(
+            "argument to 'first' must be ARRAY, got {}",
+            arg.as_type()
+        )),
+    }
+}
+
+pub fn builtin_last(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    match &args[0] {
+        Value::Array(array) => match array.last() {
+            Some(value) => Ok(value.clone()),
+            None => Ok(Value::Null),
+        },
+        arg => Err(format!(
+            "argument to 'last' must be ARRAY, got {}",
+            arg.as_type()
+        )),
+    }
+}
+
+pub fn builtin_rest(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    match &args[0] {
+        Value::Array(array) => {
+            if array.is_empty() {
+                return Ok(Value::Null);
+            }
+            Ok(Value::Array(Rc::new(array[1..].to_vec())))
+        }
+        arg => Err(format!(
+            "argument to 'rest' must be ARRAY, got {}",
+            arg.as_type()
+        )),
+    }
+}
+
+/// `slice(value, start, end)` takes the elements or chars in `start..end`, `end` defaults to
+/// the length and both bounds are clamped to `0..=len`.
+pub fn builtin_slice(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    let bound = |arg: &Value| match arg {
+        Value::Int(int) => Ok(*int),
+        arg => Err(format!(
+            "bounds of 'slice' must be INTEGER, got {}",
+            arg.as_type()
+        )),
+    };
+    let range = |len: usize| -> Result<std::ops::Range<usize>, String> {
+        let clamp = |bound: i64| bound.clamp(0, len as i64) as usize;
+        let start = clamp(bound(&args[1])?);
+        let end = match args.get(2) {
+            Some(end) => clamp(bound(end)?),
+            None => len,
+        };
+        Ok(start..end.max(start))
+    };
+    match &args[0] {
+        Value::Array(array) => Ok(Value::Array(Rc::new(array[range(array.len())?].to_vec()))),
+        Value::String(string) => {
+            let range = range(string.chars().count())?;
+            let slice = string
+                .chars()
+                .skip(range.start)
+                .take(range.len())
+                .collect::<String>();
+            Ok(Value::String(slice.into()))
+        }
+        arg => Err(format!(
+            "argument to 'slice' not supported: got {}",
+            arg.as_type()
+        )),
+    }
+}
+
+pub fn builtin_push(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    match &args[0] {
+        Value::Array(array) => {
+            let mut new_array = array.to_vec();
+            new_array.push(args[1].clone());
+            Ok(Value::Array(Rc::new(new_array)))
+        }
+        arg => Err(format!(
+            "argument to 'push' must be ARRAY, got: {}",
+            arg.as_type(),
+        )),
+    }
+}
+
+pub fn builtin_map(args: Vec<Value>, ctx: &mut dyn Context) -> Result<Value, String> {
+    match &args[0] {
+        Value::Array(array) => Ok(Value::Array(Rc::new(
+            array
+                .iter()
+                .map(|element| ctx.apply(args[1].clone(), vec![element.clone()]))
+                .collect::<Result<Vec<_>, _>>()?,
+        ))),
+        arg => Err(format!(
+            "argument to 'map' must be ARRAY, got {}",
+            arg.as_type()
+        )),
+    }
+}
+
+pub fn builtin_filter(args: Vec<Value>, ctx: &mut dyn Context) -> Result<Value, String> {
+    match &args[0] {
+        Value::Array(array) => {
+            let mut filtered = Vec::new();
+            for element in array.iter() {
+                match ctx.apply(args[1].clone(), vec![element.clone()])? {
+                    Value::Bool(false) | Value::Null => {}
+                    _ => filtered.push(element.clone()),
+                }
+            }
+            Ok(Value::Array(Rc::new(filtered)))
+        }
+        arg => Err(format!(
+            "argument to 'filter' must be ARRAY, got {}",
+            arg.as_type()
+        )),
+    }
+}
+
+pub fn builtin_reduce(args: Vec<Value>, ctx: &mut dyn Context) -> Result<Value, String> {
+    match &args[0] {
+        Value::Array(array) => array.iter().try_fold(args[1].clone(), |acc, element| {
+            ctx.apply(args[2].clone(), vec![acc, element.clone()])
+        }),
+        arg => Err(format!(
+            "argument to 'reduce' must be ARRAY, got {}",
+            arg.as_type()
+        )),
+    }
+}
+
+/// `range(end)`, `range(start, end)` or `range(start, end, step)`, `end` is exclusive.
+pub fn builtin_range(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    let ints = args
+        .iter()
+        .map(|arg| match arg {
+            Value::Int(int) => Ok(*int),
+            arg => Err(format!(
+                "argument to 'range' must be INTEGER, got {}",
+                arg.as_type()
+            )),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (start, end, step) = match ints.as_slice() {
+        [end] => (0, *end, 1),
+        [start, end] => (*start, *end, 1),
+        [start, end, step] => (*start, *end, *step),
+        _ => unreachable!("'range' takes 1 to 3 arguments"),
+    };
+
+    let range = match step.signum() {
+        0 => return Err("'range' step must not be zero".to_string()),
+        1 => (start..end).step_by(step as usize).collect::<Vec<_>>(),
+        _ => (end + 1..=start)
+            .rev()
+            .step_by(step.unsigned_abs() as usize)
+            .collect(),
+    };
+    Ok(Value::Array(Rc::new(
+        range.into_iter().map(Value::Int).collect(),
+    )))
+}
@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use super::Context;
+use crate::eval::value::Value;
+
+pub fn builtin_int(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    match &args[0] {
+        Value::Int(int) => Ok(Value::Int(*int)),
+        Value::Bool(bool) => Ok(Value::Int(*bool as i64)),
+        Value::String(string) => string
+            .trim()
+            .parse()
+            .map(Value::Int)
+            .map_err(|_| format!(r#"could not parse "{string}" as INTEGER"#)),
+        arg => Err(format!(
+            "argument to 'int' not supported: got {}",
+            arg.as_type()
+        )),
+    }
+}
+
+pub fn builtin_str(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    match &args[0] {
+        Value::String(string) => Ok(Value::String(Arc::clone(string))),
+        arg => Ok(Value::String(arg.to_string().into())),
+    }
+}
+
+pub fn builtin_bool(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    match &args[0] {
+        Value::Bool(bool) => Ok(Value::Bool(*bool)),
+        Value::Int(int) => Ok(Value::Bool(*int != 0)),
+        Value::Null => Ok(Value::Bool(false)),
+        Value::String(string) => match string.trim() {
+            "true" => Ok(Value::Bool(true)),
+            "false" => Ok(Value::Bool(false)),
+            _ => Err(format!(r#"could not parse "{string}" as BOOLEAN"#)),
+        },
+        arg => Err(format!(
+            "argument to 'bool' not supported: got {}",
+            arg.as_type()
+        )),
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::Context;
+use crate::eval::value::{HashKey, Value};
+
+fn sorted_entries(hash: &HashMap<HashKey, Value>) -> Vec<(&HashKey, &Value)> {
+    let mut entries = hash.iter().collect::<Vec<_>>();
+    entries.sort_by_key(|(key, _)| *key);
+    entries
+}
+
+pub fn builtin_keys(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    match &args[0] {
+        Value::Hash(hash) => Ok(Value::Array(Rc::new(
+            sorted_entries(hash)
+                .into_iter()
+                .map(|(key, _)| key.clone().into())
+                .collect(),
+        ))),
+        arg => Err(format!(
+            "argument to 'keys' must be HASH, got {}",
+            arg.as_type()
+        )),
+    }
+}
+
+pub fn builtin_values(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    match &args[0] {
+        Value::Hash(hash) => Ok(Value::Array(Rc::new(
+            sorted_entries(hash)
+                .into_iter()
+                .map(|(_, value)| value.clone())
+                .collect(),
+        ))),
+        arg => Err(format!(
+            "argument to 'values' must be HASH, got {}",
+            arg.as_type()
+        )),
+    }
+}
+
+pub fn builtin_delete(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    match &args[0] {
+        Value::Hash(hash) => {
+            let key = HashKey::try_from(args[1].clone())?;
+            let mut new_hash = hash.as_ref().clone();
+            new_hash.remove(&key);
+            Ok(Value::Hash(Rc::new(new_hash)))
+        }
+        arg => Err(format!(
+            "argument to 'delete' must be HASH, got {}",
+            arg.as_type()
+        )),
+    }
+}
+
+pub fn builtin_has_key(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    match &args[0] {
+        Value::Hash(hash) => {
+            let key = HashKey::try_from(args[1].clone())?;
+            Ok(Value::Bool(hash.contains_key(&key)))
+        }
+        arg => Err(format!(
+            "argument to 'has_key' must be HASH, got {}",
+            arg.as_type()
+        )),
+    }
+}
@@ -0,0 +1,88 @@
+use std::fmt::Debug;
+use std::io::{BufRead, Write};
+
+use super::string::format_values;
+use super::Context;
+use crate::eval::value::Value;
+
+/// Where `puts` writes and `input` reads, so embedders can capture or sandbox them.
+pub trait Io: Debug {
+    fn write(&mut self, text: &str) -> Result<(), String>;
+    /// Returns `None` once the input is exhausted.
+    fn read_line(&mut self) -> Result<Option<String>, String>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StdIo {
+    input: bool,
+}
+
+impl Default for StdIo {
+    fn default() -> Self {
+        StdIo { input: true }
+    }
+}
+
+impl StdIo {
+    pub fn new() -> Self {
+        StdIo::default()
+    }
+
+    pub fn without_input() -> Self {
+        StdIo { input: false }
+    }
+}
+
+impl Io for StdIo {
+    fn write(&mut self, text: &str) -> Result<(), String> {
+        let mut stdout = std::io::stdout().lock();
+        stdout
+            .write_all(text.as_bytes())
+            .and_then(|_| stdout.flush())
+            .map_err(|err| err.to_string())
+    }
+
+    fn read_line(&mut self) -> Result<Option<String>, String> {
+        if !self.input {
+            return Err("input is disabled".to_string());
+        }
+        let mut line = String::new();
+        match std::io::stdin().lock().read_line(&mut line) {
+            Ok(0) => Ok(None),
+            Ok(_) => Ok(Some(line)),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+}
+
+pub fn builtin_puts(args: Vec<Value>, ctx: &mut dyn Context) -> Result<Value, String> {
+    for arg in args {
+        ctx.io().write(&format!("{arg}\n"))?;
+    }
+    Ok(Value::Null)
+}
+
+pub fn builtin_printf(args: Vec<Value>, ctx: &mut dyn Context) -> Result<Value, String> {
+    let formatted = format_values("printf", args)?;
+    ctx.io().write(&formatted)?;
+    Ok(Value::Null)
+}
+
+/// Returns the next line without its line ending, or null at the end of the input.
+pub fn builtin_input(args: Vec<Value>, ctx: &mut dyn Context) -> Result<Value, String> {
+    match args.first() {
+        None => {}
+        Some(Value::String(prompt)) => ctx.io().write(prompt)?,
+        Some(arg) => {
+            return Err(format!(
+                "argument to 'input' must be STRING, got {}",
+                arg.as_type()
+            ))
+        }
+    }
+
+    Ok(match ctx.io().read_line()? {
+        Some(line) => Value::String(line.trim_end_matches(['\n', '\r']).into()),
+        None => Value::Null,
+    })
+}
@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::rc::Rc;
+use std::str::Chars;
+
+use super::Context;
+use crate::eval::value::{HashKey, Value};
+
+const MAX_DEPTH: usize = 128;
+
+pub fn builtin_json_parse(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    match &args[0] {
+        Value::String(source) => parse(source),
+        arg => Err(format!(
+            "argument to 'json_parse' must be STRING, got {}",
+            arg.as_type()
+        )),
+    }
+}
+
+/// Writes objects with sorted keys and no whitespace, so equal values give equal strings.
+pub fn builtin_json_stringify(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    let mut json = String::new();
+    stringify(&args[0], &mut json)?;
+    Ok(Value::String(json.into()))
+}
+
+fn stringify(value: &Value, json: &mut String) -> Result<(), String> {
+    match value {
+        Value::Null => json.push_str("null"),
+        Value::Bool(bool) => json.push_str(&bool.to_string()),
+        Value::Int(int) => json.push_str(&int.to_string()),
+        Value::String(string) => stringify_string(string, json),
+        Value::Array(array) => {
+            json.push('[');
+            for (idx, element) in array.iter().enumerate() {
+                if idx > 0 {
+                    json.push(',');
+                }
+                stringify(element, json)?;
+            }
+            json.push(']');
+        }
+        Value::Hash(hash) => {
+            let mut entries = hash
+                .iter()
+                .map(|(key, value)| match key {
+                    HashKey::String(key) => Ok((key, value)),
+                    key => Err(format!(
+                        "json object keys must be STRING, got {}",
+                        Value::from(key.clone()).as_type()
+                    )),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            entries.sort_by_key(|(key, _)| *key);
+
+            json.push('{');
+            for (idx, (key, value)) in entries.into_iter().enumerate() {
+                if idx > 0 {
+                    json.push(',');
+                }
+                stringify_string(key, json);
+                json.push(':');
+                stringify(value, json)?;
+            }
+            json.push('}');
+        }
+        value => return Err(format!("{} can't be converted to json", value.as_type())),
+    }
+    Ok(())
+}
+
+pub fn stringify_string(string: &str, json: &mut String) {
+    json.push('"');
+    for char in string.chars() {
+        match char {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            char if char.is_control() => json.push_str(&format!("\\u{:04x}", char as u32)),
+            char => json.push(char),
+        }
+    }
+    json.push('"');
+}
+
+fn parse(source: &str) -> Result<Value, String> {
+    let mut reader = Reader {
+        chars: source.chars().peekable(),
+        depth: 0,
+    };
+    let value = reader.value()?;
+    reader.skip_whitespace();
+    match reader.chars.next() {
+        None => Ok(value),
+        Some(char) => Err(format!("invalid json: unexpected '{char}' after the value")),
+    }
+}
+
+struct Reader<'a> {
+    chars: Peekable<Chars<'a>>,
+    depth: usize,
+}
+
+impl Reader<'_> {
+    fn skip_whitespace(&mut self) {
+        while self
+            .chars
+            .next_if(|char| matches!(char, ' ' | '\n' | '\r' | '\t'))
+            .is_some()
+        {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.chars.next() {
+            Some(char) if char == expected => Ok(()),
+            Some(char) => Err(format!("invalid json: expected '{expected}', got '{char}'")),
+            None => Err(format!("invalid json: expected '{expected}', got the end")),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Value) -> Result<Value, String> {
+        for expected in keyword.chars() {
+            self.expect(expected)?;
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('n') => self.keyword("null", Value::Null),
+            Some('t') => self.keyword("true", Value::Bool(true)),
+            Some('f') => self.keyword("false", Value::Bool(false)),
+            Some('"') => Ok(Value::String(self.string()?.into())),
+            Some('-' | '0'..='9') => self.number(),
+            Some('[') => self.nested(Reader::array),
+            Some('{') => self.nested(Reader::object),
+            Some(char) => Err(format!("invalid json: unexpected '{char}'")),
+            None => Err("invalid json: unexpected end".to_string()),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Value, String>) -> Result<Value, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(format!("invalid json: nested deeper than {MAX_DEPTH}"));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let mut number = String::new();
+        while let Some(char) = self
+            .chars
+            .next_if(|char| matches!(char, '-' | '+' | '.' | 'e' | 'E' | '0'..='9'))
+        {
+            number.push(char);
+        }
+        if number.contains(['.', 'e', 'E']) {
+            return Err(format!("invalid json: floats are not supported: {number}"));
+        }
+        number
+            .parse()
+            .map(Value::Int)
+            .map_err(|_| format!("invalid json: bad integer: {number}"))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => string.push(self.escape()?),
+                Some(char) if char.is_control() => {
+                    return Err("invalid json: control character in string".to_string())
+                }
+                Some(char) => string.push(char),
+                None => return Err("invalid json: unterminated string".to_string()),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, String> {
+        Ok(match self.chars.next() {
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('/') => '/',
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('u') => {
+                let high = self.hex()?;
+                if (0xD800..0xDC00).contains(&high) {
+                    self.expect('\\')?;
+                    self.expect('u')?;
+                    let low = self.hex()?;
+                    if !(0xDC00..0xE000).contains(&low) {
+                        return Err("invalid json: bad surrogate pair".to_string());
+                    }
+                    let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                    char::from_u32(code).ok_or("invalid json: bad surrogate pair")?
+                } else {
+                    char::from_u32(high).ok_or("invalid json: bad unicode escape")?
+                }
+            }
+            Some(char) => return Err(format!("invalid json: unknown escape '\\{char}'")),
+            None => return Err("invalid json: unterminated string".to_string()),
+        })
+    }
+
+    fn hex(&mut self) -> Result<u32, String> {
+        let digits = (0..4)
+            .map(|_| self.chars.next().ok_or("invalid json: unterminated string"))
+            .collect::<Result<String, _>>()?;
+        u32::from_str_radix(&digits, 16)
+            .map_err(|_| format!("invalid json: bad unicode escape: {digits}"))
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut array = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&']').is_some() {
+            return Ok(Value::Array(Rc::new(array)));
+        }
+        loop {
+            array.push(self.value()?);
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Value::Array(Rc::new(array))),
+                Some(char) => {
+                    return Err(format!("invalid json: expected ',' or ']', got '{char}'"))
+                }
+                None => return Err("invalid json: unterminated array".to_string()),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut hash = HashMap::new();
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&'}').is_some() {
+            return Ok(Value::Hash(Rc::new(hash)));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            let value = self.value()?;
+            hash.insert(HashKey::String(key.into()), value);
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Value::Hash(Rc::new(hash))),
+                Some(char) => {
+                    return Err(format!("invalid json: expected ',' or '}}', got '{char}'"))
+                }
+                None => return Err("invalid json: unterminated object".to_string()),
+            }
+        }
+    }
+}
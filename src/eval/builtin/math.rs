@@ -0,0 +1,64 @@
+use super::Context;
+use crate::eval::value::Value;
+
+fn int_arg(name: &str, arg: &Value) -> Result<i64, String> {
+    match arg {
+        Value::Int(int) => Ok(*int),
+        arg => Err(format!(
+            "argument to '{name}' must be INTEGER, got {}",
+            arg.as_type()
+        )),
+    }
+}
+
+fn unary(name: &str, args: Vec<Value>, f: fn(i64) -> Option<i64>) -> Result<Value, String> {
+    let int = int_arg(name, &args[0])?;
+    f(int)
+        .map(Value::Int)
+        .ok_or(format!("invalid argument to '{name}': {int}"))
+}
+
+fn fold(name: &str, args: Vec<Value>, f: fn(i64, i64) -> i64) -> Result<Value, String> {
+    let mut ints = args.iter().map(|arg| int_arg(name, arg));
+    let first = ints.next().unwrap()?;
+    ints.try_fold(first, |acc, int| Ok(f(acc, int?)))
+        .map(Value::Int)
+}
+
+pub fn builtin_abs(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    unary("abs", args, i64::checked_abs)
+}
+
+pub fn builtin_min(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    fold("min", args, i64::min)
+}
+
+pub fn builtin_max(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    fold("max", args, i64::max)
+}
+
+pub fn builtin_pow(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    let base = int_arg("pow", &args[0])?;
+    let exp = int_arg("pow", &args[1])?;
+    u32::try_from(exp)
+        .map_err(|_| format!("invalid exponent to 'pow': {exp}"))
+        .and_then(|exp| {
+            base.checked_pow(exp)
+                .map(Value::Int)
+                .ok_or("integer overflow in 'pow'".to_string())
+        })
+}
+
+/// Integer square root, rounded down.
+pub fn builtin_sqrt(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    unary("sqrt", args, i64::checked_isqrt)
+}
+
+/// Integers are already whole, so `floor` and `ceil` only check their argument.
+pub fn builtin_floor(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    unary("floor", args, Some)
+}
+
+pub fn builtin_ceil(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    unary("ceil", args, Some)
+}
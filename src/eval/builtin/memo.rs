@@ -0,0 +1,64 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::Context;
+use crate::eval::value::{HashKey, Value};
+
+/// A function wrapped by `memoize`, calls with the same hashable arguments run it once.
+#[derive(Debug)]
+pub struct Memo {
+    pub function: Value,
+    cache: RefCell<HashMap<Vec<HashKey>, Value>>,
+}
+
+impl Memo {
+    pub fn new(function: Value) -> Self {
+        Memo {
+            function,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Calls with an argument that can't be a hash key skip the cache.
+    pub fn call(&self, args: Vec<Value>, ctx: &mut dyn Context) -> Result<Value, String> {
+        let Ok(key) = args
+            .iter()
+            .cloned()
+            .map(HashKey::try_from)
+            .collect::<Result<Vec<_>, _>>()
+        else {
+            return ctx.apply(self.function.clone(), args);
+        };
+        if let Some(value) = self.cache.borrow().get(&key) {
+            return Ok(value.clone());
+        }
+        let value = ctx.apply(self.function.clone(), args)?;
+        self.cache.borrow_mut().insert(key, value.clone());
+        Ok(value)
+    }
+
+    pub fn cached(&self) -> Vec<Value> {
+        self.cache.borrow().values().cloned().collect()
+    }
+}
+
+/// Memos are only equal to themselves, like the functions they wrap.
+impl PartialEq for Memo {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+pub fn builtin_memoize(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    match &args[0] {
+        function @ (Value::Function { .. }
+        | Value::Closure { .. }
+        | Value::Builtin(_)
+        | Value::Memoized(_)) => Ok(Value::Memoized(Rc::new(Memo::new(function.clone())))),
+        arg => Err(format!(
+            "argument to 'memoize' must be FUNCTION, got {}",
+            arg.as_type()
+        )),
+    }
+}
@@ -0,0 +1,449 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use super::value::Value;
+
+use self::io::Io;
+use self::random::Rng;
+
+pub mod array;
+pub mod convert;
+pub mod hash;
+pub mod io;
+pub mod json;
+pub mod math;
+pub mod memo;
+pub mod random;
+pub mod regex;
+pub mod runtime;
+pub mod search;
+pub mod string;
+
+pub type BuiltinFunction = fn(Vec<Value>, &mut dyn Context) -> Result<Value, String>;
+
+/// Lets a builtin call back into the engine that is running it and reach its state.
+pub trait Context {
+    fn apply(&mut self, function: Value, args: Vec<Value>) -> Result<Value, String>;
+    fn rng(&mut self) -> &mut Rng;
+    fn io(&mut self) -> &mut dyn Io;
+    /// Records the exit code, the builtin then returns an error to unwind the engine.
+    fn exit(&mut self, code: i64);
+    /// Runs `source` against the global scope and returns its last value.
+    fn eval(&mut self, source: &str) -> Result<Value, String>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Arity {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl Arity {
+    pub const fn exact(count: usize) -> Self {
+        Arity {
+            min: count,
+            max: Some(count),
+        }
+    }
+
+    pub const fn range(min: usize, max: usize) -> Self {
+        Arity {
+            min,
+            max: Some(max),
+        }
+    }
+
+    pub const fn at_least(min: usize) -> Self {
+        Arity { min, max: None }
+    }
+
+    pub fn check(&self, got: usize) -> Result<(), String> {
+        match self.max {
+            Some(max) if got < self.min || got > max => Err(if self.min == max {
+                format!("wrong number of arguments, got={got}, want={max}")
+            } else {
+                format!(
+                    "wrong number of arguments, got={got}, want={}..{max}",
+                    self.min
+                )
+            }),
+            None if got < self.min => Err(format!(
+                "wrong number of arguments, got={got}, want at least {}",
+                self.min
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+pub struct Builtin {
+    pub name: &'static str,
+    pub arity: Arity,
+    pub function: BuiltinFunction,
+    /// How to call it, like `push(array, value)`.
+    pub signature: &'static str,
+    pub doc: &'static str,
+    /// A call and, after `//`, what it returns.
+    pub example: &'static str,
+}
+
+impl Builtin {
+    pub const fn new(name: &'static str, arity: Arity, function: BuiltinFunction) -> Self {
+        Builtin {
+            name,
+            arity,
+            function,
+            signature: name,
+            doc: "",
+            example: "",
+        }
+    }
+
+    pub const fn with_doc(
+        self,
+        signature: &'static str,
+        doc: &'static str,
+        example: &'static str,
+    ) -> Self {
+        Builtin {
+            signature,
+            doc,
+            example,
+            ..self
+        }
+    }
+
+    /// The signature, doc and example, one per line.
+    pub fn usage(&self) -> String {
+        format!("{}\n  {}\n  {}\n", self.signature, self.doc, self.example)
+    }
+
+    pub fn call(&self, args: Vec<Value>, ctx: &mut dyn Context) -> Result<Value, String> {
+        self.arity.check(args.len())?;
+        (self.function)(args, ctx)
+    }
+}
+
+impl Debug for Builtin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Builtin({})", self.name)
+    }
+}
+
+/// Builtins only live in the registry, so two are equal when they are the same entry.
+impl PartialEq for Builtin {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// The single list of builtins, a builtin's position is the index `OpGetBuiltin` and `.mkc` files use.
+pub struct BuiltinRegistry {
+    builtins: &'static [Builtin],
+}
+
+impl BuiltinRegistry {
+    pub fn get(&self, index: usize) -> Option<&'static Builtin> {
+        self.builtins.get(index)
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<&'static Builtin> {
+        self.builtins.iter().find(|builtin| builtin.name == name)
+    }
+
+    pub fn index_of(&self, builtin: &Builtin) -> Option<usize> {
+        self.builtins
+            .iter()
+            .position(|entry| std::ptr::eq(entry, builtin))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'static Builtin> {
+        self.builtins.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.builtins.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.builtins.is_empty()
+    }
+}
+
+/// New builtins go at the end, inserting one shifts the indices of compiled programs.
+pub static BUILTINS: BuiltinRegistry = BuiltinRegistry {
+    builtins: &[
+        Builtin::new("len", Arity::exact(1), array::builtin_len).with_doc(
+            "len(value)",
+            "The number of elements of an array or chars of a string.",
+            r#"len("héllo") // 5"#,
+        ),
+        Builtin::new("first", Arity::exact(1), array::builtin_first).with_doc(
+            "first(array)",
+            "The first element, null when the array is empty.",
+            "first([1, 2, 3]) // 1",
+        ),
+        Builtin::new("last", Arity::exact(1), array::builtin_last).with_doc(
+            "last(array)",
+            "The last element, null when the array is empty.",
+            "last([1, 2, 3]) // 3",
+        ),
+        Builtin::new("rest", Arity::exact(1), array::builtin_rest).with_doc(
+            "rest(array)",
+            "Every element but the first, null when the array is empty.",
+            "rest([1, 2, 3]) // [2, 3]",
+        ),
+        Builtin::new("push", Arity::exact(2), array::builtin_push).with_doc(
+            "push(array, value)",
+            "A new array with value added at the end.",
+            "push([1, 2], 3) // [1, 2, 3]",
+        ),
+        Builtin::new("puts", Arity::at_least(0), io::builtin_puts).with_doc(
+            "puts(values...)",
+            "Prints each value on its own line.",
+            r#"puts("hello") // hello"#,
+        ),
+        Builtin::new("map", Arity::exact(2), array::builtin_map).with_doc(
+            "map(array, fn)",
+            "A new array with fn applied to each element.",
+            "map([1, 2], fn(x) { x * 2 }) // [2, 4]",
+        ),
+        Builtin::new("filter", Arity::exact(2), array::builtin_filter).with_doc(
+            "filter(array, fn)",
+            "The elements fn returns true for.",
+            "filter([1, 2, 3], fn(x) { x > 1 }) // [2, 3]",
+        ),
+        Builtin::new("reduce", Arity::exact(3), array::builtin_reduce).with_doc(
+            "reduce(array, initial, fn)",
+            "Folds the elements into one value, fn gets the accumulator and an element.",
+            "reduce([1, 2, 3], 0, fn(acc, x) { acc + x }) // 6",
+        ),
+        Builtin::new("split", Arity::exact(2), string::builtin_split).with_doc(
+            "split(string, separator)",
+            "The parts between separators, an empty separator splits into chars.",
+            r#"split("a,b", ",") // ["a", "b"]"#,
+        ),
+        Builtin::new("join", Arity::exact(2), string::builtin_join).with_doc(
+            "join(array, separator)",
+            "The strings of an array joined with separator.",
+            r#"join(["a", "b"], "-") // "a-b""#,
+        ),
+        Builtin::new("trim", Arity::exact(1), string::builtin_trim).with_doc(
+            "trim(string)",
+            "The string without leading and trailing whitespace.",
+            r#"trim("  hi ") // "hi""#,
+        ),
+        Builtin::new("replace", Arity::exact(3), string::builtin_replace).with_doc(
+            "replace(string, from, to)",
+            "The string with every from replaced by to.",
+            r#"replace("a-b-c", "-", "+") // "a+b+c""#,
+        ),
+        Builtin::new("upper", Arity::exact(1), string::builtin_upper).with_doc(
+            "upper(string)",
+            "The string in upper case.",
+            r#"upper("hi") // "HI""#,
+        ),
+        Builtin::new("lower", Arity::exact(1), string::builtin_lower).with_doc(
+            "lower(string)",
+            "The string in lower case.",
+            r#"lower("HI") // "hi""#,
+        ),
+        Builtin::new("starts_with", Arity::exact(2), string::builtin_starts_with).with_doc(
+            "starts_with(string, prefix)",
+            "Whether the string starts with prefix.",
+            r#"starts_with("monkey", "mon") // true"#,
+        ),
+        Builtin::new("ends_with", Arity::exact(2), string::builtin_ends_with).with_doc(
+            "ends_with(string, suffix)",
+            "Whether the string ends with suffix.",
+            r#"ends_with("monkey", "key") // true"#,
+        ),
+        Builtin::new("keys", Arity::exact(1), hash::builtin_keys).with_doc(
+            "keys(hash)",
+            "The keys of a hash, sorted.",
+            r#"keys({"b": 2, "a": 1}) // ["a", "b"]"#,
+        ),
+        Builtin::new("values", Arity::exact(1), hash::builtin_values).with_doc(
+            "values(hash)",
+            "The values of a hash, in the order of their keys.",
+            r#"values({"b": 2, "a": 1}) // [1, 2]"#,
+        ),
+        Builtin::new("delete", Arity::exact(2), hash::builtin_delete).with_doc(
+            "delete(hash, key)",
+            "A new hash without key.",
+            r#"delete({"a": 1, "b": 2}, "a") // {"b": 2}"#,
+        ),
+        Builtin::new("has_key", Arity::exact(2), hash::builtin_has_key).with_doc(
+            "has_key(hash, key)",
+            "Whether the hash has key.",
+            r#"has_key({"a": 1}, "a") // true"#,
+        ),
+        Builtin::new("int", Arity::exact(1), convert::builtin_int).with_doc(
+            "int(value)",
+            "Converts a string or boolean to an integer.",
+            r#"int("42") // 42"#,
+        ),
+        Builtin::new("str", Arity::exact(1), convert::builtin_str).with_doc(
+            "str(value)",
+            "The value as a string.",
+            r#"str(42) // "42""#,
+        ),
+        Builtin::new("bool", Arity::exact(1), convert::builtin_bool).with_doc(
+            "bool(value)",
+            "Converts a value to a boolean, strings have to be true or false.",
+            r#"bool("true") // true"#,
+        ),
+        Builtin::new("abs", Arity::exact(1), math::builtin_abs).with_doc(
+            "abs(int)",
+            "The absolute value.",
+            "abs(-3) // 3",
+        ),
+        Builtin::new("min", Arity::at_least(1), math::builtin_min).with_doc(
+            "min(ints...)",
+            "The smallest argument.",
+            "min(3, 1, 2) // 1",
+        ),
+        Builtin::new("max", Arity::at_least(1), math::builtin_max).with_doc(
+            "max(ints...)",
+            "The largest argument.",
+            "max(3, 1, 2) // 3",
+        ),
+        Builtin::new("pow", Arity::exact(2), math::builtin_pow).with_doc(
+            "pow(base, exponent)",
+            "base raised to a non-negative exponent.",
+            "pow(2, 10) // 1024",
+        ),
+        Builtin::new("sqrt", Arity::exact(1), math::builtin_sqrt).with_doc(
+            "sqrt(int)",
+            "The square root, rounded down.",
+            "sqrt(10) // 3",
+        ),
+        Builtin::new("floor", Arity::exact(1), math::builtin_floor).with_doc(
+            "floor(int)",
+            "The integer itself, there are no floats yet.",
+            "floor(3) // 3",
+        ),
+        Builtin::new("ceil", Arity::exact(1), math::builtin_ceil).with_doc(
+            "ceil(int)",
+            "The integer itself, there are no floats yet.",
+            "ceil(3) // 3",
+        ),
+        Builtin::new("rand", Arity::exact(0), random::builtin_rand).with_doc(
+            "rand()",
+            "A random non-negative integer.",
+            "rand() // 8372049102",
+        ),
+        Builtin::new("rand_int", Arity::exact(2), random::builtin_rand_int).with_doc(
+            "rand_int(lo, hi)",
+            "A random integer in lo..hi.",
+            "rand_int(1, 7) // 4",
+        ),
+        Builtin::new("seed", Arity::exact(1), random::builtin_seed).with_doc(
+            "seed(int)",
+            "Restarts the random numbers from a seed.",
+            "seed(42)",
+        ),
+        Builtin::new("input", Arity::range(0, 1), io::builtin_input).with_doc(
+            "input(prompt?)",
+            "Reads a line, null at the end of the input.",
+            r#"input("name: ")"#,
+        ),
+        Builtin::new("range", Arity::range(1, 3), array::builtin_range).with_doc(
+            "range(start?, end, step?)",
+            "The integers from start up to end, end excluded.",
+            "range(1, 7, 2) // [1, 3, 5]",
+        ),
+        Builtin::new("format", Arity::at_least(1), string::builtin_format).with_doc(
+            "format(template, values...)",
+            "Fills each {} of the template with the next value.",
+            r#"format("{} + {}", 1, 2) // "1 + 2""#,
+        ),
+        Builtin::new("printf", Arity::at_least(1), io::builtin_printf).with_doc(
+            "printf(template, values...)",
+            "Prints the template filled like format, without a newline.",
+            r#"printf("{}!", "hi") // hi!"#,
+        ),
+        Builtin::new("exit", Arity::range(0, 1), runtime::builtin_exit).with_doc(
+            "exit(code?)",
+            "Stops the program with code, 0 by default.",
+            "exit(1)",
+        ),
+        Builtin::new("eval", Arity::exact(1), runtime::builtin_eval).with_doc(
+            "eval(source)",
+            "Runs source against the globals and returns its last value.",
+            r#"eval("1 + 2") // 3"#,
+        ),
+        Builtin::new("json_parse", Arity::exact(1), json::builtin_json_parse).with_doc(
+            "json_parse(string)",
+            "Reads JSON into arrays, hashes, strings, integers and booleans.",
+            r#"json_parse("[1, true]") // [1, true]"#,
+        ),
+        Builtin::new(
+            "json_stringify",
+            Arity::exact(1),
+            json::builtin_json_stringify,
+        )
+        .with_doc(
+            "json_stringify(value)",
+            "Writes a value as JSON with sorted keys.",
+            r#"json_stringify([1, true]) // "[1,true]""#,
+        ),
+        Builtin::new("regex_match", Arity::exact(2), regex::builtin_regex_match).with_doc(
+            "regex_match(pattern, string)",
+            "The captures of the first match, null when there is none. Needs the regex feature.",
+            r#"regex_match("(\d+)", "a12") // ["12", "12"]"#,
+        ),
+        Builtin::new(
+            "regex_find_all",
+            Arity::exact(2),
+            regex::builtin_regex_find_all,
+        )
+        .with_doc(
+            "regex_find_all(pattern, string)",
+            "Every match of the pattern. Needs the regex feature.",
+            r#"regex_find_all("\d", "a1b2") // ["1", "2"]"#,
+        ),
+        Builtin::new(
+            "regex_replace",
+            Arity::exact(3),
+            regex::builtin_regex_replace,
+        )
+        .with_doc(
+            "regex_replace(pattern, string, replacement)",
+            "The string with every match replaced. Needs the regex feature.",
+            r##"regex_replace("\d", "a1b2", "#") // "a#b#""##,
+        ),
+        Builtin::new("contains", Arity::exact(2), search::builtin_contains).with_doc(
+            "contains(collection, value)",
+            "Whether an array has the element, a string the substring or a hash the key.",
+            r#"contains("monkey", "key") // true"#,
+        ),
+        Builtin::new("index_of", Arity::exact(2), search::builtin_index_of).with_doc(
+            "index_of(collection, value)",
+            "Where the first match is, in chars for strings, or -1.",
+            "index_of([5, 6], 6) // 1",
+        ),
+        Builtin::new("slice", Arity::range(2, 3), array::builtin_slice).with_doc(
+            "slice(value, start, end?)",
+            "The elements or chars from start up to end, clamped to the length.",
+            "slice([1, 2, 3], 1) // [2, 3]",
+        ),
+        Builtin::new("memoize", Arity::exact(1), memo::builtin_memoize).with_doc(
+            "memoize(fn)",
+            "fn with its results cached by their arguments.",
+            "let fib = memoize(fn(n) { ... })",
+        ),
+        Builtin::new("help", Arity::range(0, 1), runtime::builtin_help).with_doc(
+            "help(builtin?)",
+            "Shows how to use a builtin, or lists them all.",
+            "help(len)",
+        ),
+    ],
+};
+
+pub fn new_builtins() -> HashMap<String, Value> {
+    BUILTINS
+        .iter()
+        .map(|builtin| (builtin.name.to_string(), Value::Builtin(builtin)))
+        .collect()
+}
@@ -0,0 +1,67 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::Context;
+use crate::eval::value::Value;
+
+/// SplitMix64, small and deterministic for a given seed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64);
+        Rng::new(nanos)
+    }
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// Returns a non-negative integer, there are no floats yet.
+pub fn builtin_rand(_: Vec<Value>, ctx: &mut dyn Context) -> Result<Value, String> {
+    Ok(Value::Int((ctx.rng().next_u64() >> 1) as i64))
+}
+
+/// Returns an integer in `lo..hi`.
+pub fn builtin_rand_int(args: Vec<Value>, ctx: &mut dyn Context) -> Result<Value, String> {
+    match (&args[0], &args[1]) {
+        (Value::Int(lo), Value::Int(hi)) if lo < hi => {
+            let span = hi.abs_diff(*lo);
+            let offset = ctx.rng().next_u64() % span;
+            Ok(Value::Int(lo.wrapping_add_unsigned(offset)))
+        }
+        (Value::Int(lo), Value::Int(hi)) => Err(format!("empty range for 'rand_int': {lo}..{hi}")),
+        (Value::Int(_), arg) | (arg, _) => Err(format!(
+            "argument to 'rand_int' must be INTEGER, got {}",
+            arg.as_type()
+        )),
+    }
+}
+
+pub fn builtin_seed(args: Vec<Value>, ctx: &mut dyn Context) -> Result<Value, String> {
+    match &args[0] {
+        Value::Int(seed) => {
+            *ctx.rng() = Rng::new(*seed as u64);
+            Ok(Value::Null)
+        }
+        arg => Err(format!(
+            "argument to 'seed' must be INTEGER, got {}",
+            arg.as_type()
+        )),
+    }
+}
@@ -0,0 +1,105 @@
+use super::Context;
+use crate::eval::value::Value;
+
+/// Without the `regex` feature the builtins keep their names and indices but always fail.
+#[cfg(not(feature = "regex"))]
+mod disabled {
+    use super::*;
+
+    fn disabled(name: &str) -> Result<Value, String> {
+        Err(format!(
+            "'{name}' needs the interpreter built with the regex feature"
+        ))
+    }
+
+    pub fn builtin_regex_match(_: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+        disabled("regex_match")
+    }
+
+    pub fn builtin_regex_find_all(_: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+        disabled("regex_find_all")
+    }
+
+    pub fn builtin_regex_replace(_: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+        disabled("regex_replace")
+    }
+}
+
+#[cfg(not(feature = "regex"))]
+pub use disabled::*;
+
+#[cfg(feature = "regex")]
+mod enabled {
+    use std::rc::Rc;
+
+    use regex::{Captures, Regex};
+
+    use super::*;
+
+    fn strings<'a>(name: &str, args: &'a [Value]) -> Result<Vec<&'a str>, String> {
+        args.iter()
+            .map(|arg| match arg {
+                Value::String(string) => Ok(string.as_ref()),
+                arg => Err(format!(
+                    "argument to '{name}' must be STRING, got {}",
+                    arg.as_type()
+                )),
+            })
+            .collect()
+    }
+
+    fn compile(pattern: &str) -> Result<Regex, String> {
+        Regex::new(pattern).map_err(|err| format!("invalid regex: {err}"))
+    }
+
+    /// The whole match followed by each group, null for groups that didn't take part.
+    fn captures(captures: &Captures) -> Value {
+        Value::Array(Rc::new(
+            captures
+                .iter()
+                .map(|group| {
+                    group.map_or(Value::Null, |group| Value::String(group.as_str().into()))
+                })
+                .collect(),
+        ))
+    }
+
+    /// Returns the captures of the first match, or null.
+    pub fn builtin_regex_match(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+        let args = strings("regex_match", &args)?;
+        let regex = compile(args[0])?;
+        Ok(regex
+            .captures(args[1])
+            .map_or(Value::Null, |found| captures(&found)))
+    }
+
+    /// Returns every match, as captures when the pattern has groups.
+    pub fn builtin_regex_find_all(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+        let args = strings("regex_find_all", &args)?;
+        let regex = compile(args[0])?;
+        let found = if regex.captures_len() > 1 {
+            regex
+                .captures_iter(args[1])
+                .map(|found| captures(&found))
+                .collect()
+        } else {
+            regex
+                .find_iter(args[1])
+                .map(|found| Value::String(found.as_str().into()))
+                .collect()
+        };
+        Ok(Value::Array(Rc::new(found)))
+    }
+
+    /// Replaces every match, `$1` and `${name}` in the replacement refer to groups.
+    pub fn builtin_regex_replace(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+        let args = strings("regex_replace", &args)?;
+        let regex = compile(args[0])?;
+        Ok(Value::String(
+            regex.replace_all(args[1], args[2]).as_ref().into(),
+        ))
+    }
+}
+
+#[cfg(feature = "regex")]
+pub use enabled::*;
@@ -0,0 +1,50 @@
+use super::{Builtin, Context, BUILTINS};
+use crate::eval::value::Value;
+
+pub fn builtin_exit(args: Vec<Value>, ctx: &mut dyn Context) -> Result<Value, String> {
+    let code = match args.first() {
+        None => 0,
+        Some(Value::Int(code)) => *code,
+        Some(arg) => {
+            return Err(format!(
+                "argument to 'exit' must be INTEGER, got {}",
+                arg.as_type()
+            ))
+        }
+    };
+    ctx.exit(code);
+    Err(format!("exit({code})"))
+}
+
+pub fn builtin_eval(args: Vec<Value>, ctx: &mut dyn Context) -> Result<Value, String> {
+    match &args[0] {
+        Value::String(source) => ctx.eval(source),
+        arg => Err(format!(
+            "argument to 'eval' must be STRING, got {}",
+            arg.as_type()
+        )),
+    }
+}
+
+/// `help(builtin)` prints how to use a builtin, `help()` lists them all.
+pub fn builtin_help(args: Vec<Value>, ctx: &mut dyn Context) -> Result<Value, String> {
+    let text = match args.first() {
+        None => BUILTINS
+            .iter()
+            .map(|builtin| format!("{}\n", builtin.signature))
+            .collect(),
+        Some(Value::Builtin(builtin)) => builtin.usage(),
+        Some(Value::String(name)) => BUILTINS
+            .lookup(name)
+            .map(Builtin::usage)
+            .ok_or(format!("unknown builtin: {name}"))?,
+        Some(arg) => {
+            return Err(format!(
+                "argument to 'help' must be BUILTIN or STRING, got {}",
+                arg.as_type()
+            ))
+        }
+    };
+    ctx.io().write(&text)?;
+    Ok(Value::Null)
+}
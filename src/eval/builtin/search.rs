@@ -0,0 +1,47 @@
+use super::Context;
+use crate::eval::value::{HashKey, Value};
+
+fn substring<'a>(name: &str, arg: &'a Value) -> Result<&'a str, String> {
+    match arg {
+        Value::String(string) => Ok(string),
+        arg => Err(format!(
+            "argument to '{name}' must be STRING, got {}",
+            arg.as_type()
+        )),
+    }
+}
+
+/// Looks for an element of an array, a substring of a string or a key of a hash.
+pub fn builtin_contains(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    match &args[0] {
+        Value::Array(array) => Ok(Value::Bool(array.contains(&args[1]))),
+        Value::String(string) => Ok(Value::Bool(
+            string.contains(substring("contains", &args[1])?),
+        )),
+        Value::Hash(hash) => {
+            let key = HashKey::try_from(args[1].clone())?;
+            Ok(Value::Bool(hash.contains_key(&key)))
+        }
+        arg => Err(format!(
+            "argument to 'contains' not supported: got {}",
+            arg.as_type()
+        )),
+    }
+}
+
+/// Returns the position of the first match, counted in chars for strings, or -1.
+pub fn builtin_index_of(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    let idx = match &args[0] {
+        Value::Array(array) => array.iter().position(|element| *element == args[1]),
+        Value::String(string) => string
+            .find(substring("index_of", &args[1])?)
+            .map(|byte_idx| string[..byte_idx].chars().count()),
+        arg => {
+            return Err(format!(
+                "argument to 'index_of' not supported: got {}",
+                arg.as_type()
+            ))
+        }
+    };
+    Ok(Value::Int(idx.map_or(-1, |idx| idx as i64)))
+}
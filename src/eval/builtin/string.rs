@@ -0,0 +1,152 @@
+use std::rc::Rc;
+
+use super::Context;
+use crate::eval::value::Value;
+
+pub fn builtin_split(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    match (&args[0], &args[1]) {
+        (Value::String(string), Value::String(sep)) => {
+            let parts = if sep.is_empty() {
+                string
+                    .chars()
+                    .map(|char| Value::String(char.to_string().into()))
+                    .collect()
+            } else {
+                string
+                    .split(sep.as_ref())
+                    .map(|part| Value::String(part.into()))
+                    .collect()
+            };
+            Ok(Value::Array(Rc::new(parts)))
+        }
+        (Value::String(_), arg) | (arg, _) => Err(format!(
+            "argument to 'split' must be STRING, got {}",
+            arg.as_type()
+        )),
+    }
+}
+
+pub fn builtin_join(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    match (&args[0], &args[1]) {
+        (Value::Array(array), Value::String(sep)) => {
+            let parts = array
+                .iter()
+                .map(|element| match element {
+                    Value::String(string) => Ok(string.as_ref()),
+                    element => Err(format!(
+                        "elements of 'join' must be STRING, got {}",
+                        element.as_type()
+                    )),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Value::String(parts.join(sep).into()))
+        }
+        (Value::Array(_), arg) => Err(format!(
+            "argument to 'join' must be STRING, got {}",
+            arg.as_type()
+        )),
+        (arg, _) => Err(format!(
+            "argument to 'join' must be ARRAY, got {}",
+            arg.as_type()
+        )),
+    }
+}
+
+pub fn builtin_format(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    format_values("format", args).map(|string| Value::String(string.into()))
+}
+
+/// Replaces each `{}` in the first argument with the next argument, `{{` and `}}` are literal braces.
+pub fn format_values(name: &str, args: Vec<Value>) -> Result<String, String> {
+    let (template, values) = match args.split_first() {
+        Some((Value::String(template), values)) => (template, values),
+        Some((arg, _)) => {
+            return Err(format!(
+                "argument to '{name}' must be STRING, got {}",
+                arg.as_type()
+            ))
+        }
+        None => unreachable!("'{name}' takes at least 1 argument"),
+    };
+
+    let mut formatted = String::with_capacity(template.len());
+    let mut values = values.iter();
+    let mut chars = template.chars().peekable();
+    while let Some(char) = chars.next() {
+        match (char, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                formatted.push(char);
+            }
+            ('{', Some('}')) => {
+                chars.next();
+                match values.next() {
+                    Some(Value::String(string)) => formatted.push_str(string),
+                    Some(value) => formatted.push_str(&value.to_string()),
+                    None => return Err(format!("not enough arguments for '{name}'")),
+                }
+            }
+            _ => formatted.push(char),
+        }
+    }
+    if values.next().is_some() {
+        return Err(format!("too many arguments for '{name}'"));
+    }
+    Ok(formatted)
+}
+
+pub fn builtin_trim(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    map_string("trim", args, |string| string.trim().to_string())
+}
+
+pub fn builtin_upper(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    map_string("upper", args, str::to_uppercase)
+}
+
+pub fn builtin_lower(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    map_string("lower", args, str::to_lowercase)
+}
+
+fn map_string(name: &str, args: Vec<Value>, f: fn(&str) -> String) -> Result<Value, String> {
+    match &args[0] {
+        Value::String(string) => Ok(Value::String(f(string).into())),
+        arg => Err(format!(
+            "argument to '{name}' must be STRING, got {}",
+            arg.as_type()
+        )),
+    }
+}
+
+pub fn builtin_replace(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    match (&args[0], &args[1], &args[2]) {
+        (Value::String(string), Value::String(from), Value::String(to)) => {
+            Ok(Value::String(string.replace(from.as_ref(), to).into()))
+        }
+        (Value::String(_), Value::String(_), arg) | (Value::String(_), arg, _) | (arg, _, _) => {
+            Err(format!(
+                "argument to 'replace' must be STRING, got {}",
+                arg.as_type()
+            ))
+        }
+    }
+}
+
+pub fn builtin_starts_with(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    test_strings("starts_with", args, |string, prefix| {
+        string.starts_with(prefix)
+    })
+}
+
+pub fn builtin_ends_with(args: Vec<Value>, _: &mut dyn Context) -> Result<Value, String> {
+    test_strings("ends_with", args, |string, suffix| string.ends_with(suffix))
+}
+
+fn test_strings(name: &str, args: Vec<Value>, f: fn(&str, &str) -> bool) -> Result<Value, String> {
+    match (&args[0], &args[1]) {
+        (Value::String(string), Value::String(pattern)) => Ok(Value::Bool(f(string, pattern))),
+        (Value::String(_), arg) | (arg, _) => Err(format!(
+            "argument to '{name}' must be STRING, got {}",
+            arg.as_type()
+        )),
+    }
+}
@@ -10,6 +10,12 @@ pub struct Environment {
     pub outer: Option<Rc<RefCell<Environment>>>,
 }
 
+/// The bindings of one scope at some point, see [`Environment::checkpoint`].
+#[derive(PartialEq, Debug, Clone)]
+pub struct Checkpoint {
+    store: HashMap<String, Value>,
+}
+
 impl Environment {
     pub fn new() -> Self {
         Environment {
@@ -38,6 +44,42 @@ impl Environment {
     pub fn insert(&mut self, name: impl Into<String>, value: Value) {
         self.store.insert(name.into(), value);
     }
+
+    /// Remembers the bindings of this scope so a failed evaluation can be undone with
+    /// [`Environment::rollback`]. Outer scopes aren't included.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            store: self.store.clone(),
+        }
+    }
+
+    /// Drops every binding made or changed since `checkpoint` was taken.
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        self.store = checkpoint.store;
+    }
+
+    /// Every binding from this scope out to the global one, inner scopes first. A name bound
+    /// in several scopes shows up once for each of them.
+    pub fn bindings(&self) -> impl Iterator<Item = (String, Value)> {
+        let mut bindings = self
+            .store
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect::<Vec<_>>();
+        if let Some(outer) = &self.outer {
+            bindings.extend(outer.borrow().bindings());
+        }
+        bindings.into_iter()
+    }
+
+    /// The bindings visible from this scope, in a single environment without an outer one.
+    pub fn flatten(&self) -> Environment {
+        let mut store = HashMap::new();
+        for (name, value) in self.bindings() {
+            store.entry(name).or_insert(value);
+        }
+        Environment::from(store)
+    }
 }
 
 impl From<HashMap<String, Value>> for Environment {
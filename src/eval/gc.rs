@@ -0,0 +1,156 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+use super::environment::Environment;
+use super::value::Value;
+
+const MIN_THRESHOLD: usize = 1024;
+
+/// Frees the environments of function calls that only stay alive through a cycle, like the
+/// scope of a call that bound a recursive closure: the scope holds the closure and the closure
+/// holds the scope.
+///
+/// An environment is still in use when it has more strong references than the tracked
+/// environments account for, everything it reaches is kept too. The rest is garbage, its
+/// bindings are dropped to break the cycles.
+#[derive(Debug)]
+pub struct Collector {
+    environments: Vec<Weak<RefCell<Environment>>>,
+    /// When to drop the environments that died on their own from `environments`.
+    threshold: usize,
+    /// How many environments survived the last collection.
+    survivors: usize,
+}
+
+impl Default for Collector {
+    fn default() -> Self {
+        Collector {
+            environments: vec![],
+            threshold: MIN_THRESHOLD,
+            survivors: 0,
+        }
+    }
+}
+
+impl Collector {
+    pub fn track(&mut self, env: &Rc<RefCell<Environment>>) {
+        if self.environments.len() >= self.threshold {
+            self.environments.retain(|env| env.strong_count() > 0);
+            self.threshold = (self.environments.len() * 2).max(MIN_THRESHOLD);
+        }
+        self.environments.push(Rc::downgrade(env));
+    }
+
+    /// Whether the live environments grew enough since the last collection to run another one.
+    pub fn should_collect(&self) -> bool {
+        self.environments.len() >= (self.survivors * 2).max(MIN_THRESHOLD)
+    }
+
+    /// Returns how many of the tracked environments are still alive. References the
+    /// environments don't account for, like the evaluator's stacks, keep what they point to.
+    pub fn collect(&mut self) -> usize {
+        let environments = self
+            .environments
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect::<Vec<_>>();
+        let index = environments
+            .iter()
+            .enumerate()
+            .map(|(idx, env)| (Rc::as_ptr(env), idx))
+            .collect::<HashMap<_, _>>();
+
+        let mut internal = vec![0; environments.len()];
+        let mut reachable = vec![false; environments.len()];
+        for env in &environments {
+            references(&env.borrow(), &mut |env, shared| {
+                if let Some(&idx) = index.get(&Rc::as_ptr(env)) {
+                    if shared {
+                        reachable[idx] = true;
+                    } else {
+                        internal[idx] += 1;
+                    }
+                }
+            });
+        }
+
+        // One strong reference is the upgraded copy in `environments`.
+        let mut pending = environments
+            .iter()
+            .enumerate()
+            .filter(|(idx, env)| reachable[*idx] || Rc::strong_count(env) - 1 > internal[*idx])
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+        for &idx in &pending {
+            reachable[idx] = true;
+        }
+        while let Some(idx) = pending.pop() {
+            references(&environments[idx].borrow(), &mut |env, _| {
+                if let Some(&idx) = index.get(&Rc::as_ptr(env)) {
+                    if !reachable[idx] {
+                        reachable[idx] = true;
+                        pending.push(idx);
+                    }
+                }
+            });
+        }
+
+        let mut garbage = vec![];
+        for (env, reachable) in environments.iter().zip(&reachable) {
+            if !reachable {
+                let mut env = env.borrow_mut();
+                garbage.push((std::mem::take(&mut env.store), env.outer.take()));
+            }
+        }
+        drop(garbage);
+
+        self.environments = environments
+            .iter()
+            .zip(reachable)
+            .filter(|(_, reachable)| *reachable)
+            .map(|(env, _)| Rc::downgrade(env))
+            .collect();
+        self.survivors = self.environments.len();
+        self.threshold = (self.survivors * 2).max(MIN_THRESHOLD);
+        self.survivors
+    }
+}
+
+/// Calls `found` with every environment `env` holds a strong reference to. `shared` is set
+/// when the reference sits in an array or hash that something else holds too, those can't be
+/// told apart from references outside the environments.
+fn references(env: &Environment, found: &mut impl FnMut(&Rc<RefCell<Environment>>, bool)) {
+    if let Some(outer) = &env.outer {
+        found(outer, false);
+    }
+    for value in env.store.values() {
+        captured(value, false, found);
+    }
+}
+
+fn captured(value: &Value, shared: bool, found: &mut impl FnMut(&Rc<RefCell<Environment>>, bool)) {
+    match value {
+        Value::Function { env, .. } => found(env, shared),
+        Value::Array(items) => {
+            let shared = shared || Rc::strong_count(items) > 1;
+            for item in items.iter() {
+                captured(item, shared, found);
+            }
+        }
+        Value::Hash(pairs) => {
+            let shared = shared || Rc::strong_count(pairs) > 1;
+            for value in pairs.values() {
+                captured(value, shared, found);
+            }
+        }
+        Value::Memoized(memo) => {
+            let shared = shared || Rc::strong_count(memo) > 1;
+            captured(&memo.function, shared, found);
+            for value in memo.cached() {
+                captured(&value, shared, found);
+            }
+        }
+        _ => {}
+    }
+}
@@ -3,29 +3,48 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::ast::expression::Expression;
 use crate::ast::program::Program;
 use crate::ast::statement::Statement;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
 
+use self::builtin::io::{Io, StdIo};
+use self::builtin::random::Rng;
+use self::builtin::Context;
 use self::environment::Environment;
-use self::value::Value;
+use self::gc::Collector;
+use self::value::{HashKey, Value};
 
 pub mod builtin;
 pub mod environment;
+pub mod gc;
 pub mod value;
 
 #[cfg(test)]
 mod tests;
+#[cfg(test)]
+mod value_test;
 
 #[derive(Debug)]
 pub struct EvalError {
     msg: String,
+    position: Option<(usize, usize)>,
 }
 
 impl EvalError {
     pub fn new(msg: impl Into<String>) -> Self {
-        EvalError { msg: msg.into() }
+        EvalError {
+            msg: msg.into(),
+            position: None,
+        }
+    }
+
+    /// The line and column of the top level statement that failed.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        self.position
     }
 }
 
@@ -35,72 +54,313 @@ impl Display for EvalError {
     }
 }
 
+impl std::error::Error for EvalError {}
+
+/// Calls don't use the host stack, the limit only stops runaway recursion. Builtins that call
+/// back into Monkey, like `map`, still nest a Rust call for each level.
+pub const DEFAULT_MAX_DEPTH: usize = 10_000;
+
+/// How evaluating a statement ended. A jump like `return` leaves the blocks it is in as a
+/// `ControlFlow` rather than as a value, loops would add their `break` and `continue` here.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlFlow {
+    /// Ran to the end with the value of the statement.
+    Next(Value),
+    /// A `return` outside of any function, the rest of the program is skipped.
+    Return(Value),
+}
+
+impl ControlFlow {
+    pub fn value(self) -> Value {
+        match self {
+            ControlFlow::Next(value) | ControlFlow::Return(value) => value,
+        }
+    }
+}
+
+/// A step the evaluator still has to take. `Statement` and `Expression` expand into the steps
+/// for their parts, the rest combine the values those parts left on the value stack.
+enum Work {
+    Statement(Statement),
+    Expression(Expression),
+    Let(String),
+    Return,
+    /// Swaps the value of the previous statement for the next one's.
+    /// Blocks share the statements of the AST, only the statement that runs is cloned.
+    Block(Rc<[Statement]>, usize),
+    Prefix(PrefixOperator),
+    Infix(InfixOperator),
+    Branch {
+        consequence: Rc<[Statement]>,
+        alternative: Option<Rc<[Statement]>>,
+    },
+    Call(usize),
+    /// Ends a call and puts back the caller's environment. A `return` drops the steps and
+    /// values above the call, down to `base`, and leaves its value in their place.
+    Leave {
+        env: Rc<RefCell<Environment>>,
+        base: usize,
+    },
+    Array(usize),
+    /// Rejects an unusable key before the value next to it runs.
+    HashKey,
+    Hash(usize),
+    Index,
+}
+
+fn pop(values: &mut Vec<Value>) -> Value {
+    values
+        .pop()
+        .expect("every step leaves its value on the stack")
+}
+
 #[derive()]
 pub struct Eval {
     pub env: Rc<RefCell<Environment>>,
+    rng: Rng,
+    io: Box<dyn Io>,
+    exit: Option<i64>,
+    depth: usize,
+    max_depth: usize,
+    collector: Collector,
+    interrupt: Option<&'static AtomicBool>,
+}
+
+impl Context for Eval {
+    fn apply(&mut self, function: Value, args: Vec<Value>) -> Result<Value, String> {
+        self.apply_function(function, args).map_err(|err| err.msg)
+    }
+
+    fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+
+    fn io(&mut self) -> &mut dyn Io {
+        self.io.as_mut()
+    }
+
+    fn exit(&mut self, code: i64) {
+        self.exit = Some(code);
+    }
+
+    fn eval(&mut self, source: &str) -> Result<Value, String> {
+        let mut parser = Parser::new(Lexer::from(source));
+        let program = parser.parse_program();
+        if let Some(err) = parser.errors().first() {
+            return Err(format!("parser error: {err}"));
+        }
+
+        let mut global = Rc::clone(&self.env);
+        while let Some(outer) = global.clone().borrow().outer.clone() {
+            global = outer;
+        }
+        let current = std::mem::replace(&mut self.env, global);
+        let mut flow = Ok(ControlFlow::Next(Value::Null));
+        for statement in program.statements {
+            flow = self.eval_statement(statement);
+            if matches!(flow, Err(_) | Ok(ControlFlow::Return(_))) {
+                break;
+            }
+        }
+        self.env = current;
+
+        match flow.map(ControlFlow::value) {
+            Ok(Value::Let) => Ok(Value::Null),
+            Ok(value) => Ok(value),
+            Err(err) => Err(err.msg),
+        }
+    }
 }
 
 impl Eval {
     pub fn new(env: Rc<RefCell<Environment>>) -> Self {
-        Eval { env }
+        Eval {
+            env,
+            rng: Rng::default(),
+            io: Box::new(StdIo::new()),
+            exit: None,
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            collector: Collector::default(),
+            interrupt: None,
+        }
     }
+
+    /// How many Monkey function calls can be nested before eval gives up with an error.
+    pub fn with_max_depth(self, max_depth: usize) -> Self {
+        Eval { max_depth, ..self }
+    }
+
+    pub fn with_io(self, io: Box<dyn Io>) -> Self {
+        Eval { io, ..self }
+    }
+
+    /// Stops evaluating with an error once `interrupt` is set, like from a Ctrl-C handler.
+    pub fn with_interrupt(self, interrupt: &'static AtomicBool) -> Self {
+        Eval {
+            interrupt: Some(interrupt),
+            ..self
+        }
+    }
+
+    /// Defines a global the programs evaluated after it can read, `Engine::set_global` for
+    /// the evaluator.
+    pub fn set_global(&mut self, name: &str, value: impl Into<Value>) {
+        self.env.borrow_mut().insert(name, value.into());
+    }
+
+    /// `Engine::get_global` for the evaluator, builtins aren't globals.
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        match self.env.borrow_mut().get(name)? {
+            Value::Builtin(_) => None,
+            value => Some(value),
+        }
+    }
+
+    /// A call to `exit` stops the program with `Value::Exit`.
     pub fn eval_program(&mut self, program: Program) -> Result<Value, EvalError> {
         let mut value = Value::Null;
 
-        for statement in program.statements {
-            value = self.eval_statement(statement)?;
+        for (idx, statement) in program.statements.into_iter().enumerate() {
+            value = match self.eval_statement(statement) {
+                Ok(ControlFlow::Next(value)) => value,
+                Ok(ControlFlow::Return(value)) => return Ok(value),
+                Err(err) => {
+                    let position = program.positions.get(idx).copied();
+                    return self
+                        .exit
+                        .take()
+                        .map(Value::Exit)
+                        .ok_or(EvalError { position, ..err });
+                }
+            };
 
-            if let Value::Return(value) = value {
-                return Ok(*value);
+            if self.collector.should_collect() {
+                self.collector.collect();
             }
         }
         Ok(value)
     }
 
-    fn eval_statement(&mut self, statement: Statement) -> Result<Value, EvalError> {
-        match statement {
-            Statement::Expression(expression) => self.eval_expression(expression),
-            Statement::Let { name, value } => {
-                let value = self.eval_expression(value)?;
-                self.env.borrow_mut().insert(name, value.clone());
-                Ok(Value::Let)
-            }
-            Statement::Return(expression) => {
-                Ok(Value::Return(Box::new(self.eval_expression(expression)?)))
-            }
-            Statement::Block(statements) => {
-                let mut value = Value::Null;
+    /// Frees the scopes of finished calls that are only kept alive by closures they hold,
+    /// returns how many call scopes are still alive. Runs on its own between statements once
+    /// enough scopes pile up.
+    pub fn collect_garbage(&mut self) -> usize {
+        self.collector.collect()
+    }
 
-                for statement in statements {
-                    value = self.eval_statement(statement)?;
-                    if let Value::Return(_) = value {
-                        return Ok(value);
+    fn eval_statement(&mut self, statement: Statement) -> Result<ControlFlow, EvalError> {
+        self.run(vec![Work::Statement(statement)], vec![])
+    }
+
+    fn apply_function(&mut self, function: Value, args: Vec<Value>) -> Result<Value, EvalError> {
+        let mut values = vec![function];
+        let count = args.len();
+        values.extend(args);
+        self.run(vec![Work::Call(count)], values)
+            .map(ControlFlow::value)
+    }
+
+    /// Takes steps until `work` is empty, then returns the one value they left. On an error
+    /// the environments of the functions still running are unwound before returning it.
+    fn run(
+        &mut self,
+        mut work: Vec<Work>,
+        mut values: Vec<Value>,
+    ) -> Result<ControlFlow, EvalError> {
+        while let Some(step) = work.pop() {
+            let result = match self.interrupt {
+                Some(interrupt) if interrupt.load(Ordering::Relaxed) => {
+                    Err(EvalError::new("interrupted"))
+                }
+                _ => self.step(step, &mut work, &mut values),
+            };
+            match result {
+                Ok(None) => {}
+                Ok(Some(flow)) => return Ok(flow),
+                Err(err) => {
+                    for step in work.into_iter().rev() {
+                        if let Work::Leave { env, .. } = step {
+                            self.env = env;
+                            self.depth -= 1;
+                        }
                     }
+                    return Err(err);
                 }
-                Ok(value)
             }
         }
+        Ok(ControlFlow::Next(values.pop().unwrap_or(Value::Null)))
     }
 
-    fn eval_expression(&mut self, expression: Expression) -> Result<Value, EvalError> {
-        match expression {
-            Expression::Int(value) => Ok(Value::Int(value)),
-            Expression::Bool(value) => Ok(Value::Bool(value)),
-            Expression::Prefix { rhs, operator } => {
-                let rhs = self.eval_expression(*rhs)?;
-                self.eval_prefix_expression(operator, rhs)
+    /// Returns a jump that left all the steps `run` was given, like a top level `return`.
+    fn step(
+        &mut self,
+        step: Work,
+        work: &mut Vec<Work>,
+        values: &mut Vec<Value>,
+    ) -> Result<Option<ControlFlow>, EvalError> {
+        match step {
+            Work::Statement(statement) => match statement {
+                Statement::Expression(expression) => work.push(Work::Expression(expression)),
+                Statement::Let { name, value } => {
+                    work.push(Work::Let(name));
+                    work.push(Work::Expression(value));
+                }
+                Statement::Return(expression) => {
+                    work.push(Work::Return);
+                    work.push(Work::Expression(expression));
+                }
+                Statement::Block(statements) => {
+                    values.push(Value::Null);
+                    work.push(Work::Block(statements.into(), 0));
+                }
+            },
+            Work::Expression(expression) => self.expand(expression, work, values)?,
+            Work::Let(name) => {
+                let value = pop(values);
+                self.env.borrow_mut().insert(name, value);
+                values.push(Value::Let);
             }
-            Expression::Infix { lhs, operator, rhs } => {
-                let lhs = self.eval_expression(*lhs)?;
-                let rhs = self.eval_expression(*rhs)?;
-                self.eval_infix_expression(operator, lhs, rhs)
+            Work::Return => {
+                let value = pop(values);
+                loop {
+                    match work.pop() {
+                        Some(Work::Leave { env, base }) => {
+                            values.truncate(base);
+                            values.push(value);
+                            work.push(Work::Leave { env, base });
+                            break;
+                        }
+                        Some(_) => {}
+                        None => return Ok(Some(ControlFlow::Return(value))),
+                    }
+                }
             }
-            Expression::If {
-                condition,
+            Work::Block(statements, next) => {
+                let value = pop(values);
+                match statements.get(next) {
+                    Some(statement) => {
+                        let statement = statement.clone();
+                        work.push(Work::Block(statements, next + 1));
+                        work.push(Work::Statement(statement));
+                    }
+                    _ => values.push(value),
+                }
+            }
+            Work::Prefix(operator) => {
+                let rhs = pop(values);
+                values.push(self.eval_prefix_expression(operator, rhs)?);
+            }
+            Work::Infix(operator) => {
+                let rhs = pop(values);
+                let lhs = pop(values);
+                values.push(self.eval_infix_expression(operator, lhs, rhs)?);
+            }
+            Work::Branch {
                 consequence,
                 alternative,
             } => {
-                let condition = match self.eval_expression(*condition)? {
+                let condition = match pop(values) {
                     Value::Bool(value) => value,
                     Value::Int(value) => value != 0,
                     condition => {
@@ -109,18 +369,92 @@ impl Eval {
                         )))
                     }
                 };
-
-                if condition {
-                    self.eval_statement(Statement::Block(consequence))
-                } else {
-                    alternative.map_or(Ok(Value::Null), |statements| {
-                        self.eval_statement(Statement::Block(statements))
-                    })
+                match (condition, alternative) {
+                    (true, _) => {
+                        values.push(Value::Null);
+                        work.push(Work::Block(consequence, 0));
+                    }
+                    (false, Some(alternative)) => {
+                        values.push(Value::Null);
+                        work.push(Work::Block(alternative, 0));
+                    }
+                    (false, None) => values.push(Value::Null),
                 }
             }
+            Work::Call(count) => {
+                let args = values.split_off(values.len() - count);
+                let function = pop(values);
+                self.call(function, args, work, values)?;
+            }
+            Work::Leave { env, .. } => {
+                self.env = env;
+                self.depth -= 1;
+            }
+            Work::Array(count) => {
+                let elements = values.split_off(values.len() - count);
+                values.push(Value::Array(Rc::new(elements)));
+            }
+            Work::HashKey => {
+                let key = values.last().expect("the key is on the stack").clone();
+                HashKey::try_from(key).map_err(EvalError::new)?;
+            }
+            Work::Hash(count) => {
+                let mut pairs = values.split_off(values.len() - count * 2).into_iter();
+                let mut hash = HashMap::with_capacity(count);
+                while let (Some(key), Some(value)) = (pairs.next(), pairs.next()) {
+                    let key = HashKey::try_from(key).map_err(EvalError::new)?;
+                    hash.insert(key, value);
+                }
+                values.push(Value::Hash(Rc::new(hash)));
+            }
+            Work::Index => {
+                let index = pop(values);
+                let lhs = pop(values);
+                values.push(self.eval_index_expression(lhs, index)?);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Replaces an expression with the steps for its operands followed by the one that
+    /// combines them, operands run left to right.
+    fn expand(
+        &mut self,
+        expression: Expression,
+        work: &mut Vec<Work>,
+        values: &mut Vec<Value>,
+    ) -> Result<(), EvalError> {
+        match expression {
+            Expression::Int(value) => values.push(Value::Int(value)),
+            Expression::Bool(value) => values.push(Value::Bool(value)),
+            Expression::String(string) => values.push(Value::String(string.into())),
+            Expression::Identifier(name) => match self.env.borrow_mut().get(&name) {
+                Some(value) => values.push(value.clone()),
+                None => return Err(EvalError::new(format!("identifier not found: {}", name))),
+            },
+            Expression::Prefix { rhs, operator } => {
+                work.push(Work::Prefix(operator));
+                work.push(Work::Expression(*rhs));
+            }
+            Expression::Infix { lhs, operator, rhs } => {
+                work.push(Work::Infix(operator));
+                work.push(Work::Expression(*rhs));
+                work.push(Work::Expression(*lhs));
+            }
+            Expression::If {
+                condition,
+                consequence,
+                alternative,
+            } => {
+                work.push(Work::Branch {
+                    consequence,
+                    alternative,
+                });
+                work.push(Work::Expression(*condition));
+            }
             Expression::Fn {
                 parameters, body, ..
-            } => Ok(Value::Function {
+            } => values.push(Value::Function {
                 parameters,
                 body,
                 env: Rc::clone(&self.env),
@@ -129,102 +463,111 @@ impl Eval {
                 function,
                 arguments,
             } => {
-                let evaluated = self.eval_expression(*function)?;
-                let args = arguments
-                    .iter()
-                    .map(|arg| self.eval_expression(arg.clone()))
-                    .collect::<Result<Vec<_>, EvalError>>()?;
-                let (parameters, body, env) = match evaluated {
-                    Value::Function {
-                        parameters,
-                        body,
-                        env,
-                    } => (parameters, body, env),
-                    Value::Builtin(f) => {
-                        return f(args).map_err(EvalError::new);
-                    }
-                    evaluated => {
-                        return Err(EvalError::new(format!(
-                            "not a function: {}",
-                            evaluated.as_type()
-                        )))
-                    }
-                };
-                if args.len() != parameters.len() {
-                    return Err(EvalError::new(format!(
-                        "expected parameters: {parameters:?}, got: {args:?}",
-                    )));
-                }
-
-                let current_env = Rc::clone(&self.env);
-                let mut local_env = Environment::new_with_outer(Rc::clone(&env));
-
-                parameters
-                    .iter()
-                    .zip(args.iter())
-                    .for_each(|(name, value)| local_env.insert(name, value.clone()));
-                self.env = Rc::new(RefCell::new(local_env));
-                let value = self.eval_statement(Statement::Block(body));
-                self.env = current_env;
-                value
+                work.push(Work::Call(arguments.len()));
+                work.extend(arguments.into_iter().rev().map(Work::Expression));
+                work.push(Work::Expression(*function));
+            }
+            Expression::Array(elements) => {
+                work.push(Work::Array(elements.len()));
+                work.extend(elements.into_iter().rev().map(Work::Expression));
             }
-            Expression::Identifier(name) => match self.env.borrow_mut().get(&name) {
-                Some(value) => Ok(value.clone()),
-                None => Err(EvalError::new(format!("identifier not found: {}", name))),
-            },
-            Expression::String(string) => Ok(Value::String(string)),
-            Expression::Array(elements) => Ok(Value::Array(
-                elements
-                    .iter()
-                    .map(|element| self.eval_expression(element.clone()))
-                    .collect::<Result<Vec<_>, _>>()?,
-            )),
             Expression::Index { lhs, index } => {
-                let lhs = self.eval_expression(*lhs)?;
-                let index = self.eval_expression(*index)?;
-
-                self.eval_index_expression(lhs, index)
+                work.push(Work::Index);
+                work.push(Work::Expression(*index));
+                work.push(Work::Expression(*lhs));
             }
             Expression::Hash(pairs) => {
-                #[allow(clippy::mutable_key_type)]
-                let hash = pairs
-                    .iter()
-                    .map(|(k, v)| {
-                        let key = self.eval_expression(k.clone())?;
-                        let value = self.eval_expression(v.clone())?;
-                        Ok((key, value))
-                    })
-                    .collect::<Result<HashMap<_, _>, EvalError>>()?;
-                Ok(Value::Hash(hash))
+                work.push(Work::Hash(pairs.len()));
+                for (key, value) in pairs.into_iter().rev() {
+                    work.push(Work::Expression(value));
+                    work.push(Work::HashKey);
+                    work.push(Work::Expression(key));
+                }
             }
         }
+        Ok(())
+    }
+
+    /// Builtins run right away, a Monkey function pushes its body and the step that leaves it.
+    fn call(
+        &mut self,
+        function: Value,
+        args: Vec<Value>,
+        work: &mut Vec<Work>,
+        values: &mut Vec<Value>,
+    ) -> Result<(), EvalError> {
+        let (parameters, body, env) = match function {
+            Value::Function {
+                parameters,
+                body,
+                env,
+            } => (parameters, body, env),
+            Value::Builtin(builtin) => {
+                values.push(builtin.call(args, self).map_err(EvalError::new)?);
+                return Ok(());
+            }
+            Value::Memoized(memo) => {
+                values.push(memo.call(args, self).map_err(EvalError::new)?);
+                return Ok(());
+            }
+            function => {
+                return Err(EvalError::new(format!(
+                    "not a function: {}",
+                    function.as_type()
+                )))
+            }
+        };
+        if args.len() != parameters.len() {
+            return Err(EvalError::new(format!(
+                "expected parameters: {parameters:?}, got: {args:?}",
+            )));
+        }
+        if self.depth >= self.max_depth {
+            return Err(EvalError::new("maximum recursion depth exceeded"));
+        }
+
+        let mut local_env = Environment::new_with_outer(env);
+        parameters
+            .iter()
+            .zip(args)
+            .for_each(|(name, value)| local_env.insert(name, value));
+        let local_env = Rc::new(RefCell::new(local_env));
+        self.collector.track(&local_env);
+        let caller_env = std::mem::replace(&mut self.env, local_env);
+        self.depth += 1;
+        work.push(Work::Leave {
+            env: caller_env,
+            base: values.len(),
+        });
+        values.push(Value::Null);
+        work.push(Work::Block(body, 0));
+        Ok(())
     }
 
     fn eval_index_expression(&mut self, lhs: Value, index: Value) -> Result<Value, EvalError> {
         match (lhs, index) {
             (Value::Array(array), Value::Int(idx)) => self.eval_array_index_expression(array, idx),
-            (Value::Hash(lhs), index) => match index {
-                Value::Bool(_) | Value::Int(_) | Value::String(_) => {
-                    self.eval_hash_index_expression(lhs, index)
-                }
-                _ => Err(EvalError::new(format!(
-                    "unusable as hash key: {}",
-                    index.as_type()
-                ))),
-            },
+            (Value::String(string), Value::Int(idx)) => usize::try_from(idx)
+                .ok()
+                .and_then(|idx| string.chars().nth(idx))
+                .map(|char| Value::String(char.to_string().into()))
+                .ok_or_else(|| EvalError::new("index out of bounds")),
+            (Value::Hash(lhs), index) => {
+                let key = HashKey::try_from(index).map_err(EvalError::new)?;
+                self.eval_hash_index_expression(lhs, key)
+            }
             (lhs, _) => Err(EvalError::new(format!(
                 "index operator not supported: {lhs}"
             ))),
         }
     }
 
-    #[allow(clippy::mutable_key_type)]
     fn eval_hash_index_expression(
         &mut self,
-        #[allow(clippy::mutable_key_type)] lhs: HashMap<Value, Value>,
-        idx: Value,
+        lhs: Rc<HashMap<HashKey, Value>>,
+        key: HashKey,
     ) -> Result<Value, EvalError> {
-        Ok(match lhs.get(&idx) {
+        Ok(match lhs.get(&key) {
             Some(value) => value.clone(),
             None => Value::Null,
         })
@@ -232,7 +575,7 @@ impl Eval {
 
     fn eval_array_index_expression(
         &mut self,
-        array: Vec<Value>,
+        array: Rc<Vec<Value>>,
         index: i64,
     ) -> Result<Value, EvalError> {
         if index < 0 || index > (array.len() - 1) as i64 {
@@ -287,7 +630,7 @@ impl Eval {
     ) -> Result<Value, EvalError> {
         match (lhs, rhs) {
             (Value::Int(lhs), Value::Int(rhs)) => {
-                Ok(self.eval_int_infix_expression(operator, lhs, rhs))
+                self.eval_int_infix_expression(operator, lhs, rhs)
             }
             (Value::Bool(lhs), Value::Bool(rhs)) => match operator {
                 InfixOperator::Equal => Ok(Value::Bool(lhs == rhs)),
@@ -297,7 +640,7 @@ impl Eval {
                 ))),
             },
             (Value::String(lhs), Value::String(rhs)) => match operator {
-                InfixOperator::Add => Ok(Value::String(lhs + &rhs)),
+                InfixOperator::Add => Ok(Value::String(format!("{lhs}{rhs}").into())),
                 _ => Err(EvalError::new(format!(
                     "unknown operator: STRING {operator} STRING"
                 ))),
@@ -310,19 +653,27 @@ impl Eval {
         }
     }
 
-    fn eval_int_infix_expression(&self, operator: InfixOperator, lhs: i64, rhs: i64) -> Value {
-        match operator {
+    fn eval_int_infix_expression(
+        &self,
+        operator: InfixOperator,
+        lhs: i64,
+        rhs: i64,
+    ) -> Result<Value, EvalError> {
+        Ok(match operator {
+            InfixOperator::Div | InfixOperator::Modulo if rhs == 0 => {
+                return Err(EvalError::new("division by zero"))
+            }
             InfixOperator::Add => Value::Int(lhs + rhs),
             InfixOperator::Sub => Value::Int(lhs - rhs),
             InfixOperator::Mul => Value::Int(lhs * rhs),
-            InfixOperator::Div => Value::Int(lhs / rhs),
+            InfixOperator::Div => Value::Int(lhs.wrapping_div(rhs)),
             InfixOperator::Equal => Value::Bool(lhs == rhs),
             InfixOperator::NotEqual => Value::Bool(lhs != rhs),
             InfixOperator::GreaterThan => Value::Bool(lhs > rhs),
             InfixOperator::LessThan => Value::Bool(lhs < rhs),
-            InfixOperator::Modulo => Value::Int(lhs % rhs),
+            InfixOperator::Modulo => Value::Int(lhs.wrapping_rem(rhs)),
             InfixOperator::GreaterThanOrEqual => Value::Bool(lhs >= rhs),
             InfixOperator::LessThanOrEqual => Value::Bool(lhs <= rhs),
-        }
+        })
     }
 }
@@ -2,13 +2,14 @@ use core::panic;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::lexer::Lexer;
 use crate::parser::Parser;
 
 use super::builtin::new_builtins;
 use super::environment::Environment;
-use super::value::Value;
+use super::value::{HashKey, Value};
 use super::Eval;
 
 #[test]
@@ -32,7 +33,7 @@ fn test_eval_int_expression() {
     ];
 
     tests_cases.iter().for_each(|(input, expected)| {
-        let lexer = Lexer::new(input.chars().collect());
+        let lexer = Lexer::from(*input);
         let mut parser = Parser::new(lexer);
         let program = parser.parse_program();
         let env = Environment::new();
@@ -69,7 +70,7 @@ fn test_eval_bool_expression() {
     ];
 
     tests_cases.iter().for_each(|(input, expected)| {
-        let lexer = Lexer::new(input.chars().collect());
+        let lexer = Lexer::from(*input);
         let mut parser = Parser::new(lexer);
         let program = parser.parse_program();
         let env = Environment::new();
@@ -85,13 +86,13 @@ fn test_eval_bool_expression() {
 fn test_string_expression() {
     let tests_cases = [(r#""foobar""#, "foobar"), (r#""foo bar""#, "foo bar")];
     tests_cases.iter().for_each(|(input, expected)| {
-        let lexer = Lexer::new(input.chars().collect());
+        let lexer = Lexer::from(*input);
         let mut parser = Parser::new(lexer);
         let program = parser.parse_program();
         let env = Environment::new();
         let mut eval = Eval::new(Rc::new(RefCell::new(env)));
         match eval.eval_program(program) {
-            Ok(Value::String(string)) => assert_eq!(string, expected.to_string()),
+            Ok(Value::String(string)) => assert_eq!(&*string, *expected),
             value => panic!("evaluated expected {expected}, got {value:?}"),
         }
     })
@@ -101,13 +102,13 @@ fn test_string_expression() {
 fn test_string_concatenation() {
     let tests_cases = [(r#""Hello" + " " + "World!""#, "Hello World!")];
     tests_cases.iter().for_each(|(input, expected)| {
-        let lexer = Lexer::new(input.chars().collect());
+        let lexer = Lexer::from(*input);
         let mut parser = Parser::new(lexer);
         let program = parser.parse_program();
         let env = Environment::new();
         let mut eval = Eval::new(Rc::new(RefCell::new(env)));
         match eval.eval_program(program) {
-            Ok(Value::String(string)) => assert_eq!(string, expected.to_string()),
+            Ok(Value::String(string)) => assert_eq!(&*string, *expected),
             value => panic!("evaluated expected {expected}, got {value:?}"),
         }
     })
@@ -124,7 +125,7 @@ fn test_eval_bang_operator() {
         ("!!5", true),
     ];
     tests_cases.iter().for_each(|(input, expected)| {
-        let lexer = Lexer::new(input.chars().collect());
+        let lexer = Lexer::from(*input);
         let mut parser = Parser::new(lexer);
         let program = parser.parse_program();
         let env = Environment::new();
@@ -149,7 +150,7 @@ fn test_eval_if_else_expression() {
     ];
 
     tests_cases.iter().for_each(|(input, expected)| {
-        let lexer = Lexer::new(input.chars().collect());
+        let lexer = Lexer::from(*input);
         let mut parser = Parser::new(lexer);
         let program = parser.parse_program();
         let env = Environment::new();
@@ -176,10 +177,18 @@ fn test_eval_return_statement() {
             "if (10 > 1) { if (10 < 1) { 8; 9; return 10; } 1; 2; return 3; } return 4;",
             3,
         ),
+        (
+            "let f = fn(x) { let y = 1 + if (x) { return 10; } else { 2 }; y * 100 }; f(true) + f(false)",
+            310,
+        ),
+        (
+            "let f = fn(x) { [x, if (x) { return 2; } else { 3 }] }; f(false)[1] + f(true)",
+            5,
+        ),
     ];
 
     tests_cases.iter().for_each(|(input, expected)| {
-        let lexer = Lexer::new(input.chars().collect());
+        let lexer = Lexer::from(*input);
         let mut parser = Parser::new(lexer);
         let program = parser.parse_program();
         let env = Environment::new();
@@ -217,16 +226,19 @@ fn test_eval_error_handling() {
         ),
         ("foobar", "identifier not found: foobar"),
         (r#""Hello" - "World""#, "unknown operator: STRING - STRING"),
+        ("1 / 0", "division by zero"),
+        ("let a = 0; 10 % a", "division by zero"),
         ("[1, 2, 3][3]", "index out of bounds"),
         ("[1, 2, 3][-1]", "index out of bounds"),
         (
             r#"{"name": "Monkey"}[fn(x) { x }];"#,
             "unusable as hash key: FUNCTION",
         ),
+        ("{[1]: 2}", "unusable as hash key: ARRAY"),
     ];
 
     tests_cases.iter().for_each(|(input, expected)| {
-        let lexer = Lexer::new(input.chars().collect());
+        let lexer = Lexer::from(*input);
         let mut parser = Parser::new(lexer);
         let program = parser.parse_program();
         let env = Environment::from(new_builtins());
@@ -239,6 +251,26 @@ fn test_eval_error_handling() {
     });
 }
 
+#[test]
+fn test_eval_error_position() {
+    let tests_cases = [
+        ("foo", Some((1, 1))),
+        ("let x = 1;\n\nlet y = 2; x + foo;", Some((3, 12))),
+        ("let f = fn() {\n  1 + true\n};\nf()", Some((4, 1))),
+    ];
+
+    tests_cases.iter().for_each(|(input, expected)| {
+        let mut parser = Parser::new(Lexer::from(*input));
+        let program = parser.parse_program();
+        let env = Environment::from(new_builtins());
+        let mut eval = Eval::new(Rc::new(RefCell::new(env)));
+        match eval.eval_program(program) {
+            Err(err) => assert_eq!(err.position(), *expected),
+            unexpected => panic!("expected an error, got: {unexpected:?}"),
+        }
+    });
+}
+
 #[test]
 fn test_eval_let_statement() {
     let tests_cases = [
@@ -248,7 +280,7 @@ fn test_eval_let_statement() {
         ("let a = 5; let b = a; let c = a + b + 5; c;", 15),
     ];
     tests_cases.iter().for_each(|(input, expected)| {
-        let lexer = Lexer::new(input.chars().collect());
+        let lexer = Lexer::from(*input);
         let mut parser = Parser::new(lexer);
         let program = parser.parse_program();
         let env = Environment::new();
@@ -263,7 +295,7 @@ fn test_eval_let_statement() {
 #[test]
 fn test_eval_function() {
     let input = "fn(x) { x + 2; };";
-    let lexer = Lexer::new(input.chars().collect());
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
     let program = parser.parse_program();
     let env = Environment::new();
@@ -299,7 +331,7 @@ fn test_eval_function_call() {
     ];
 
     tests_cases.iter().for_each(|(input, expected)| {
-        let lexer = Lexer::new(input.chars().collect());
+        let lexer = Lexer::from(*input);
         let mut parser = Parser::new(lexer);
         let program = parser.parse_program();
         let env = Environment::new();
@@ -317,10 +349,12 @@ fn test_builtin_functions() {
         (r#"len("")"#, 0),
         (r#"len("four")"#, 4),
         (r#"len("hello world")"#, 11),
+        (r#"len("héllo wörld")"#, 11),
+        (r#"len("日本")"#, 2),
     ];
 
     tests_cases.iter().for_each(|(input, expected)| {
-        let lexer = Lexer::new(input.chars().collect());
+        let lexer = Lexer::from(*input);
         let mut parser = Parser::new(lexer);
         let program = parser.parse_program();
         let env = Environment::from(new_builtins());
@@ -332,10 +366,276 @@ fn test_builtin_functions() {
     });
 }
 
+#[test]
+fn test_higher_order_builtins() {
+    let tests_cases = [
+        (
+            "map([1, 2, 3], fn(x) { x * 2 })",
+            Value::Array(Rc::new(vec![Value::Int(2), Value::Int(4), Value::Int(6)])),
+        ),
+        (
+            "let n = 10; map([1, 2], fn(x) { return x + n; })",
+            Value::Array(Rc::new(vec![Value::Int(11), Value::Int(12)])),
+        ),
+        (
+            "filter([1, 2, 3, 4], fn(x) { x > 2 })",
+            Value::Array(Rc::new(vec![Value::Int(3), Value::Int(4)])),
+        ),
+        (
+            "reduce([1, 2, 3, 4], 0, fn(acc, x) { acc + x })",
+            Value::Int(10),
+        ),
+        (
+            "let sum = fn(arr) { reduce(arr, 0, fn(acc, x) { acc + x }) }; map([[1, 2], [3, 4]], sum)",
+            Value::Array(Rc::new(vec![Value::Int(3), Value::Int(7)])),
+        ),
+    ];
+
+    tests_cases.iter().for_each(|(input, expected)| {
+        let lexer = Lexer::from(*input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        parser.check_errors();
+        let env = Environment::from(new_builtins());
+        let mut eval = Eval::new(Rc::new(RefCell::new(env)));
+        match eval.eval_program(program) {
+            Ok(value) => assert_eq!(value, *expected),
+            unexpected => panic!("got an error: {unexpected:?}"),
+        }
+    });
+}
+
+#[test]
+fn test_string_builtins() {
+    let tests_cases = [
+        (
+            r#"split("a,b", ",")"#,
+            Value::Array(Rc::new(vec![Value::from("a"), Value::from("b")])),
+        ),
+        (r#"join(["a", "b"], ", ")"#, Value::from("a, b")),
+        (r#"upper(trim(" monkey "))"#, Value::from("MONKEY")),
+        (r#"replace("Monkey", "M", "D")"#, Value::from("Donkey")),
+        (r#"starts_with(lower("Monkey"), "mon")"#, Value::Bool(true)),
+    ];
+
+    tests_cases.iter().for_each(|(input, expected)| {
+        let lexer = Lexer::from(*input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        parser.check_errors();
+        let env = Environment::from(new_builtins());
+        let mut eval = Eval::new(Rc::new(RefCell::new(env)));
+        match eval.eval_program(program) {
+            Ok(value) => assert_eq!(value, *expected),
+            unexpected => panic!("got an error: {unexpected:?}"),
+        }
+    });
+}
+
+#[test]
+fn test_hash_builtins() {
+    let tests_cases = [
+        (
+            "keys({2: 1, 1: 2})",
+            Value::Array(Rc::new(vec![Value::Int(1), Value::Int(2)])),
+        ),
+        (
+            "values({2: 1, 1: 2})",
+            Value::Array(Rc::new(vec![Value::Int(2), Value::Int(1)])),
+        ),
+        ("len(keys(delete({1: 1, 2: 2}, 1)))", Value::Int(1)),
+        ("has_key({true: 1}, true)", Value::Bool(true)),
+        ("contains({true: 1}, true)", Value::Bool(true)),
+        ("contains([1, 2], 3)", Value::Bool(false)),
+        (r#"index_of("monkey", "key")"#, Value::Int(3)),
+    ];
+
+    tests_cases.iter().for_each(|(input, expected)| {
+        let lexer = Lexer::from(*input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        parser.check_errors();
+        let env = Environment::from(new_builtins());
+        let mut eval = Eval::new(Rc::new(RefCell::new(env)));
+        match eval.eval_program(program) {
+            Ok(value) => assert_eq!(value, *expected),
+            unexpected => panic!("got an error: {unexpected:?}"),
+        }
+    });
+}
+
+#[test]
+fn test_conversion_builtins() {
+    let tests_cases = [
+        (r#"int("12")"#, Value::Int(12)),
+        ("str(42)", Value::from("42")),
+        (r#"bool("false")"#, Value::Bool(false)),
+        (r#"int(str(7)) + 1"#, Value::Int(8)),
+    ];
+
+    tests_cases.iter().for_each(|(input, expected)| {
+        let lexer = Lexer::from(*input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        parser.check_errors();
+        let env = Environment::from(new_builtins());
+        let mut eval = Eval::new(Rc::new(RefCell::new(env)));
+        match eval.eval_program(program) {
+            Ok(value) => assert_eq!(value, *expected),
+            unexpected => panic!("got an error: {unexpected:?}"),
+        }
+    });
+}
+
+#[test]
+fn test_math_builtins() {
+    let tests_cases = [
+        ("abs(-3)", Value::Int(3)),
+        ("min(4, 2, 8)", Value::Int(2)),
+        ("max(4, 2, 8)", Value::Int(8)),
+        ("pow(3, 3)", Value::Int(27)),
+        ("sqrt(16) + floor(1) + ceil(1)", Value::Int(6)),
+    ];
+
+    tests_cases.iter().for_each(|(input, expected)| {
+        let lexer = Lexer::from(*input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        parser.check_errors();
+        let env = Environment::from(new_builtins());
+        let mut eval = Eval::new(Rc::new(RefCell::new(env)));
+        match eval.eval_program(program) {
+            Ok(value) => assert_eq!(value, *expected),
+            unexpected => panic!("got an error: {unexpected:?}"),
+        }
+    });
+}
+
+#[test]
+fn test_random_builtins() {
+    let tests_cases = [
+        (
+            "seed(3); let a = rand_int(0, 1000); seed(3); a == rand_int(0, 1000)",
+            Value::Bool(true),
+        ),
+        ("rand_int(-1, 0)", Value::Int(-1)),
+    ];
+
+    tests_cases.iter().for_each(|(input, expected)| {
+        let lexer = Lexer::from(*input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        parser.check_errors();
+        let env = Environment::from(new_builtins());
+        let mut eval = Eval::new(Rc::new(RefCell::new(env)));
+        match eval.eval_program(program) {
+            Ok(value) => assert_eq!(value, *expected),
+            unexpected => panic!("got an error: {unexpected:?}"),
+        }
+    });
+}
+
+#[test]
+fn test_range_builtin() {
+    let tests_cases = [
+        ("len(range(10))", Value::Int(10)),
+        (
+            "range(3, 0, -1)",
+            Value::Array(Rc::new(vec![Value::Int(3), Value::Int(2), Value::Int(1)])),
+        ),
+    ];
+
+    tests_cases.iter().for_each(|(input, expected)| {
+        let lexer = Lexer::from(*input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        parser.check_errors();
+        let env = Environment::from(new_builtins());
+        let mut eval = Eval::new(Rc::new(RefCell::new(env)));
+        match eval.eval_program(program) {
+            Ok(value) => assert_eq!(value, *expected),
+            unexpected => panic!("got an error: {unexpected:?}"),
+        }
+    });
+}
+
+#[test]
+fn test_format_builtin() {
+    let tests_cases = [
+        (
+            r#"format("{} + {} = {}", 1, 2, 1 + 2)"#,
+            Value::from("1 + 2 = 3"),
+        ),
+        (r#"format("{}", "a")"#, Value::from("a")),
+    ];
+
+    tests_cases.iter().for_each(|(input, expected)| {
+        let lexer = Lexer::from(*input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        parser.check_errors();
+        let env = Environment::from(new_builtins());
+        let mut eval = Eval::new(Rc::new(RefCell::new(env)));
+        match eval.eval_program(program) {
+            Ok(value) => assert_eq!(value, *expected),
+            unexpected => panic!("got an error: {unexpected:?}"),
+        }
+    });
+}
+
+#[test]
+fn test_exit_builtin() {
+    let tests_cases = [
+        ("exit(3); 1", Value::Exit(3)),
+        ("let f = fn() { exit(4); 1 }; f() + 1", Value::Exit(4)),
+        ("map([1], fn(x) { exit(x) })", Value::Exit(1)),
+    ];
+
+    tests_cases.iter().for_each(|(input, expected)| {
+        let lexer = Lexer::from(*input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        parser.check_errors();
+        let env = Environment::from(new_builtins());
+        let mut eval = Eval::new(Rc::new(RefCell::new(env)));
+        match eval.eval_program(program) {
+            Ok(value) => assert_eq!(value, *expected),
+            unexpected => panic!("got an error: {unexpected:?}"),
+        }
+    });
+}
+
+#[test]
+fn test_eval_builtin() {
+    let tests_cases = [
+        (r#"eval("1 + 2")"#, Value::Int(3)),
+        (r#"let a = 5; eval("a * 2")"#, Value::Int(10)),
+        (r#"eval("let b = 4"); eval("b + 1")"#, Value::Int(5)),
+        (r#"eval("return 7; 8")"#, Value::Int(7)),
+        (
+            r#"let f = fn() { let a = 1; eval("a") }; let a = 2; f()"#,
+            Value::Int(2),
+        ),
+    ];
+
+    tests_cases.iter().for_each(|(input, expected)| {
+        let lexer = Lexer::from(*input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        parser.check_errors();
+        let env = Environment::from(new_builtins());
+        let mut eval = Eval::new(Rc::new(RefCell::new(env)));
+        match eval.eval_program(program) {
+            Ok(value) => assert_eq!(value, *expected),
+            unexpected => panic!("got an error: {unexpected:?}"),
+        }
+    });
+}
+
 #[test]
 fn test_eval_array() {
     let input = "[1, 2 * 2, 3 + 3]";
-    let lexer = Lexer::new(input.chars().collect());
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
     let program = parser.parse_program();
     let env = Environment::new();
@@ -370,7 +670,7 @@ fn test_eval_arrray_index_expression() {
     ];
 
     tests_cases.iter().for_each(|(input, expected)| {
-        let lexer = Lexer::new(input.chars().collect());
+        let lexer = Lexer::from(*input);
         let mut parser = Parser::new(lexer);
         let program = parser.parse_program();
         let env = Environment::from(new_builtins());
@@ -382,27 +682,50 @@ fn test_eval_arrray_index_expression() {
     });
 }
 
-#[allow(clippy::mutable_key_type)]
+#[test]
+fn test_eval_string_index_expression() {
+    let tests_cases = [
+        (r#""héllo"[1]"#, Ok("é")),
+        (r#""日本語"[2]"#, Ok("語")),
+        (r#"slice("héllo", 1, 3)"#, Ok("él")),
+        (r#""héllo"[5]"#, Err("index out of bounds")),
+        (r#""héllo"[-1]"#, Err("index out of bounds")),
+    ];
+
+    tests_cases.iter().for_each(|(input, expected)| {
+        let lexer = Lexer::from(*input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        let env = Environment::from(new_builtins());
+        let mut eval = Eval::new(Rc::new(RefCell::new(env)));
+        match (eval.eval_program(program), expected) {
+            (Ok(Value::String(value)), Ok(expected)) => assert_eq!(value.as_ref(), *expected),
+            (Err(err), Err(expected)) => assert_eq!(err.to_string(), *expected),
+            (unexpected, _) => panic!("unexpected result for {input}: {unexpected:?}"),
+        }
+    });
+}
+
 #[test]
 fn test_hash_literals() {
     let input = r#"let two = "two";
 {"one": 10 - 9, two: 1 + 1, "thr" + "ee": 6 / 2, 4: 4, true: 5, false: 6}"#;
 
     let mut expected = HashMap::new();
-    expected.insert(Value::String("one".into()), Value::Int(1));
-    expected.insert(Value::String("two".into()), Value::Int(2));
-    expected.insert(Value::String("three".into()), Value::Int(3));
-    expected.insert(Value::Int(4), Value::Int(4));
-    expected.insert(Value::Bool(true), Value::Int(5));
-    expected.insert(Value::Bool(false), Value::Int(6));
-
-    let lexer = Lexer::new(input.chars().collect());
+    expected.insert(HashKey::from("one"), Value::Int(1));
+    expected.insert(HashKey::from("two"), Value::Int(2));
+    expected.insert(HashKey::from("three"), Value::Int(3));
+    expected.insert(HashKey::Int(4), Value::Int(4));
+    expected.insert(HashKey::Bool(true), Value::Int(5));
+    expected.insert(HashKey::Bool(false), Value::Int(6));
+
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
     let program = parser.parse_program();
     let env = Environment::from(new_builtins());
     let mut eval = Eval::new(Rc::new(RefCell::new(env)));
     match eval.eval_program(program) {
-        Ok(Value::Hash(value)) => assert_eq!(value, expected),
+        Ok(Value::Hash(value)) => assert_eq!(*value, expected),
         unexpected => panic!("got an error: {unexpected:?}"),
     }
 }
@@ -419,7 +742,7 @@ fn test_hash_index_expression() {
         (r#"{false: 5}[false]"#, Value::Int(5)),
     ];
     tests_cases.iter().for_each(|(input, expected)| {
-        let lexer = Lexer::new(input.chars().collect());
+        let lexer = Lexer::from(*input);
         let mut parser = Parser::new(lexer);
         let program = parser.parse_program();
         let env = Environment::from(new_builtins());
@@ -430,3 +753,176 @@ fn test_hash_index_expression() {
         }
     });
 }
+
+#[test]
+fn test_max_depth() {
+    let input = "let f = fn(x) { if (x == 0) { 0 } else { 1 + f(x - 1) } };";
+    let tests_cases = [
+        ("f(9)", Ok(9)),
+        ("f(10)", Err("maximum recursion depth exceeded")),
+        (
+            "map([1], fn(x) { f(9) })",
+            Err("maximum recursion depth exceeded"),
+        ),
+        ("f(10); 1", Err("maximum recursion depth exceeded")),
+    ];
+
+    tests_cases.iter().for_each(|(call, expected)| {
+        let source = format!("{input} {call}");
+        let program = Parser::new(Lexer::from(source.as_str())).parse_program();
+        let env = Environment::from(new_builtins());
+        let mut eval = Eval::new(Rc::new(RefCell::new(env))).with_max_depth(10);
+        match (eval.eval_program(program), expected) {
+            (Ok(Value::Int(value)), Ok(expected)) => assert_eq!(value, *expected),
+            (Err(err), Err(expected)) => assert_eq!(err.msg, *expected),
+            (unexpected, _) => panic!("unexpected result for {call}: {unexpected:?}"),
+        }
+        assert_eq!(eval.depth, 0);
+    });
+}
+
+#[test]
+fn test_interrupt() {
+    static INTERRUPT: AtomicBool = AtomicBool::new(false);
+    let source = "let f = fn(x) { f(x + 1) }; f(1)";
+    let program = Parser::new(Lexer::from(source)).parse_program();
+    let env = Environment::from(new_builtins());
+    let mut eval = Eval::new(Rc::new(RefCell::new(env))).with_interrupt(&INTERRUPT);
+    INTERRUPT.store(true, Ordering::Relaxed);
+    match eval.eval_program(program) {
+        Err(err) => assert_eq!(err.msg, "interrupted"),
+        unexpected => panic!("expected the evaluation to be interrupted: {unexpected:?}"),
+    }
+    assert_eq!(eval.depth, 0);
+}
+
+#[test]
+fn test_deep_recursion() {
+    let tests_cases = [
+        (
+            "let f = fn(x) { if (x == 0) { 0 } else { 1 + f(x - 1) } }; f(5000)",
+            5000,
+        ),
+        (
+            "let f = fn(x) { if (x == 0) { return 0; } return f(x - 1) + 2; }; f(3) + 1",
+            7,
+        ),
+    ];
+
+    tests_cases.iter().for_each(|(input, expected)| {
+        let program = Parser::new(Lexer::from(*input)).parse_program();
+        let mut eval = Eval::new(Rc::new(RefCell::new(Environment::new())));
+        match eval.eval_program(program) {
+            Ok(Value::Int(value)) => assert_eq!(value, *expected),
+            unexpected => panic!("got an error: {unexpected:?}"),
+        }
+    });
+}
+
+#[test]
+fn test_error_unwinds_calls() {
+    let env = Rc::new(RefCell::new(Environment::from(new_builtins())));
+    let mut eval = Eval::new(Rc::clone(&env));
+
+    let program = Parser::new(Lexer::from(
+        "let x = 1; let f = fn(x) { if (x == 0) { x + true } else { f(x - 1) } }; f(3)",
+    ))
+    .parse_program();
+    assert!(eval.eval_program(program).is_err());
+    assert!(Rc::ptr_eq(&eval.env, &env));
+    assert_eq!(eval.depth, 0);
+
+    let program = Parser::new(Lexer::from("x")).parse_program();
+    match eval.eval_program(program) {
+        Ok(Value::Int(value)) => assert_eq!(value, 1),
+        unexpected => panic!("got an error: {unexpected:?}"),
+    }
+}
+
+#[test]
+fn test_environment_bindings() {
+    let global = Rc::new(RefCell::new(Environment::new()));
+    global.borrow_mut().insert("x", Value::Int(1));
+    global.borrow_mut().insert("y", Value::Int(2));
+    let mut local = Environment::new_with_outer(Rc::clone(&global));
+    local.insert("x", Value::Int(3));
+
+    let mut bindings = local.bindings().collect::<Vec<_>>();
+    assert_eq!(bindings.remove(0), ("x".into(), Value::Int(3)));
+    bindings.sort_by(|(a, _), (b, _)| a.cmp(b));
+    assert_eq!(
+        bindings,
+        vec![("x".into(), Value::Int(1)), ("y".into(), Value::Int(2))]
+    );
+
+    let flat = local.flatten();
+    assert_eq!(flat.outer, None);
+    assert_eq!(
+        flat.store,
+        HashMap::from([("x".into(), Value::Int(3)), ("y".into(), Value::Int(2))])
+    );
+}
+
+#[test]
+fn test_environment_rollback() {
+    let env = Rc::new(RefCell::new(Environment::new()));
+    let mut eval = Eval::new(Rc::clone(&env));
+    eval.eval_program(Parser::new(Lexer::from("let x = 1;")).parse_program())
+        .unwrap();
+
+    let checkpoint = env.borrow().checkpoint();
+    let program = Parser::new(Lexer::from("let x = 2; let y = 3; y + true;")).parse_program();
+    assert!(eval.eval_program(program).is_err());
+    assert_eq!(env.borrow_mut().get("y"), Some(Value::Int(3)));
+
+    env.borrow_mut().rollback(checkpoint);
+    assert_eq!(env.borrow_mut().get("x"), Some(Value::Int(1)));
+    assert_eq!(env.borrow_mut().get("y"), None);
+}
+
+#[test]
+fn test_collect_closure_cycles() {
+    let input = "
+let leak = fn() { let f = fn() { f() }; 1 };
+let adder = fn(x) { let add = fn(y) { add; x + y }; add };
+leak();
+leak();
+let addTwo = adder(2);
+";
+    let mut eval = Eval::new(Rc::new(RefCell::new(Environment::new())));
+    eval.eval_program(Parser::new(Lexer::from(input)).parse_program())
+        .unwrap();
+
+    assert_eq!(eval.collect_garbage(), 1);
+    let program = Parser::new(Lexer::from("addTwo(3)")).parse_program();
+    assert_eq!(eval.eval_program(program).unwrap(), Value::Int(5));
+
+    eval.eval_program(Parser::new(Lexer::from("let addTwo = 0;")).parse_program())
+        .unwrap();
+    assert_eq!(eval.collect_garbage(), 0);
+}
+
+#[test]
+fn test_memoize() {
+    let input = "
+let fib = memoize(fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } });
+[fib(80), memoize(fn(a) { len(a) })([1, 2]), memoize(len)(\"abc\")]";
+    let env = Environment::from(new_builtins());
+    let mut eval = Eval::new(Rc::new(RefCell::new(env)));
+    let program = Parser::new(Lexer::from(input)).parse_program();
+
+    assert_eq!(
+        eval.eval_program(program).unwrap(),
+        Value::from(vec![
+            Value::Int(23416728348467685),
+            Value::Int(2),
+            Value::Int(3),
+        ])
+    );
+
+    let program = Parser::new(Lexer::from("memoize(1)")).parse_program();
+    assert_eq!(
+        eval.eval_program(program).unwrap_err().to_string(),
+        "argument to 'memoize' must be FUNCTION, got INTEGER"
+    );
+}
@@ -1,265 +1,217 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt::Display;
-use std::hash::Hash;
 use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::ast::statement::Statement;
 use crate::code::Instructions;
 
+use super::builtin::memo::Memo;
+use super::builtin::Builtin;
 use super::environment::Environment;
 
-pub type BuiltinFuncion = fn(Vec<Value>) -> Result<Value, String>;
-
-pub enum Builtin {
-    Len,
-    First,
-    Last,
-    Rest,
-    Push,
-    Puts,
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    Int(i64),
+    Bool(bool),
+    String(Arc<str>),
+    Null,
+    Let,
+    Exit(i64),
+    Array(Rc<Vec<Value>>),
+    Function {
+        parameters: Vec<String>,
+        body: Rc<[Statement]>,
+        env: Rc<RefCell<Environment>>,
+    },
+    Builtin(&'static Builtin),
+    Hash(Rc<HashMap<HashKey, Value>>),
+    CompiledFunction {
+        instructions: Arc<Instructions>,
+        num_locals: usize,
+        num_parameters: usize,
+    },
+    Closure {
+        fun: Box<Value>,
+        free: Vec<Value>,
+    },
+    Memoized(Rc<Memo>),
 }
 
-impl TryFrom<u8> for Builtin {
-    type Error = ();
-
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            0 => Ok(Builtin::Len),
-            1 => Ok(Builtin::First),
-            2 => Ok(Builtin::Last),
-            3 => Ok(Builtin::Rest),
-            4 => Ok(Builtin::Push),
-            5 => Ok(Builtin::Puts),
-            _ => Err(()),
-        }
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
     }
 }
 
-impl TryFrom<String> for Builtin {
-    type Error = ();
-
-    fn try_from(value: String) -> Result<Self, Self::Error> {
-        match value.as_str() {
-            "len" => Ok(Builtin::Len),
-            "first" => Ok(Builtin::First),
-            "last" => Ok(Builtin::Last),
-            "rest" => Ok(Builtin::Rest),
-            "push" => Ok(Builtin::Push),
-            "puts" => Ok(Builtin::Puts),
-            _ => Err(()),
-        }
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
     }
 }
 
-impl Builtin {
-    pub fn get_builtin_fn(builtin: Builtin) -> BuiltinFuncion {
-        match builtin {
-            Builtin::Len => builtin_len,
-            Builtin::First => builtin_first,
-            Builtin::Last => builtin_last,
-            Builtin::Rest => builtin_rest,
-            Builtin::Push => builtin_push,
-            Builtin::Puts => builtin_puts,
-        }
+impl From<&'static str> for Value {
+    fn from(value: &'static str) -> Self {
+        Value::String(value.into())
     }
 }
 
-pub fn get_builtin_by_name(name: String) -> Result<Value, String> {
-    Ok(Value::Builtin(match name.as_str() {
-        "len" => builtin_len,
-        "first" => builtin_first,
-        "last" => builtin_last,
-        "rest" => builtin_rest,
-        "push" => builtin_push,
-        "puts" => builtin_puts,
-        _ => return Err("Invalid builtin name".into()),
-    }))
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value.into())
+    }
 }
 
-fn builtin_len(args: Vec<Value>) -> Result<Value, String> {
-    if args.len() != 1 {
-        return Err(format!(
-            "wrong number of arguments, got={}, want=1",
-            args.len()
-        ));
+impl From<HashMap<HashKey, Value>> for Value {
+    fn from(value: HashMap<HashKey, Value>) -> Self {
+        Value::Hash(Rc::new(value))
     }
+}
 
-    match &args[0] {
-        Value::String(arg) => Ok(Value::Int(arg.len() as i64)),
-        Value::Array(array) => Ok(Value::Int(array.len() as i64)),
-        arg => Err(format!(
-            r#"argument to "len" not supported: got {}"#,
-            arg.as_type()
-        )),
+impl<T: Into<Value> + Clone> From<Vec<T>> for Value {
+    fn from(values: Vec<T>) -> Self {
+        Value::Array(Rc::new(
+            values
+                .iter()
+                .map(|value| std::convert::Into::<Value>::into(value.clone()))
+                .collect::<Vec<Value>>(),
+        ))
     }
 }
 
-fn builtin_first(args: Vec<Value>) -> Result<Value, String> {
-    if args.len() != 1 {
-        return Err(format!(
-            "wrong number of arguments, got={}, want=1",
-            args.len()
-        ));
+impl<T: Into<Value>> From<HashMap<String, T>> for Value {
+    fn from(values: HashMap<String, T>) -> Self {
+        let values = values
+            .into_iter()
+            .map(|(key, value)| (HashKey::String(key.into()), value.into()));
+        Value::Hash(Rc::new(values.collect()))
     }
+}
 
-    match &args[0] {
-        Value::Array(array) => match array.first() {
-            Some(value) => Ok(value.clone()),
-            None => Ok(Value::Null),
-        },
-        arg => Err(format!(
-            "argument to 'first' must be ARRAY, got {}",
-            arg.as_type()
-        )),
-    }
+fn expected(kind: &str, value: &Value) -> String {
+    format!("expected {kind}, got {}", value.as_type())
 }
 
-fn builtin_last(args: Vec<Value>) -> Result<Value, String> {
-    if args.len() != 1 {
-        return Err(format!(
-            "wrong number of arguments, got={}, want=1",
-            args.len()
-        ));
-    }
+impl TryFrom<Value> for i64 {
+    type Error = String;
 
-    match &args[0] {
-        Value::Array(array) => match array.last() {
-            Some(value) => Ok(value.clone()),
-            None => Ok(Value::Null),
-        },
-        arg => Err(format!(
-            "argument to 'last' must be ARRAY, got {}",
-            arg.as_type()
-        )),
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Int(int) => Ok(int),
+            value => Err(expected("INTEGER", &value)),
+        }
     }
 }
 
-fn builtin_rest(args: Vec<Value>) -> Result<Value, String> {
-    if args.len() != 1 {
-        return Err(format!(
-            "wrong number of arguments, got={}, want=1",
-            args.len()
-        ));
-    }
+impl TryFrom<Value> for bool {
+    type Error = String;
 
-    match &args[0] {
-        Value::Array(array) => {
-            if array.is_empty() {
-                return Ok(Value::Null);
-            }
-            Ok(Value::Array(array[1..].to_vec()))
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Bool(bool) => Ok(bool),
+            value => Err(expected("BOOLEAN", &value)),
         }
-        arg => Err(format!(
-            "argument to 'rest' must be ARRAY, got {}",
-            arg.as_type()
-        )),
     }
 }
 
-fn builtin_push(args: Vec<Value>) -> Result<Value, String> {
-    if args.len() != 2 {
-        return Err(format!(
-            "wrong number of arguments, got={}, want=2",
-            args.len()
-        ));
+impl TryFrom<Value> for String {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(string) => Ok(string.to_string()),
+            value => Err(expected("STRING", &value)),
+        }
     }
+}
+
+/// Fails on the first element that doesn't convert.
+impl<T: TryFrom<Value, Error = String>> TryFrom<Value> for Vec<T> {
+    type Error = String;
 
-    match &args[0] {
-        Value::Array(array) => {
-            let mut new_array = array.clone();
-            new_array.push(args[1].clone());
-            Ok(Value::Array(new_array))
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Array(array) => array.iter().cloned().map(T::try_from).collect(),
+            value => Err(expected("ARRAY", &value)),
         }
-        arg => Err(format!(
-            "argument to 'push' must be ARRAY, got: {}",
-            arg.as_type(),
-        )),
     }
 }
 
-fn builtin_puts(args: Vec<Value>) -> Result<Value, String> {
-    args.iter().for_each(|arg| println!("{arg}"));
-    Ok(Value::Null)
+/// Only hashes with string keys convert.
+impl<T: TryFrom<Value, Error = String>> TryFrom<Value> for HashMap<String, T> {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let Value::Hash(hash) = value else {
+            return Err(expected("HASH", &value));
+        };
+        hash.iter()
+            .map(|(key, value)| match key {
+                HashKey::String(key) => Ok((key.to_string(), T::try_from(value.clone())?)),
+                key => Err(format!(
+                    "expected STRING keys, got {}",
+                    Value::from(key.clone()).as_type()
+                )),
+            })
+            .collect()
+    }
 }
 
-#[derive(Debug, PartialEq, Clone)]
-pub enum Value {
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub enum HashKey {
     Int(i64),
     Bool(bool),
-    String(String),
-    Null,
-    Let,
-    Return(Box<Value>),
-    Array(Vec<Value>),
-    Function {
-        parameters: Vec<String>,
-        body: Vec<Statement>,
-        env: Rc<RefCell<Environment>>,
-    },
-    Builtin(BuiltinFuncion),
-    Hash(HashMap<Value, Value>),
-    CompiledFunction {
-        instructions: Instructions,
-        num_locals: usize,
-        num_parameters: usize,
-    },
-    Closure {
-        fun: Box<Value>,
-        free: Vec<Value>,
-    },
+    String(Arc<str>),
 }
 
-impl From<i64> for Value {
-    fn from(value: i64) -> Self {
-        Value::Int(value)
-    }
-}
+impl TryFrom<Value> for HashKey {
+    type Error = String;
 
-impl From<bool> for Value {
-    fn from(value: bool) -> Self {
-        Value::Bool(value)
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Int(int) => Ok(HashKey::Int(int)),
+            Value::Bool(bool) => Ok(HashKey::Bool(bool)),
+            Value::String(string) => Ok(HashKey::String(string)),
+            value => Err(format!("unusable as hash key: {}", value.as_type())),
+        }
     }
 }
 
-impl From<&'static str> for Value {
-    fn from(value: &'static str) -> Self {
-        Value::String(value.to_string())
+impl From<HashKey> for Value {
+    fn from(value: HashKey) -> Self {
+        match value {
+            HashKey::Int(int) => Value::Int(int),
+            HashKey::Bool(bool) => Value::Bool(bool),
+            HashKey::String(string) => Value::String(string),
+        }
     }
 }
 
-impl From<String> for Value {
-    fn from(value: String) -> Self {
-        Value::String(value)
+impl From<i64> for HashKey {
+    fn from(value: i64) -> Self {
+        HashKey::Int(value)
     }
 }
 
-impl From<HashMap<Value, Value>> for Value {
-    fn from(value: HashMap<Value, Value>) -> Self {
-        Value::Hash(value)
+impl From<bool> for HashKey {
+    fn from(value: bool) -> Self {
+        HashKey::Bool(value)
     }
 }
 
-impl<T: Into<Value> + Clone> From<Vec<T>> for Value {
-    fn from(values: Vec<T>) -> Self {
-        Value::Array(
-            values
-                .iter()
-                .map(|value| std::convert::Into::<Value>::into(value.clone()))
-                .collect::<Vec<Value>>(),
-        )
+impl From<&'static str> for HashKey {
+    fn from(value: &'static str) -> Self {
+        HashKey::String(value.into())
     }
 }
 
-impl Eq for Value {}
-
-impl Hash for Value {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        match *self {
-            Value::Int(ref int) => int.hash(state),
-            Value::Bool(ref bool) => bool.hash(state),
-            Value::String(ref string) => string.hash(state),
-            _ => "".hash(state),
+impl Display for HashKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HashKey::Int(int) => write!(f, "{int}"),
+            HashKey::Bool(bool) => write!(f, "{bool}"),
+            HashKey::String(string) => write!(f, r#""{}""#, string),
         }
     }
 }
@@ -270,14 +222,14 @@ impl Display for Value {
             Value::Int(value) => write!(f, "{value}"),
             Value::Bool(value) => write!(f, "{value}"),
             Value::Null => write!(f, "null"),
-            Value::Return(value) => write!(f, "{value}"),
             Value::Let => write!(f, "let"),
+            Value::Exit(code) => write!(f, "exit({code})"),
             Value::Function {
                 parameters, body, ..
             } => {
                 write!(f, "fn ({}) {{", parameters.join(", "))?;
 
-                for statement in body {
+                for statement in body.iter() {
                     write!(f, "{statement}")?;
                 }
 
@@ -312,6 +264,7 @@ impl Display for Value {
             Value::Closure { fun, .. } => {
                 write!(f, "Closure[{fun}]")
             }
+            Value::Memoized(memo) => write!(f, "memoized({})", memo.function),
         }
     }
 }
@@ -322,8 +275,8 @@ impl Value {
             Value::Int(_) => "INTEGER".into(),
             Value::Bool(_) => "BOOLEAN".into(),
             Value::Null => "NULL".into(),
-            Value::Return(_) => "RETURN".into(),
             Value::Let => "LET".into(),
+            Value::Exit(_) => "EXIT".into(),
             Value::Function { .. } => "FUNCTION".into(),
             Value::String(_) => "STRING".into(),
             Value::Builtin(_) => "BUILTIN".into(),
@@ -331,6 +284,7 @@ impl Value {
             Value::Hash(_) => "HASH".into(),
             Value::CompiledFunction { .. } => "COMPILED_FUNCTION_OBJ".into(),
             Value::Closure { .. } => "CLOSURE".into(),
+            Value::Memoized(_) => "MEMOIZED".into(),
         }
     }
 }
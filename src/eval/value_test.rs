@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+
+use crate::engine::run_source;
+
+use super::value::Value;
+
+#[test]
+fn test_into_rust() {
+    assert_eq!(i64::try_from(Value::Int(3)), Ok(3));
+    assert_eq!(bool::try_from(Value::Bool(true)), Ok(true));
+    assert_eq!(
+        String::try_from(Value::from("monkey")),
+        Ok("monkey".to_string())
+    );
+    assert_eq!(
+        i64::try_from(Value::from("3")),
+        Err("expected INTEGER, got STRING".to_string())
+    );
+
+    let array = run_source("[1, 2, 3]").unwrap();
+    assert_eq!(Vec::<i64>::try_from(array.clone()), Ok(vec![1, 2, 3]));
+    assert_eq!(
+        Vec::<bool>::try_from(array),
+        Err("expected BOOLEAN, got INTEGER".to_string())
+    );
+
+    let nested = run_source(r#"{"a": [true], "b": []}"#).unwrap();
+    let expected = HashMap::from([("a".to_string(), vec![true]), ("b".to_string(), vec![])]);
+    assert_eq!(HashMap::<String, Vec<bool>>::try_from(nested), Ok(expected));
+    assert_eq!(
+        HashMap::<String, i64>::try_from(run_source("{1: 2}").unwrap()),
+        Err("expected STRING keys, got INTEGER".to_string())
+    );
+}
+
+#[test]
+fn test_from_rust() {
+    let hash = Value::from(HashMap::from([("x".to_string(), vec![1, 2])]));
+    assert_eq!(hash.to_string(), "{\"x\":[1,2]}");
+    assert_eq!(
+        HashMap::<String, Vec<i64>>::try_from(hash).unwrap()["x"],
+        [1, 2]
+    );
+}
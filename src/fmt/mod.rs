@@ -0,0 +1,350 @@
+#[cfg(test)]
+mod tests;
+
+use crate::ast::expression::Expression;
+use crate::ast::program::Program;
+use crate::ast::statement::Statement;
+use crate::ast::trivia::Comment;
+use crate::lexer::Lexer;
+use crate::parser::precedence::Precedence;
+use crate::parser::{Parser, ParserError};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FormatConfig {
+    pub indent: usize,
+    pub line_width: usize,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        FormatConfig {
+            indent: 4,
+            line_width: 80,
+        }
+    }
+}
+
+impl FormatConfig {
+    pub fn with_indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    pub fn with_line_width(mut self, line_width: usize) -> Self {
+        self.line_width = line_width;
+        self
+    }
+}
+
+pub fn format_source(source: &str, config: FormatConfig) -> Result<String, Vec<ParserError>> {
+    let mut parser = Parser::new(Lexer::from(source));
+    let program = parser.parse_program();
+    if !parser.errors().is_empty() {
+        return Err(parser.errors().to_vec());
+    }
+    Ok(format_program(&program, config))
+}
+
+/// Prints `program` back as Monkey source. Top level comments keep their place, comments
+/// inside a statement move above it since nested statements don't record where they were.
+pub fn format_program(program: &Program, config: FormatConfig) -> String {
+    let formatter = Formatter { config };
+    let mut out = String::new();
+    let mut previous_multiline = false;
+
+    for (idx, statement) in program.statements.iter().enumerate() {
+        let text = formatter.statement(statement, 0, false);
+        let multiline = text.contains('\n');
+        if idx > 0 && (multiline || previous_multiline) {
+            out.push('\n');
+        }
+        previous_multiline = multiline;
+
+        if let Some(trivia) = program.trivia.get(idx) {
+            for comment in trivia.leading.iter().chain(&trivia.inner) {
+                out.push_str(&format!("//{}\n", comment.text));
+            }
+            out.push_str(&text);
+            if let Some(comment) = &trivia.trailing {
+                out.push_str(&format!(" //{}", comment.text));
+            }
+        } else {
+            out.push_str(&text);
+        }
+        out.push('\n');
+    }
+
+    if !program.dangling.is_empty() && !program.statements.is_empty() {
+        out.push('\n');
+    }
+    for Comment { text, .. } in &program.dangling {
+        out.push_str(&format!("//{text}\n"));
+    }
+    out
+}
+
+enum ListItem<'a> {
+    Expression(&'a Expression),
+    Pair(&'a Expression, &'a Expression),
+}
+
+struct Formatter {
+    config: FormatConfig,
+}
+
+/// The column right after `text` when it starts at `column`.
+fn end_column(column: usize, text: &str) -> usize {
+    match text.rfind('\n') {
+        Some(idx) => text[idx + 1..].chars().count(),
+        None => column + text.chars().count(),
+    }
+}
+
+/// Strings with a `"` can only be written as a heredoc, none of their lines may start with its tag.
+fn format_string(string: &str) -> String {
+    if !string.contains('"') {
+        return format!("\"{string}\"");
+    }
+    let mut tag = "END".to_string();
+    let closes = |line: &str, tag: &str| {
+        line.trim_start_matches([' ', '\t'])
+            .strip_prefix(tag)
+            .is_some_and(|rest| !rest.starts_with(|char: char| char.is_alphabetic() || char == '_'))
+    };
+    while string.lines().any(|line| closes(line, &tag)) {
+        tag.push('_');
+    }
+    format!("<<<{tag}\n{string}\n{tag}")
+}
+
+fn infix_precedence(expression: &Expression) -> Option<Precedence> {
+    match expression {
+        Expression::Infix { operator, .. } => Some(Precedence::from(operator)),
+        _ => None,
+    }
+}
+
+impl Formatter {
+    fn indent(&self, level: usize) -> String {
+        " ".repeat(level * self.config.indent)
+    }
+
+    /// Formats a statement that starts at the indentation of `level`, without that indentation.
+    fn statement(&self, statement: &Statement, level: usize, last_in_block: bool) -> String {
+        let column = level * self.config.indent;
+        match statement {
+            Statement::Let { name, value } => {
+                let prefix = format!("let {name} = ");
+                let value = self.expression(value, level, column + prefix.len());
+                format!("{prefix}{value};")
+            }
+            Statement::Return(value) => {
+                format!("return {};", self.expression(value, level, column + 7))
+            }
+            Statement::Expression(value) => {
+                let value = self.expression(value, level, column);
+                if last_in_block {
+                    value
+                } else {
+                    format!("{value};")
+                }
+            }
+            Statement::Block(statements) => statements
+                .iter()
+                .enumerate()
+                .map(|(idx, statement)| {
+                    let text = self.statement(statement, level, idx + 1 == statements.len());
+                    if idx == 0 {
+                        text
+                    } else {
+                        format!("{}{text}", self.indent(level))
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    fn block(&self, statements: &[Statement], level: usize) -> String {
+        if statements.is_empty() {
+            return "{}".to_string();
+        }
+        let mut block = "{\n".to_string();
+        for (idx, statement) in statements.iter().enumerate() {
+            block.push_str(&self.indent(level + 1));
+            block.push_str(&self.statement(statement, level + 1, idx + 1 == statements.len()));
+            block.push('\n');
+        }
+        block.push_str(&self.indent(level));
+        block.push('}');
+        block
+    }
+
+    /// Formats an operand, wrapping it in parens when it would otherwise bind differently.
+    fn operand(
+        &self,
+        expression: &Expression,
+        level: usize,
+        column: usize,
+        parens: bool,
+    ) -> String {
+        if parens {
+            format!("({})", self.expression(expression, level, column + 1))
+        } else {
+            self.expression(expression, level, column)
+        }
+    }
+
+    fn expression(&self, expression: &Expression, level: usize, column: usize) -> String {
+        match expression {
+            Expression::Int(int) => int.to_string(),
+            Expression::Bool(bool) => bool.to_string(),
+            Expression::Identifier(name) => name.to_string(),
+            Expression::String(string) => format_string(string),
+            Expression::Prefix { rhs, operator } => {
+                let rhs = self.operand(
+                    rhs,
+                    level,
+                    column + 1,
+                    matches!(**rhs, Expression::Infix { .. }),
+                );
+                format!("{operator}{rhs}")
+            }
+            Expression::Infix { lhs, operator, rhs } => {
+                let precedence = Precedence::from(operator);
+                let lhs = self.operand(
+                    lhs,
+                    level,
+                    column,
+                    infix_precedence(lhs).is_some_and(|lhs| lhs < precedence),
+                );
+                let operator = format!(" {operator} ");
+                let rhs_column = end_column(column, &lhs) + operator.len();
+                let rhs = self.operand(
+                    rhs,
+                    level,
+                    rhs_column,
+                    infix_precedence(rhs).is_some_and(|rhs| rhs <= precedence),
+                );
+                format!("{lhs}{operator}{rhs}")
+            }
+            Expression::If {
+                condition,
+                consequence,
+                alternative,
+            } => {
+                let condition = self.expression(condition, level, column + 4);
+                let mut text = format!("if ({condition}) {}", self.block(consequence, level));
+                if let Some(alternative) = alternative {
+                    text.push_str(" else ");
+                    text.push_str(&self.block(alternative, level));
+                }
+                text
+            }
+            Expression::Fn {
+                parameters, body, ..
+            } => format!("fn({}) {}", parameters.join(", "), self.block(body, level)),
+            Expression::Call {
+                function,
+                arguments,
+            } => {
+                let function = self.operand(
+                    function,
+                    level,
+                    column,
+                    matches!(
+                        **function,
+                        Expression::Infix { .. }
+                            | Expression::Prefix { .. }
+                            | Expression::If { .. }
+                    ),
+                );
+                let items = arguments
+                    .iter()
+                    .map(ListItem::Expression)
+                    .collect::<Vec<_>>();
+                let arguments = self.list("(", ")", &items, level, end_column(column, &function));
+                format!("{function}{arguments}")
+            }
+            Expression::Array(elements) => {
+                let items = elements
+                    .iter()
+                    .map(ListItem::Expression)
+                    .collect::<Vec<_>>();
+                self.list("[", "]", &items, level, column)
+            }
+            Expression::Index { lhs, index } => {
+                let lhs = self.operand(
+                    lhs,
+                    level,
+                    column,
+                    matches!(
+                        **lhs,
+                        Expression::Infix { .. }
+                            | Expression::Prefix { .. }
+                            | Expression::If { .. }
+                    ),
+                );
+                let index = self.expression(index, level, end_column(column, &lhs) + 1);
+                format!("{lhs}[{index}]")
+            }
+            Expression::Hash(pairs) => {
+                let items = pairs
+                    .iter()
+                    .map(|(key, value)| ListItem::Pair(key, value))
+                    .collect::<Vec<_>>();
+                self.list("{", "}", &items, level, column)
+            }
+        }
+    }
+
+    fn item(&self, item: &ListItem, level: usize, column: usize) -> String {
+        match item {
+            ListItem::Expression(expression) => self.expression(expression, level, column),
+            ListItem::Pair(key, value) => {
+                let key = self.expression(key, level, column);
+                let value = self.expression(value, level, end_column(column, &key) + 2);
+                format!("{key}: {value}")
+            }
+        }
+    }
+
+    /// Keeps the items on one line when every line fits, otherwise puts one item per line,
+    /// each followed by a comma.
+    fn list(
+        &self,
+        open: &str,
+        close: &str,
+        items: &[ListItem],
+        level: usize,
+        column: usize,
+    ) -> String {
+        let mut flat = open.to_string();
+        let mut item_column = column + open.len();
+        for (idx, item) in items.iter().enumerate() {
+            if idx > 0 {
+                flat.push_str(", ");
+                item_column += 2;
+            }
+            let text = self.item(item, level, item_column);
+            item_column = end_column(item_column, &text);
+            flat.push_str(&text);
+        }
+        flat.push_str(close);
+
+        let fits = flat.lines().enumerate().all(|(idx, line)| {
+            let start = if idx == 0 { column } else { 0 };
+            start + line.chars().count() <= self.config.line_width
+        });
+        if fits || items.is_empty() {
+            return flat;
+        }
+
+        let indent = self.indent(level + 1);
+        let items = items
+            .iter()
+            .map(|item| format!("{indent}{},\n", self.item(item, level + 1, indent.len())))
+            .collect::<String>();
+        format!("{open}\n{items}{}{close}", self.indent(level))
+    }
+}
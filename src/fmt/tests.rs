@@ -0,0 +1,130 @@
+use super::*;
+
+fn format(source: &str) -> String {
+    format_source(source, FormatConfig::default()).expect("source should parse")
+}
+
+#[test]
+fn test_format_statements() {
+    let tests = [
+        ("let   x=5", "let x = 5;\n"),
+        ("return x+1", "return x + 1;\n"),
+        ("puts( \"hi\" ,1)", "puts(\"hi\", 1);\n"),
+        ("let a = [1,2,3][0];", "let a = [1, 2, 3][0];\n"),
+        ("{1:true,\"b\":-2}", "{1: true, \"b\": -2};\n"),
+        ("let f = fn(){};", "let f = fn() {};\n"),
+    ];
+
+    for (source, expected) in tests {
+        assert_eq!(format(source), expected, "formatting {source:?}");
+    }
+}
+
+#[test]
+fn test_format_precedence() {
+    let tests = [
+        ("(1 + 2) * 3", "(1 + 2) * 3;\n"),
+        ("1 + 2 * 3", "1 + 2 * 3;\n"),
+        ("((1 + 2) + 3)", "1 + 2 + 3;\n"),
+        ("1 - (2 - 3)", "1 - (2 - 3);\n"),
+        ("-(a + b)", "-(a + b);\n"),
+        ("!(-a)", "!-a;\n"),
+        ("(a < b) == true", "a < b == true;\n"),
+        ("(fn(x) { x })(1)", "fn(x) {\n    x\n}(1);\n"),
+    ];
+
+    for (source, expected) in tests {
+        assert_eq!(format(source), expected, "formatting {source:?}");
+    }
+}
+
+#[test]
+fn test_format_blocks() {
+    let source = "let max=fn(a,b){if(a>b){return a;}else{b}};max(1,2);";
+
+    assert_eq!(
+        format(source),
+        "let max = fn(a, b) {
+    if (a > b) {
+        return a;
+    } else {
+        b
+    }
+};
+
+max(1, 2);
+"
+    );
+}
+
+#[test]
+fn test_format_config() {
+    let source = "let f = fn(x) { [x, x + 1, x + 2] };";
+    let config = FormatConfig::default().with_indent(2).with_line_width(16);
+
+    assert_eq!(
+        format_source(source, config).unwrap(),
+        "let f = fn(x) {
+  [
+    x,
+    x + 1,
+    x + 2,
+  ]
+};
+"
+    );
+}
+
+#[test]
+fn test_format_comments() {
+    let source = "// setup
+let x = 1; // one
+let y = fn() {
+    // inner
+    x
+};
+// done";
+
+    assert_eq!(
+        format(source),
+        "// setup
+let x = 1; // one
+
+// inner
+let y = fn() {
+    x
+};
+
+// done
+"
+    );
+}
+
+#[test]
+fn test_format_is_idempotent() {
+    let source = "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } };
+let people = [{\"name\": \"Alice\", \"age\": 24}, {\"name\": \"Anna\", \"age\": 28}, {\"name\": \"Bob\", \"age\": 31}];
+puts(fib(10)); // 55";
+
+    let once = format(source);
+    assert_eq!(format(&once), once);
+}
+
+#[test]
+fn test_format_errors() {
+    let errors = format_source("let = 5;", FormatConfig::default()).unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn test_format_heredoc() {
+    let source = "let s = <<<END\nsay \"hi\"\nEND;\nputs(s);";
+
+    assert_eq!(
+        format(source),
+        "let s = <<<END\nsay \"hi\"\nEND;\n\nputs(s);\n"
+    );
+    assert_eq!(format("\"a\nb\""), "\"a\nb\";\n");
+    assert_eq!(format("<<<X\n\"\nEND;\nX"), "<<<END_\n\"\nEND;\nEND_;\n");
+}
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+use super::token::Token;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keyword {
+    Fn,
+    Let,
+    True,
+    False,
+    If,
+    Else,
+    Return,
+}
+
+impl Keyword {
+    pub fn token(&self) -> Token {
+        match self {
+            Keyword::Fn => Token::Function,
+            Keyword::Let => Token::Let,
+            Keyword::True => Token::True,
+            Keyword::False => Token::False,
+            Keyword::If => Token::If,
+            Keyword::Else => Token::Else,
+            Keyword::Return => Token::Return,
+        }
+    }
+}
+
+/// The words the lexer reads as keywords, everything else is an identifier. Starts with the
+/// standard words, embedders can add aliases or replace words to build a DSL on top of Monkey.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keywords {
+    words: HashMap<String, Keyword>,
+}
+
+impl Default for Keywords {
+    fn default() -> Self {
+        Keywords::empty()
+            .with("fn", Keyword::Fn)
+            .with("let", Keyword::Let)
+            .with("true", Keyword::True)
+            .with("false", Keyword::False)
+            .with("if", Keyword::If)
+            .with("else", Keyword::Else)
+            .with("return", Keyword::Return)
+    }
+}
+
+impl Keywords {
+    pub fn empty() -> Self {
+        Keywords {
+            words: HashMap::new(),
+        }
+    }
+
+    /// Makes `word` read as `keyword`, next to any word it already has.
+    pub fn with(mut self, word: impl Into<String>, keyword: Keyword) -> Self {
+        self.words.insert(word.into(), keyword);
+        self
+    }
+
+    /// Turns `word` back into a plain identifier.
+    pub fn without(mut self, word: &str) -> Self {
+        self.words.remove(word);
+        self
+    }
+
+    /// Replaces every word of `keyword` with `word`.
+    pub fn rename(mut self, keyword: Keyword, word: impl Into<String>) -> Self {
+        self.words.retain(|_, entry| *entry != keyword);
+        self.with(word, keyword)
+    }
+
+    pub fn get(&self, word: &str) -> Option<Keyword> {
+        self.words.get(word).copied()
+    }
+
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.words.keys().map(String::as_str)
+    }
+}
@@ -1,33 +1,95 @@
+pub mod keywords;
 pub mod token;
 
-use token::Token;
+use std::fmt::Display;
+
+use keywords::Keywords;
+use token::{Span, SpannedToken, Token};
 
 #[cfg(test)]
 mod tests;
 
+/// A char that no token starts with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexerError {
+    pub ch: char,
+    pub span: Span,
+}
+
+impl Display for LexerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "illegal character {:?} {}:{}",
+            self.ch, self.span.line, self.span.column
+        )
+    }
+}
+
+impl std::error::Error for LexerError {}
+
 pub struct Lexer {
+    source: String,
     input: Vec<char>,
     position: usize,
     read_position: usize,
+    offset: usize,
+    read_offset: usize,
     column: usize,
     line: usize,
     ch: char,
+    errors: Vec<LexerError>,
+    keywords: Keywords,
 }
 
 impl Lexer {
     pub fn new(input: Vec<char>) -> Self {
         let mut lexer = Self {
+            source: input.iter().collect(),
             input,
             position: 0,
+            offset: 0,
+            read_offset: 0,
             column: 0,
             line: 1,
             read_position: 0,
             ch: '\0',
+            errors: vec![],
+            keywords: Keywords::default(),
         };
         lexer.read_char();
+        lexer.skip_shebang();
         lexer
     }
 
+    /// Skips a `#!` line at the very start so scripts can be run directly.
+    fn skip_shebang(&mut self) {
+        if self.ch == '#' && self.peak_char() == '!' {
+            while self.ch != '\n' && self.ch != '\0' {
+                self.read_char();
+            }
+        }
+    }
+
+    pub fn with_keywords(mut self, keywords: Keywords) -> Self {
+        self.keywords = keywords;
+        self
+    }
+
+    pub fn keywords(&self) -> &Keywords {
+        &self.keywords
+    }
+
+    /// Every illegal char met so far, in source order.
+    pub fn errors(&self) -> &[LexerError] {
+        &self.errors
+    }
+
+    /// The text being lexed, spans index into it.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
     pub fn read_char(&mut self) {
         self.ch = match self.input.get(self.read_position) {
             None => '\0',
@@ -35,6 +97,10 @@ impl Lexer {
         };
         self.position = self.read_position;
         self.read_position += 1;
+        self.offset = self.read_offset;
+        if self.position < self.input.len() {
+            self.read_offset += self.ch.len_utf8();
+        }
         if let '\n' = self.ch {
             self.column = 0;
             self.line += 1;
@@ -77,30 +143,36 @@ impl Lexer {
 
     fn read_identifier(&mut self) -> Token {
         let position = self.position;
-        while self.is_letter() {
+        while self.is_letter() || self.is_digit() {
             self.read_char()
         }
 
         let ident = String::from_iter(&self.input[position..self.position]);
-        match ident.as_str() {
-            "fn" => Token::Function,
-            "let" => Token::Let,
-            "true" => Token::True,
-            "false" => Token::False,
-            "if" => Token::If,
-            "else" => Token::Else,
-            "return" => Token::Return,
-            _ => Token::Ident(ident),
+        match self.keywords.get(&ident) {
+            Some(keyword) => keyword.token(),
+            None => Token::Ident(ident),
+        }
+    }
+
+    fn read_comment(&mut self) -> Token {
+        self.read_char();
+        self.read_char();
+        let position = self.position;
+        while self.ch != '\n' && self.ch != '\0' {
+            self.read_char();
         }
+        let comment = String::from_iter(&self.input[position..self.position]);
+        Token::Comment(comment.trim_end_matches('\r').to_string())
     }
 
+    /// Strings can span lines, they end at the next `"`.
     pub fn read_string(&mut self) -> Token {
         self.read_char();
         let position = self.position;
 
-        loop {
-            if self.ch == '"' || self.ch == '\0' {
-                break;
+        while self.ch != '"' {
+            if self.ch == '\0' {
+                return Token::UnterminatedString;
             }
             self.read_char();
         }
@@ -109,69 +181,172 @@ impl Lexer {
         Token::String(self.input[position..self.position - 1].iter().collect())
     }
 
-    pub fn next_token(&mut self) -> (Token, (usize, usize)) {
-        self.skip_withespace();
-        let token = (
-            match self.ch {
-                '=' => {
-                    if self.peak_char() == '=' {
-                        self.read_char();
-                        Token::Eq
-                    } else {
-                        Token::Assign
-                    }
-                }
-                '+' => Token::Plus,
-                '-' => Token::Minus,
-                '!' => {
-                    if self.peak_char() == '=' {
-                        self.read_char();
-                        Token::NotEq
-                    } else {
-                        Token::Bang
-                    }
+    fn is_heredoc(&self) -> bool {
+        self.ch == '<'
+            && self.input.get(self.read_position) == Some(&'<')
+            && self.input.get(self.read_position + 1) == Some(&'<')
+            && self
+                .input
+                .get(self.read_position + 2)
+                .is_some_and(|char| char.is_alphabetic() || *char == '_')
+    }
+
+    /// Reads `<<<TAG`, the rest of that line is ignored and the string is every following
+    /// line up to the one that starts with `TAG`, without the last line break. Lexing goes on
+    /// right after that `TAG`, so it can be followed by `;` or `)`.
+    fn read_heredoc(&mut self) -> Token {
+        for _ in 0..3 {
+            self.read_char();
+        }
+        let position = self.position;
+        while self.is_letter() {
+            self.read_char();
+        }
+        let tag = self.input[position..self.position].to_vec();
+        while self.ch != '\n' && self.ch != '\0' {
+            self.read_char();
+        }
+
+        let mut lines = Vec::new();
+        while self.ch == '\n' {
+            self.read_char();
+            let position = self.position;
+            while self.ch == ' ' || self.ch == '\t' {
+                self.read_char();
+            }
+            let end = self.position + tag.len();
+            if self.input.get(self.position..end) == Some(&tag[..])
+                && !self
+                    .input
+                    .get(end)
+                    .is_some_and(|char| char.is_alphabetic() || *char == '_')
+            {
+                for _ in 0..tag.len() {
+                    self.read_char();
                 }
-                '/' => Token::Slash,
-                '*' => Token::Asterisk,
-                '%' => Token::Percent,
-                '>' => {
-                    if self.peak_char() == '=' {
-                        self.read_char();
-                        Token::GtorEq
-                    } else {
-                        Token::Gt
-                    }
+                return Token::String(lines.join("\n"));
+            }
+            while self.ch != '\n' && self.ch != '\0' {
+                self.read_char();
+            }
+            let line = String::from_iter(&self.input[position..self.position]);
+            lines.push(line.trim_end_matches('\r').to_string());
+        }
+        Token::UnterminatedString
+    }
+
+    /// Keeps returning `Token::Eof` once the input is exhausted.
+    pub fn next_token(&mut self) -> SpannedToken {
+        self.skip_withespace();
+        let (start, line, column) = (self.offset, self.line, self.column);
+        // Strings, numbers and identifiers stop on the char after them, everything else
+        // on its last char, and `Eof` stays where it is.
+        let reads_ahead = self.ch == '"'
+            || self.ch == '\0'
+            || self.is_heredoc()
+            || (self.ch == '/' && self.peak_char() == '/')
+            || self.is_digit()
+            || self.is_letter();
+        let token = match self.ch {
+            '=' => {
+                if self.peak_char() == '=' {
+                    self.read_char();
+                    Token::Eq
+                } else {
+                    Token::Assign
                 }
-                '<' => {
-                    if self.peak_char() == '=' {
-                        self.read_char();
-                        Token::LtorEq
-                    } else {
-                        Token::Lt
-                    }
+            }
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '!' => {
+                if self.peak_char() == '=' {
+                    self.read_char();
+                    Token::NotEq
+                } else {
+                    Token::Bang
                 }
-                ';' => Token::Semicolon,
-                '(' => Token::Lparen,
-                ')' => Token::Rparen,
-                ',' => Token::Comma,
-                '{' => Token::Lbrace,
-                '}' => Token::Rbrace,
-                '[' => Token::Lbracket,
-                ']' => Token::Rbracket,
-                '\0' => Token::Eof,
-                ':' => Token::Colon,
-                '"' => return (self.read_string(), (self.line, self.column)),
-                _ if self.is_digit() => {
-                    return (self.read_digit(), (self.line, self.column));
+            }
+            '/' if self.peak_char() == '/' => self.read_comment(),
+            '/' => Token::Slash,
+            '*' => Token::Asterisk,
+            '%' => Token::Percent,
+            '>' => {
+                if self.peak_char() == '=' {
+                    self.read_char();
+                    Token::GtorEq
+                } else {
+                    Token::Gt
                 }
-                _ if self.is_letter() => {
-                    return (self.read_identifier(), (self.line, self.column));
+            }
+            '<' if self.is_heredoc() => self.read_heredoc(),
+            '<' => {
+                if self.peak_char() == '=' {
+                    self.read_char();
+                    Token::LtorEq
+                } else {
+                    Token::Lt
                 }
-                _ => Token::Illegal,
-            },
-            (self.line, self.column),
-        );
-        self.read_char();
+            }
+            ';' => Token::Semicolon,
+            '(' => Token::Lparen,
+            ')' => Token::Rparen,
+            ',' => Token::Comma,
+            '{' => Token::Lbrace,
+            '}' => Token::Rbrace,
+            '[' => Token::Lbracket,
+            ']' => Token::Rbracket,
+            '\0' => Token::Eof,
+            ':' => Token::Colon,
+            '"' => self.read_string(),
+            _ if self.is_digit() => self.read_digit(),
+            _ if self.is_letter() => self.read_identifier(),
+            char => Token::Illegal(char),
+        };
+        if !reads_ahead {
+            self.read_char();
+        }
+        let token = self.spanned(token, start, line, column);
+        if let Token::Illegal(ch) = token.token {
+            self.errors.push(LexerError {
+                ch,
+                span: token.span,
+            });
+        }
         token
     }
+
+    /// Wraps a token that ends right before the current char.
+    fn spanned(&self, token: Token, start: usize, line: usize, column: usize) -> SpannedToken {
+        SpannedToken {
+            token,
+            span: Span {
+                start,
+                end: self.offset,
+                line,
+                column,
+            },
+        }
+    }
+}
+
+impl From<&str> for Lexer {
+    fn from(source: &str) -> Self {
+        Lexer::new(source.chars().collect())
+    }
+}
+
+impl From<String> for Lexer {
+    fn from(source: String) -> Self {
+        Lexer::from(source.as_str())
+    }
+}
+
+/// Yields every token up to, but not including, `Token::Eof`.
+impl Iterator for Lexer {
+    type Item = SpannedToken;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.next_token();
+        (token.token != Token::Eof).then_some(token)
+    }
 }
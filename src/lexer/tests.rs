@@ -1,3 +1,4 @@
+use super::keywords::Keyword;
 use super::*;
 
 #[test]
@@ -14,10 +15,10 @@ fn test_lexer1() {
         Token::Semicolon,
         Token::Eof,
     ];
-    let mut lexer = Lexer::new(input.chars().collect());
+    let mut lexer = Lexer::from(input);
     for expect in expected {
         let token = lexer.next_token();
-        assert_eq!(expect, token.0);
+        assert_eq!(expect, token.token);
     }
 }
 
@@ -71,10 +72,10 @@ let result = add(five, ten);
         Token::Semicolon,
     ];
 
-    let mut lexer = Lexer::new(input.chars().collect());
+    let mut lexer = Lexer::from(input);
     for expect in expected {
         let token = lexer.next_token();
-        assert_eq!(expect, token.0);
+        assert_eq!(expect, token.token);
     }
 }
 
@@ -98,10 +99,10 @@ fn test_lexer3() {
         Token::Semicolon,
         Token::Eof,
     ];
-    let mut lexer = Lexer::new(input.chars().collect());
+    let mut lexer = Lexer::from(input);
     for expect in expected {
         let token = lexer.next_token();
-        assert_eq!(expect, token.0);
+        assert_eq!(expect, token.token);
     }
 }
 
@@ -133,10 +134,10 @@ fn test_lexer4() {
         Token::Rbrace,
         Token::Semicolon,
     ];
-    let mut lexer = Lexer::new(input.chars().collect());
+    let mut lexer = Lexer::from(input);
     for expect in expected {
         let token = lexer.next_token();
-        assert_eq!(expect, token.0);
+        assert_eq!(expect, token.token);
     }
 }
 
@@ -179,9 +180,257 @@ fn test_lexer5() {
         Token::Eof,
     ];
 
-    let mut lexer = Lexer::new(input.chars().collect());
+    let mut lexer = Lexer::from(input);
     for expect in expected {
         let token = lexer.next_token();
-        assert_eq!(expect, token.0);
+        assert_eq!(expect, token.token);
     }
 }
+
+#[test]
+fn test_spans() {
+    let input = "let ab = \"é\";\n  10 >= x";
+    let expected = vec![
+        (Token::Let, (0, 3, 1, 1)),
+        (Token::Ident("ab".into()), (4, 6, 1, 5)),
+        (Token::Assign, (7, 8, 1, 8)),
+        (Token::String("é".into()), (9, 13, 1, 10)),
+        (Token::Semicolon, (13, 14, 1, 13)),
+        (Token::Int(10), (17, 19, 2, 3)),
+        (Token::GtorEq, (20, 22, 2, 6)),
+        (Token::Ident("x".into()), (23, 24, 2, 9)),
+    ];
+
+    let tokens = Lexer::from(input).collect::<Vec<_>>();
+    assert_eq!(
+        tokens,
+        expected
+            .into_iter()
+            .map(|(token, (start, end, line, column))| SpannedToken {
+                token,
+                span: Span {
+                    start,
+                    end,
+                    line,
+                    column,
+                },
+            })
+            .collect::<Vec<_>>()
+    );
+
+    let mut lexer = Lexer::from(input);
+    lexer.by_ref().for_each(drop);
+    assert_eq!(
+        lexer.next_token(),
+        SpannedToken {
+            token: Token::Eof,
+            span: Span {
+                start: 24,
+                end: 24,
+                line: 2,
+                column: 10,
+            },
+        }
+    );
+}
+
+#[test]
+fn test_from_str() {
+    let source = "let x = 5;";
+    let lexer = Lexer::from(source);
+    assert_eq!(lexer.source(), source);
+
+    let tokens = Lexer::from(source.to_string())
+        .map(|spanned| spanned.token)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Let,
+            Token::Ident("x".into()),
+            Token::Assign,
+            Token::Int(5),
+            Token::Semicolon,
+        ]
+    );
+}
+
+#[test]
+fn test_comments() {
+    let input = "// leading\nlet x = 4 / 2; // trailing\r\n//";
+    let expected = vec![
+        Token::Comment(" leading".into()),
+        Token::Let,
+        Token::Ident("x".into()),
+        Token::Assign,
+        Token::Int(4),
+        Token::Slash,
+        Token::Int(2),
+        Token::Semicolon,
+        Token::Comment(" trailing".into()),
+        Token::Comment("".into()),
+        Token::Eof,
+    ];
+    let mut lexer = Lexer::from(input);
+    for expect in expected {
+        assert_eq!(expect, lexer.next_token().token);
+    }
+
+    let comment = Lexer::from("1 // end").nth(1).unwrap();
+    assert_eq!(
+        comment.span,
+        Span {
+            start: 2,
+            end: 8,
+            line: 1,
+            column: 3,
+        }
+    );
+}
+
+#[test]
+fn test_multiline_strings() {
+    let tests = [
+        ("\"a\nb\"", Token::String("a\nb".into())),
+        ("\"abc", Token::UnterminatedString),
+        (
+            "<<<END\nline \"one\"\r\n  two\nEND",
+            Token::String("line \"one\"\n  two".into()),
+        ),
+        ("<<<EOF ignored\n\nx\n  EOF  ", Token::String("\nx".into())),
+        ("<<<END\nEND", Token::String("".into())),
+        ("<<<END\nno end", Token::UnterminatedString),
+        ("<<<END\nENDING\nEND", Token::String("ENDING".into())),
+    ];
+
+    for (input, expected) in tests {
+        let mut lexer = Lexer::from(input);
+        assert_eq!(lexer.next_token().token, expected, "lexing {input:?}");
+        assert_eq!(lexer.next_token().token, Token::Eof, "lexing {input:?}");
+    }
+
+    let tokens = Lexer::from("x <<<T\n1\n  T; y << 2")
+        .map(|token| token.token)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Ident("x".into()),
+            Token::String("1".into()),
+            Token::Semicolon,
+            Token::Ident("y".into()),
+            Token::Lt,
+            Token::Lt,
+            Token::Int(2),
+        ]
+    );
+}
+
+#[test]
+fn test_unicode_identifiers() {
+    let tokens = Lexer::from("let café = größe_ñ + 日本2 - _1;")
+        .map(|token| token.token)
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Let,
+            Token::Ident("café".into()),
+            Token::Assign,
+            Token::Ident("größe_ñ".into()),
+            Token::Plus,
+            Token::Ident("日本2".into()),
+            Token::Minus,
+            Token::Ident("_1".into()),
+            Token::Semicolon,
+        ]
+    );
+}
+
+#[test]
+fn test_shebang() {
+    let mut lexer = Lexer::from("#!/usr/bin/env monkey\nlet x");
+    let token = lexer.next_token();
+    assert_eq!(token.token, Token::Let);
+    assert_eq!(
+        (token.span.start, token.span.line, token.span.column),
+        (22, 2, 1)
+    );
+
+    let mut lexer = Lexer::from("let x\n#!/usr/bin/env monkey");
+    let tokens = std::iter::from_fn(|| Some(lexer.next_token().token))
+        .take(4)
+        .collect::<Vec<_>>();
+    assert_eq!(tokens[2], Token::Illegal('#'));
+}
+
+#[test]
+fn test_lexer_errors() {
+    let mut lexer = Lexer::from("a ? é\n  ~");
+    let tokens = lexer.by_ref().map(|token| token.token).collect::<Vec<_>>();
+
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Ident("a".into()),
+            Token::Illegal('?'),
+            Token::Ident("é".into()),
+            Token::Illegal('~'),
+        ]
+    );
+    assert_eq!(
+        lexer.errors(),
+        [
+            LexerError {
+                ch: '?',
+                span: Span {
+                    start: 2,
+                    end: 3,
+                    line: 1,
+                    column: 3
+                }
+            },
+            LexerError {
+                ch: '~',
+                span: Span {
+                    start: 9,
+                    end: 10,
+                    line: 2,
+                    column: 3
+                }
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_keywords() {
+    let keywords = Keywords::default()
+        .with("func", Keyword::Fn)
+        .rename(Keyword::Let, "sea")
+        .without("if");
+    let tokens = Lexer::from("sea f = func(x) { fn }; let if")
+        .with_keywords(keywords)
+        .map(|token| token.token)
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Let,
+            Token::Ident("f".into()),
+            Token::Assign,
+            Token::Function,
+            Token::Lparen,
+            Token::Ident("x".into()),
+            Token::Rparen,
+            Token::Lbrace,
+            Token::Function,
+            Token::Rbrace,
+            Token::Semicolon,
+            Token::Ident("let".into()),
+            Token::Ident("if".into()),
+        ]
+    );
+}
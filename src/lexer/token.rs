@@ -1,8 +1,36 @@
 use std::fmt::Debug;
 
+/// Where a token sits in the source: the byte offsets `start..end` and the line
+/// and column of its first char, both counted from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+}
+
 #[derive(PartialEq)]
 pub enum Token {
-    Illegal,
+    Illegal(char),
+    /// A string or heredoc that reaches the end of the input before it is closed.
+    UnterminatedString,
     Eof,
     Ident(String),
     String(String),
@@ -36,6 +64,8 @@ pub enum Token {
     Lbracket,
     Rbracket,
     Colon,
+    /// A `//` comment, without the slashes.
+    Comment(String),
 }
 
 impl Debug for Token {
@@ -54,7 +84,8 @@ impl Debug for Token {
             Token::Gt => write!(f, "Greater than"),
             Token::Minus => write!(f, "Minus"),
             Token::Bang => write!(f, "Bang"),
-            Token::Illegal => write!(f, "Illegal"),
+            Token::Illegal(char) => write!(f, "Illegal: {:?}", char),
+            Token::UnterminatedString => write!(f, "Unterminated string"),
             Token::Eof => write!(f, "Eof"),
             Token::Ident(ident) => write!(f, "Ident: {}", ident),
             Token::Int(int) => write!(f, "Int: {}", int),
@@ -75,6 +106,7 @@ impl Debug for Token {
             Token::Lbracket => write!(f, "Lbracket"),
             Token::Rbracket => write!(f, "Rbracket"),
             Token::Colon => write!(f, "Colon"),
+            Token::Comment(comment) => write!(f, "Comment: {}", comment),
         }
     }
 }
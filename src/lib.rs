@@ -1 +1,27 @@
+#![cfg_attr(test, feature(test))]
+pub mod ast;
+pub mod code;
+pub mod compiler;
+pub mod diagnostics;
+pub mod engine;
+#[cfg(test)]
+mod engine_test;
+pub mod error;
+#[cfg(test)]
+mod error_test;
+pub mod eval;
+pub mod fmt;
 pub mod lexer;
+pub mod parser;
+pub mod relp;
+#[cfg(test)]
+mod tests;
+pub mod vm;
+
+pub use self::compiler::{ByteCode, Compiler};
+pub use self::engine::{run_source, run_source_with, Engine};
+pub use self::error::MonkeyError;
+pub use self::eval::value::Value;
+pub use self::lexer::Lexer;
+pub use self::parser::Parser;
+pub use self::vm::Vm;
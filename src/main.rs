@@ -1,19 +1,631 @@
-#![feature(test)]
-pub mod ast;
-pub mod code;
-pub mod compiler;
-pub mod eval;
-pub mod lexer;
-pub mod parser;
-pub mod relp;
-#[cfg(test)]
-mod tests;
-pub mod vm;
-
-use self::relp::start_compiler;
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+
+use monkey::ast::dump::ast_dump;
+use monkey::ast::program::Program;
+use monkey::compiler::cache::BytecodeCache;
+use monkey::compiler::disasm::{disassemble, disassemble_json};
+use monkey::compiler::source_map::SourceMap;
+use monkey::compiler::symbol_table::SymbolTable;
+use monkey::compiler::{mkc, ByteCode, Compiler};
+use monkey::diagnostics::{suggest, Diagnostic};
+use monkey::fmt::{format_source, FormatConfig};
+use monkey::lexer::Lexer;
+use monkey::parser::Parser;
+use monkey::relp::config::{ReplConfig, ScriptConfig};
+use monkey::relp::engine::Backend;
+use monkey::relp::{bench_file, run_file, start, watch_file, EXIT_FAILED, EXIT_INVALID};
+use monkey::vm::{Completion, Vm, VmError};
+
+/// Exit codes: the program ran, it has errors, or the command line is wrong.
+const SUCCESS: i32 = 0;
+const FAILURE: i32 = 1;
+const USAGE: i32 = 2;
+
+/// `monkey --emit=ast file.mk...` prints the tree of each file.
+fn run_emit_ast(files: &[&String]) -> i32 {
+    if files.is_empty() {
+        eprintln!("usage: monkey --emit=ast <file>...");
+        return 2;
+    }
+
+    let mut code = 0;
+    for file in files {
+        let source = match std::fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("could not read {file}: {err}");
+                code = 1;
+                continue;
+            }
+        };
+        let mut parser = Parser::new(Lexer::from(source.as_str()));
+        let program = parser.parse_program();
+        if parser.errors().is_empty() {
+            print!("{}", ast_dump(&program));
+        } else {
+            for err in parser.errors() {
+                eprint!("{}", Diagnostic::from(err).render(&source));
+            }
+            code = 1;
+        }
+    }
+    code
+}
+
+/// `monkey fmt [--indent=N] [--width=N] file.mk...` prints each file formatted.
+fn run_fmt(args: &[String]) -> i32 {
+    let mut config = FormatConfig::default();
+    let mut files = Vec::new();
+    for arg in args {
+        if let Some(indent) = arg.strip_prefix("--indent=") {
+            match indent.parse() {
+                Ok(indent) => config = config.with_indent(indent),
+                Err(_) => {
+                    eprintln!("invalid indent: {indent}");
+                    return 2;
+                }
+            }
+        } else if let Some(width) = arg.strip_prefix("--width=") {
+            match width.parse() {
+                Ok(width) => config = config.with_line_width(width),
+                Err(_) => {
+                    eprintln!("invalid width: {width}");
+                    return 2;
+                }
+            }
+        } else {
+            files.push(arg);
+        }
+    }
+
+    if files.is_empty() {
+        eprintln!("usage: monkey fmt [--indent=N] [--width=N] <file>...");
+        return 2;
+    }
+
+    let mut code = 0;
+    for file in files {
+        let source = match std::fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("could not read {file}: {err}");
+                code = 1;
+                continue;
+            }
+        };
+        match format_source(&source, config) {
+            Ok(formatted) => print!("{formatted}"),
+            Err(errors) => {
+                for err in &errors {
+                    eprint!("{}", Diagnostic::from(err).render(&source));
+                }
+                code = 1;
+            }
+        }
+    }
+    code
+}
+
+/// The value of `--name=value` or `--name value` among `args`.
+fn flag<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    let long = format!("--{name}");
+    args.iter().enumerate().find_map(|(idx, arg)| {
+        match arg.strip_prefix(&long)?.strip_prefix('=') {
+            Some(value) => Some(value),
+            None if *arg == long => args.get(idx + 1).map(String::as_str),
+            None => None,
+        }
+    })
+}
+
+/// Fails on a `--flag` that isn't one of `known`.
+fn check_flags(command: &str, args: &[String], known: &[&str]) -> Result<(), i32> {
+    for arg in args.iter().filter(|arg| arg.starts_with("--")) {
+        let name = arg[2..].split('=').next().unwrap_or_default();
+        if !known.contains(&name) {
+            eprintln!("unknown flag --{name} for monkey {command}");
+            return Err(USAGE);
+        }
+    }
+    Ok(())
+}
+
+/// The `--engine=` among `args`, the vm when there is none.
+fn parse_engine(args: &[String]) -> Result<Backend, String> {
+    match flag(args, "engine") {
+        Some(name) => name.parse(),
+        None => Ok(Backend::Vm),
+    }
+}
+
+/// How to run the one file of `monkey <command> [--engine=eval|vm] [--sandbox] file.mk`, or
+/// the exit code for bad arguments. `flags` are the command's own flags.
+fn script_args<'a>(
+    command: &str,
+    args: &'a [String],
+    flags: &[&str],
+) -> Result<(ScriptConfig, &'a str), i32> {
+    let (args, script) = split_script_args(args);
+    let known = [&["engine", "sandbox"], flags].concat();
+    check_flags(command, args, &known)?;
+    let engine = parse_engine(args).map_err(|err| {
+        eprintln!("{err}");
+        USAGE
+    })?;
+    let config = ScriptConfig::new()
+        .engine(engine)
+        .args(script.to_vec())
+        .sandbox(args.iter().any(|arg| arg == "--sandbox"));
+    match files(args)[..] {
+        [file] => Ok((config, file)),
+        _ => {
+            eprintln!("usage: monkey {command} [--engine=eval|vm] [--sandbox] <file> [-- args...]");
+            Err(USAGE)
+        }
+    }
+}
+
+/// The arguments of the command and the ones after `--`, which are for the script.
+fn split_script_args(args: &[String]) -> (&[String], &[String]) {
+    match args.iter().position(|arg| arg == "--") {
+        Some(idx) => (&args[..idx], &args[idx + 1..]),
+        None => (args, &[]),
+    }
+}
+
+/// The flags that take a value, which may come as the next argument.
+const VALUE_FLAGS: &[&str] = &["--engine", "--prompt", "--out", "--iters", "--format"];
+
+/// The arguments that aren't flags or the value of one.
+fn files(args: &[String]) -> Vec<&str> {
+    let mut files = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if VALUE_FLAGS.contains(&arg.as_str()) {
+            args.next();
+        } else if !arg.starts_with("--") {
+            files.push(arg.as_str());
+        }
+    }
+    files
+}
+
+/// Reads a file, `-` is stdin.
+fn read_file(path: &str) -> Result<String, i32> {
+    let read = if path == "-" {
+        std::io::read_to_string(std::io::stdin())
+    } else {
+        std::fs::read_to_string(path)
+    };
+    read.map_err(|err| {
+        eprintln!("could not read {path}: {err}");
+        FAILURE
+    })
+}
+
+/// Parses and compiles a file, its errors go to stderr.
+fn compile_file(path: &str, source: &str) -> Result<Compiler, i32> {
+    let mut parser = Parser::new(Lexer::from(source));
+    let program: Program = parser.parse_program();
+    if !parser.errors().is_empty() {
+        for err in parser.errors() {
+            eprint!("{}", Diagnostic::from(err).with_file(path).render(source));
+        }
+        return Err(FAILURE);
+    }
+    let mut symbol_table = SymbolTable::new_with_builtins();
+    for (name, _) in ScriptConfig::new().sandbox(true).globals() {
+        symbol_table.define(name);
+    }
+    let mut compiler = Compiler::new_with_state(
+        Arc::new(RwLock::new(symbol_table)),
+        Rc::new(RefCell::new(vec![])),
+    );
+    if let Err(err) = compiler.compile_program(program) {
+        match err.position() {
+            Some((line, column)) => {
+                let diagnostic = Diagnostic::new(format!("Compiler error: {err}"), line, column);
+                eprint!("{}", diagnostic.with_file(path).render(source));
+            }
+            None => eprintln!("{path}: Compiler error: {err}"),
+        }
+        return Err(FAILURE);
+    }
+    Ok(compiler)
+}
+
+/// A `monkey <name>` command, `run` gets the arguments after the name and returns the exit code.
+struct Subcommand {
+    name: &'static str,
+    usage: &'static str,
+    help: &'static str,
+    run: fn(&[String]) -> i32,
+}
+
+const SUBCOMMANDS: &[Subcommand] = &[
+    Subcommand {
+        name: "repl",
+        usage: "[--engine=eval|vm] [--prompt=P] [--quiet] [--no-rc] [--report] [--profile]",
+        help: "start the REPL, also what `monkey` alone does",
+        run: repl_command,
+    },
+    Subcommand {
+        name: "run",
+        usage: "[--engine=eval|vm] [--sandbox] [--no-cache] <file> [-- args...]",
+        help: "run a .mk file, or a .mkc file on the vm",
+        run: run_command,
+    },
+    Subcommand {
+        name: "watch",
+        usage: "[--engine=eval|vm] [--sandbox] <file> [-- args...]",
+        help: "run a file again whenever it changes",
+        run: watch_command,
+    },
+    Subcommand {
+        name: "bench",
+        usage: "[--iters=N] [--engine=eval|vm|both] [--sandbox] <file> [-- args...]",
+        help: "time a file over several runs on each engine",
+        run: bench_command,
+    },
+    Subcommand {
+        name: "build",
+        usage: "[--out=file.mkc] [--source-map] <file>",
+        help: "compile a file to bytecode",
+        run: build_command,
+    },
+    Subcommand {
+        name: "disasm",
+        usage: "[--format=text|json] <file>",
+        help: "show the instructions of a .mk or .mkc file",
+        run: disasm_command,
+    },
+    Subcommand {
+        name: "fmt",
+        usage: "[--indent=N] [--width=N] <file>...",
+        help: "print files formatted",
+        run: run_fmt,
+    },
+    Subcommand {
+        name: "check",
+        usage: "<file>... (- reads stdin)",
+        help: "report parse and compile errors without running",
+        run: check_command,
+    },
+    Subcommand {
+        name: "help",
+        usage: "",
+        help: "show this list",
+        run: help_command,
+    },
+];
+
+fn help_command(_: &[String]) -> i32 {
+    println!("usage: monkey <command> [args]\n");
+    for command in SUBCOMMANDS {
+        println!("  {:<7} {}", command.name, command.help);
+        if !command.usage.is_empty() {
+            println!("          monkey {} {}", command.name, command.usage);
+        }
+    }
+    println!("\nexit codes: 0 success, 1 errors in the program, 2 bad arguments");
+    println!("run exits 65 when the file doesn't compile, 70 when it fails, or with exit(code)");
+    SUCCESS
+}
+
+fn repl_command(args: &[String]) -> i32 {
+    let known = ["engine", "prompt", "quiet", "no-rc", "report", "profile"];
+    if let Err(code) = check_flags("repl", args, &known) {
+        return code;
+    }
+    let engine = match parse_engine(args) {
+        Ok(engine) => engine,
+        Err(err) => {
+            eprintln!("{err}");
+            return USAGE;
+        }
+    };
+    let mut config = ReplConfig::new()
+        .engine(engine)
+        .report(args.iter().any(|arg| arg == "--report"))
+        .profile(args.iter().any(|arg| arg == "--profile"))
+        .quiet(args.iter().any(|arg| arg == "--quiet"));
+    if args.iter().any(|arg| arg == "--no-rc") {
+        config = config.rc_file(None);
+    }
+    if let Some(prompt) = flag(args, "prompt") {
+        config = config.prompt(prompt);
+    }
+    start(config).expect("the repl dont fail") as i32
+}
+
+/// The bytecode of a source and its source map, from the cache when the same source was
+/// compiled before.
+fn cached_bytecode(file: &str, source: &str) -> Result<(ByteCode, Option<SourceMap>), i32> {
+    let compile = || compile_file(file, source).map_err(|_| EXIT_INVALID as i32);
+    match BytecodeCache::default_dir().map(BytecodeCache::new) {
+        Some(cache) => cache.compile(file, source, compile),
+        None => {
+            let mut compiler = compile()?;
+            Ok((compiler.bytecode(), Some(compiler.source_map(file, source))))
+        }
+    }
+}
+
+/// A runtime error with the line it stopped at, when the source map and the source it was
+/// made from are at hand.
+fn render_vm_error(err: VmError, source_map: Option<&SourceMap>, source: Option<&str>) -> String {
+    let Some(source_map) = source_map else {
+        return format!("Executing bytecode error: {err}\n");
+    };
+    let err = err.locate(source_map);
+    let msg = format!("Executing bytecode error: {err}");
+    match (err.position(), source) {
+        (Some((line, column)), Some(source)) => Diagnostic::new(msg, line, column)
+            .with_file(&source_map.file)
+            .render(source),
+        (Some((line, column)), None) => format!("{}:{line}:{column}: {msg}\n", source_map.file),
+        (None, _) => format!("{msg}\n"),
+    }
+}
+
+/// `.mkc` files run on the vm, anything else is read as source. Sources the vm runs are
+/// compiled through the cache unless `--no-cache` is given.
+fn run_command(args: &[String]) -> i32 {
+    let (config, file) = match script_args("run", args, &["no-cache"]) {
+        Ok(args) => args,
+        Err(code) => return code,
+    };
+    let no_cache = split_script_args(args)
+        .0
+        .iter()
+        .any(|arg| arg == "--no-cache");
+    let bytes = match std::fs::read(file) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("could not read {file}: {err}");
+            return FAILURE;
+        }
+    };
+    let (byte_code, source_map, source) = if mkc::is_mkc(&bytes) {
+        if config.engine == Backend::Eval {
+            eprintln!("{file} is bytecode, it only runs on the vm");
+            return USAGE;
+        }
+        match mkc::decode(&bytes) {
+            Ok((byte_code, source_map)) => {
+                // The source the file was built from, if it's still there unchanged.
+                let source = source_map.as_ref().and_then(|source_map| {
+                    let source = std::fs::read_to_string(&source_map.file).ok()?;
+                    source_map.matches(&source).then_some(source)
+                });
+                (byte_code, source_map, source)
+            }
+            Err(err) => {
+                eprintln!("{file}: {err}");
+                return EXIT_INVALID as i32;
+            }
+        }
+    } else if config.engine == Backend::Vm && !no_cache {
+        let Ok(source) = String::from_utf8(bytes) else {
+            eprintln!("could not read {file}: it isn't valid UTF-8");
+            return FAILURE;
+        };
+        match cached_bytecode(file, &source) {
+            Ok((byte_code, source_map)) => (byte_code, source_map, Some(source)),
+            Err(code) => return code,
+        }
+    } else {
+        return run_file(file, &config) as i32;
+    };
+    let globals = config.globals().into_iter().map(|(_, value)| value);
+    let globals = Rc::new(RefCell::new(globals.collect()));
+    match Vm::new_with_global_store(byte_code, globals).run() {
+        Ok(Completion::Finished) => SUCCESS,
+        Ok(Completion::Exit(code)) => code as i32,
+        Err(err) => {
+            eprint!(
+                "{}",
+                render_vm_error(err, source_map.as_ref(), source.as_deref())
+            );
+            EXIT_FAILED as i32
+        }
+    }
+}
+
+fn watch_command(args: &[String]) -> i32 {
+    match script_args("watch", args, &[]) {
+        Ok((config, file)) => watch_file(file, &config),
+        Err(code) => code,
+    }
+}
+
+fn bench_command(args: &[String]) -> i32 {
+    let (args, script) = split_script_args(args);
+    if let Err(code) = check_flags("bench", args, &["iters", "engine", "sandbox"]) {
+        return code;
+    }
+    let engines = match flag(args, "engine") {
+        None | Some("both") => vec![Backend::Eval, Backend::Vm],
+        Some("eval") => vec![Backend::Eval],
+        Some("vm") => vec![Backend::Vm],
+        Some(name) => {
+            eprintln!("unknown engine: {name}, expected eval, vm or both");
+            return USAGE;
+        }
+    };
+    let iters = match flag(args, "iters").map(str::parse) {
+        None => 10,
+        Some(Ok(iters)) if iters > 0 => iters,
+        Some(_) => {
+            eprintln!("--iters takes a number above 0");
+            return USAGE;
+        }
+    };
+    match files(args)[..] {
+        [file] => {
+            let config = ScriptConfig::new()
+                .args(script.to_vec())
+                .sandbox(args.iter().any(|arg| arg == "--sandbox"));
+            bench_file(&engines, file, iters, &config) as i32
+        }
+        _ => {
+            eprintln!(
+                "usage: monkey bench [--iters=N] [--engine=eval|vm|both] [--sandbox] <file> [-- args...]"
+            );
+            USAGE
+        }
+    }
+}
+
+fn build_command(args: &[String]) -> i32 {
+    if let Err(code) = check_flags("build", args, &["out", "source-map"]) {
+        return code;
+    }
+    let [file] = files(args)[..] else {
+        eprintln!("usage: monkey build [--out=file.mkc] [--source-map] <file>");
+        return USAGE;
+    };
+    let out = match flag(args, "out") {
+        Some(out) => out.to_string(),
+        None => Path::new(file).with_extension("mkc").display().to_string(),
+    };
+    let source = match read_file(file) {
+        Ok(source) => source,
+        Err(code) => return code,
+    };
+    let mut compiler = match compile_file(file, &source) {
+        Ok(compiler) => compiler,
+        Err(code) => return code,
+    };
+    let source_map = args
+        .iter()
+        .any(|arg| arg == "--source-map")
+        .then(|| compiler.source_map(file, &source));
+    let bytes = match mkc::encode(&compiler.bytecode(), source_map.as_ref()) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("{file}: {err}");
+            return FAILURE;
+        }
+    };
+    match std::fs::write(&out, bytes) {
+        Ok(()) => SUCCESS,
+        Err(err) => {
+            eprintln!("could not write {out}: {err}");
+            FAILURE
+        }
+    }
+}
+
+fn disasm_command(args: &[String]) -> i32 {
+    if let Err(code) = check_flags("disasm", args, &["format"]) {
+        return code;
+    }
+    let json = match flag(args, "format") {
+        None | Some("text") => false,
+        Some("json") => true,
+        Some(format) => {
+            eprintln!("unknown format: {format}, expected text or json");
+            return USAGE;
+        }
+    };
+    let [file] = files(args)[..] else {
+        eprintln!("usage: monkey disasm [--format=text|json] <file>");
+        return USAGE;
+    };
+    let bytes = match std::fs::read(file) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("could not read {file}: {err}");
+            return FAILURE;
+        }
+    };
+    let (byte_code, source_map) = if mkc::is_mkc(&bytes) {
+        match mkc::decode(&bytes) {
+            Ok(decoded) => decoded,
+            Err(err) => {
+                eprintln!("{file}: {err}");
+                return FAILURE;
+            }
+        }
+    } else {
+        let source = String::from_utf8_lossy(&bytes);
+        match compile_file(file, &source) {
+            Ok(mut compiler) => (
+                compiler.bytecode(),
+                Some(compiler.source_map(file, &source)),
+            ),
+            Err(code) => return code,
+        }
+    };
+    if json {
+        println!("{}", disassemble_json(&byte_code, source_map.as_ref()));
+    } else {
+        print!("{}", disassemble(&byte_code));
+    }
+    SUCCESS
+}
+
+fn check_command(args: &[String]) -> i32 {
+    if let Err(code) = check_flags("check", args, &[]) {
+        return code;
+    }
+    let files = files(args);
+    if files.is_empty() {
+        eprintln!("usage: monkey check <file>... (- reads stdin)");
+        return USAGE;
+    }
+    let total = files.len();
+    let failed = files
+        .into_iter()
+        .filter(|&file| {
+            let name = if file == "-" { "<stdin>" } else { file };
+            read_file(file)
+                .and_then(|source| compile_file(name, &source))
+                .is_err()
+        })
+        .count();
+    if failed == 0 {
+        return SUCCESS;
+    }
+    eprintln!("{failed} of {total} files have errors");
+    FAILURE
+}
 
 fn main() {
-    println!("Hello! This is the Monkey programming language!");
-    println!("Feel free to type in commands");
-    start_compiler().expect("the repl dont fail");
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if let Some(emit) = args.iter().find_map(|arg| arg.strip_prefix("--emit=")) {
+        if emit != "ast" {
+            eprintln!("unknown --emit kind: {emit}");
+            std::process::exit(USAGE);
+        }
+        let files = args
+            .iter()
+            .filter(|arg| !arg.starts_with("--"))
+            .collect::<Vec<_>>();
+        std::process::exit(run_emit_ast(&files));
+    }
+
+    let code = match args.first().map(String::as_str) {
+        None => repl_command(&args),
+        Some("--help" | "-h") => help_command(&args),
+        // `monkey --engine=eval` keeps starting the REPL.
+        Some(arg) if arg.starts_with("--") => repl_command(&args),
+        Some(name) => match SUBCOMMANDS.iter().find(|command| command.name == name) {
+            Some(command) => (command.run)(&args[1..]),
+            None => {
+                match suggest(name, SUBCOMMANDS.iter().map(|command| command.name)) {
+                    Some(suggestion) => {
+                        eprintln!("unknown command {name}, did you mean `{suggestion}`?")
+                    }
+                    None => eprintln!("unknown command {name}, see monkey help"),
+                }
+                USAGE
+            }
+        },
+    };
+    std::process::exit(code);
 }
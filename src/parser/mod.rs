@@ -1,7 +1,9 @@
-mod precedence;
+pub mod precedence;
 #[cfg(test)]
 mod tests;
 
+use std::fmt::Display;
+
 use crate::{
     ast::{
         self,
@@ -9,34 +11,48 @@ use crate::{
         operator::{InfixOperator, PrefixOperator},
         program::Program,
         statement::Statement,
+        trivia::{Comment, Trivia},
+    },
+    diagnostics::suggest,
+    lexer::{
+        token::{Span, Token},
+        Lexer, LexerError,
     },
-    lexer::{token::Token, Lexer},
     parser::precedence::Precedence,
 };
 
-struct ParserError {
-    msg: String,
-    line: usize,
-    column: usize,
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParserError {
+    pub msg: String,
+    pub span: Span,
 }
 
 impl ParserError {
-    fn new(msg: impl Into<String>, line: usize, column: usize) -> ParserError {
+    pub fn new(msg: impl Into<String>, span: Span) -> ParserError {
         ParserError {
             msg: msg.into(),
-            line,
-            column,
+            span,
         }
     }
 }
 
+impl Display for ParserError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}:{}", self.msg, self.span.line, self.span.column)
+    }
+}
+
+impl std::error::Error for ParserError {}
+
 pub struct Parser {
     lexer: Lexer,
     current_token: Token,
-    current_token_position: (usize, usize),
+    current_span: Span,
     peek_token: Token,
-    peek_token_position: (usize, usize),
+    peek_span: Span,
     errors: Vec<ParserError>,
+    block_depth: usize,
+    comments: Vec<Comment>,
 }
 
 impl Parser {
@@ -47,33 +63,63 @@ impl Parser {
         }
         Err(ParserError::new(
             format!("expected {:?}, got {:?} instead", expected, self.peek_token,),
-            self.peek_token_position.0,
-            self.peek_token_position.1,
+            self.peek_span,
         ))
     }
-    pub fn new(mut lexer: Lexer) -> Self {
-        let current_token = lexer.next_token();
-        let peek_token = lexer.next_token();
-
-        Parser {
+    pub fn new(lexer: Lexer) -> Self {
+        let mut parser = Parser {
             lexer,
-            current_token: current_token.0,
-            current_token_position: current_token.1,
-            peek_token: peek_token.0,
-            peek_token_position: current_token.1,
+            current_token: Token::Eof,
+            current_span: Span::default(),
+            peek_token: Token::Eof,
+            peek_span: Span::default(),
             errors: Vec::new(),
-        }
+            block_depth: 0,
+            comments: Vec::new(),
+        };
+        parser.next_token();
+        parser.next_token();
+        parser
     }
 
+    /// Comments never reach the grammar, they wait in `comments` until a statement claims them.
     fn next_token(&mut self) {
         std::mem::swap(&mut self.current_token, &mut self.peek_token);
-        std::mem::swap(
-            &mut self.current_token_position,
-            &mut self.peek_token_position,
-        );
-        let peek_token = self.lexer.next_token();
-        self.peek_token = peek_token.0;
-        self.peek_token_position = peek_token.1;
+        std::mem::swap(&mut self.current_span, &mut self.peek_span);
+        loop {
+            let peek_token = self.lexer.next_token();
+            match peek_token.token {
+                Token::Comment(text) => self.comments.push(Comment {
+                    text,
+                    span: peek_token.span,
+                }),
+                token => {
+                    self.peek_token = token;
+                    self.peek_span = peek_token.span;
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Removes the pending comments that start before `offset`.
+    fn take_comments_before(&mut self, offset: usize) -> Vec<Comment> {
+        let split = self
+            .comments
+            .iter()
+            .position(|comment| comment.span.start >= offset)
+            .unwrap_or(self.comments.len());
+        self.comments.drain(..split).collect()
+    }
+
+    /// Removes the first pending comment when it's on the line of the current token.
+    fn take_trailing_comment(&mut self) -> Option<Comment> {
+        match self.comments.first() {
+            Some(comment) if comment.span.line == self.current_span.line => {
+                Some(self.comments.remove(0))
+            }
+            _ => None,
+        }
     }
 
     fn peek_precedence(&self) -> Precedence {
@@ -84,28 +130,80 @@ impl Parser {
         Precedence::from(&self.current_token)
     }
 
+    pub fn source(&self) -> &str {
+        self.lexer.source()
+    }
+
+    /// Every error found by `parse_program`, in source order.
+    pub fn errors(&self) -> &[ParserError] {
+        &self.errors
+    }
+
+    /// The illegal chars met while scanning, including those no statement error points at.
+    pub fn lexer_errors(&self) -> &[LexerError] {
+        self.lexer.errors()
+    }
+
     pub fn check_errors(&self) {
         if !self.errors.is_empty() {
             println!("parser has {} errors", self.errors.len());
 
             self.errors
                 .iter()
-                .for_each(|err| println!("parser error: {} {}:{}", err.msg, err.line, err.column))
+                .for_each(|err| println!("parser error: {err}"))
         }
     }
 
     pub fn parse_program(&mut self) -> ast::program::Program {
         let mut program = Program::default();
         while self.current_token != Token::Eof {
+            let position = (self.current_span.line, self.current_span.column);
+            let leading = self.take_comments_before(self.current_span.start);
             match self.parse_statement() {
-                Ok(statement) => program.statements.push(statement),
-                Err(err) => self.errors.push(err),
+                Ok(statement) => {
+                    let inner = self.take_comments_before(self.current_span.end);
+                    let trailing = self.take_trailing_comment();
+                    program.statements.push(statement);
+                    program.positions.push(position);
+                    program.trivia.push(Trivia {
+                        leading,
+                        inner,
+                        trailing,
+                    });
+                }
+                Err(err) => {
+                    self.errors.push(err);
+                    self.synchronize();
+                    self.take_comments_before(self.current_span.end);
+                    self.take_trailing_comment();
+                }
             }
             self.next_token();
         }
+        program.dangling = std::mem::take(&mut self.comments);
         program
     }
 
+    /// Skips the rest of a broken statement, including the blocks it left open,
+    /// so one syntax error doesn't cascade into errors for every token after it.
+    fn synchronize(&mut self) {
+        let mut depth = std::mem::take(&mut self.block_depth);
+        loop {
+            match self.current_token {
+                Token::Eof => return,
+                Token::Semicolon if depth == 0 => return,
+                Token::Rbrace if depth <= 1 => break,
+                Token::Rbrace => depth -= 1,
+                Token::Lbrace => depth += 1,
+                _ => {}
+            }
+            self.next_token();
+        }
+        if self.peek_token == Token::Semicolon {
+            self.next_token();
+        }
+    }
+
     fn parse_statement(&mut self) -> Result<Statement, ParserError> {
         match self.current_token {
             Token::Let => self.parse_let_statement(),
@@ -119,8 +217,7 @@ impl Parser {
             Token::Ident(name) => Ok(name.to_owned()),
             token => Err(ParserError::new(
                 format!("expected Token::Ident, got {:?} instead", token),
-                self.peek_token_position.0,
-                self.peek_token_position.1,
+                self.peek_span,
             )),
         }?;
 
@@ -164,6 +261,25 @@ impl Parser {
     fn parse_expression_statement(&mut self) -> Result<Statement, ParserError> {
         let expression = self.parse_expression(Precedence::Lowest)?;
 
+        // `lte x = 1` or `retrun x` would otherwise parse as two statements.
+        if let Expression::Identifier(name) = &expression {
+            let continues = matches!(
+                self.peek_token,
+                Token::Ident(_) | Token::Int(_) | Token::String(_)
+            );
+            if continues && self.peek_span.line == self.current_span.line {
+                if let Some(keyword) = suggest(name, self.lexer.keywords().words()) {
+                    return Err(ParserError::new(
+                        format!(
+                            "unexpected {:?} after `{name}`, did you mean `{keyword}`?",
+                            self.peek_token
+                        ),
+                        self.current_span,
+                    ));
+                }
+            }
+        }
+
         if self.peek_token == Token::Semicolon {
             self.next_token();
         }
@@ -195,10 +311,16 @@ impl Parser {
             Token::Lparen => self.parse_grouped_expression(),
             Token::If => self.parse_if_expression(),
             Token::Function => self.parse_function_literal(),
+            Token::Illegal(ch) => Err(ParserError::new(
+                format!("illegal character {ch:?}"),
+                self.current_span,
+            )),
+            Token::UnterminatedString => {
+                Err(ParserError::new("unterminated string", self.current_span))
+            }
             token => Err(ParserError::new(
                 format!("i dont now what is this: {:?}", token),
-                self.current_token_position.0,
-                self.current_token_position.1,
+                self.current_span,
             )),
         }
     }
@@ -242,6 +364,9 @@ impl Parser {
 
         while self.peek_token == Token::Comma {
             self.next_token();
+            if self.peek_token == end {
+                break;
+            }
             self.next_token();
             list.push(self.parse_expression(Precedence::Lowest)?)
         }
@@ -258,8 +383,7 @@ impl Parser {
         if self.peek_token != Token::Rparen {
             return Err(ParserError::new(
                 format!("expected Token::Rparen, got: {:?}", self.peek_token),
-                self.peek_token_position.0,
-                self.peek_token_position.1,
+                self.peek_span,
             ));
         }
         self.next_token();
@@ -273,8 +397,7 @@ impl Parser {
             value => {
                 return Err(ParserError::new(
                     format!("this is not a valid PrefixOperator: {:?}", value),
-                    self.current_token_position.0,
-                    self.current_token_position.1,
+                    self.current_span,
                 ))
             }
         };
@@ -290,6 +413,7 @@ impl Parser {
     fn parse_block_statement(&mut self) -> Result<Vec<Statement>, ParserError> {
         let mut statements = Vec::new();
 
+        self.block_depth += 1;
         self.next_token();
 
         while self.current_token != Token::Rbrace && self.current_token != Token::Eof {
@@ -298,6 +422,7 @@ impl Parser {
             self.next_token()
         }
 
+        self.block_depth -= 1;
         Ok(statements)
     }
 
@@ -318,13 +443,13 @@ impl Parser {
             let alternative = self.parse_block_statement()?;
             return Ok(Expression::If {
                 condition: Box::new(condition),
-                consequence,
-                alternative: Some(alternative),
+                consequence: consequence.into(),
+                alternative: Some(alternative.into()),
             });
         }
         Ok(Expression::If {
             condition: Box::new(condition),
-            consequence,
+            consequence: consequence.into(),
             alternative: None,
         })
     }
@@ -340,7 +465,7 @@ impl Parser {
         Ok(Expression::Fn {
             name: String::default(),
             parameters,
-            body,
+            body: body.into(),
         })
     }
 
@@ -359,13 +484,15 @@ impl Parser {
             value => {
                 return Err(ParserError::new(
                     format!("expected Token::Ident, got: {:?}", value),
-                    self.current_token_position.0,
-                    self.current_token_position.1,
+                    self.current_span,
                 ))
             }
         }
         while self.peek_token == Token::Comma {
             self.next_token();
+            if self.peek_token == Token::Rparen {
+                break;
+            }
             self.next_token();
             match &self.current_token {
                 Token::Ident(param) => {
@@ -374,8 +501,7 @@ impl Parser {
                 value => {
                     return Err(ParserError::new(
                         format!("expected Token::Ident, got: {:?}", value),
-                        self.current_token_position.0,
-                        self.current_token_position.1,
+                        self.current_span,
                     ))
                 }
             }
@@ -440,8 +566,7 @@ impl Parser {
             value => {
                 return Err(ParserError::new(
                     format!("This is not a valid InfixOperator: {:?}", value),
-                    self.current_token_position.0,
-                    self.current_token_position.1,
+                    self.current_span,
                 ))
             }
         };
@@ -1,3 +1,4 @@
+use crate::ast::operator::InfixOperator;
 use crate::lexer::token::Token;
 
 #[derive(PartialEq, PartialOrd, Debug)]
@@ -25,9 +26,24 @@ impl From<&Token> for Precedence {
             Token::Minus => Precedence::Sum,
             Token::Slash => Precedence::Product,
             Token::Asterisk => Precedence::Product,
+            Token::Percent => Precedence::Product,
             Token::Lparen => Precedence::Call,
             Token::Lbracket => Precedence::Index,
             _ => Precedence::Lowest,
         }
     }
 }
+
+impl From<&InfixOperator> for Precedence {
+    fn from(value: &InfixOperator) -> Self {
+        match value {
+            InfixOperator::Equal | InfixOperator::NotEqual => Precedence::Equals,
+            InfixOperator::LessThan
+            | InfixOperator::LessThanOrEqual
+            | InfixOperator::GreaterThan
+            | InfixOperator::GreaterThanOrEqual => Precedence::LessGreater,
+            InfixOperator::Add | InfixOperator::Sub => Precedence::Sum,
+            InfixOperator::Mul | InfixOperator::Div | InfixOperator::Modulo => Precedence::Product,
+        }
+    }
+}
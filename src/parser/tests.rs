@@ -8,14 +8,14 @@ fn test_let_statement() {
     let y = 10;
     let foobar = 838383;";
 
-    let lexer = Lexer::new(input.chars().collect());
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
 
     let program = parser.parse_program();
     parser.check_errors();
 
     assert_eq!(program.statements.len(), 3);
-    let expected = vec![
+    let expected = [
         Statement::r#let("x", Expression::Int(5)),
         Statement::r#let("y", Expression::Int(10)),
         Statement::r#let("foobar", Expression::Int(838383)),
@@ -34,7 +34,7 @@ fn test_let_statement() {
 fn test_return_statement() {
     let input = "return 5; return 10; return 993322;";
 
-    let lexer = Lexer::new(input.chars().collect());
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
 
     let program = parser.parse_program();
@@ -42,7 +42,7 @@ fn test_return_statement() {
 
     assert_eq!(program.statements.len(), 3);
 
-    let expected = vec![
+    let expected = [
         Statement::Return(Expression::Int(5)),
         Statement::Return(Expression::Int(10)),
         Statement::Return(Expression::Int(993322)),
@@ -61,7 +61,7 @@ fn test_return_statement() {
 fn test_identifier() {
     let input = "foobar;";
 
-    let lexer = Lexer::new(input.chars().collect());
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
 
     let program = parser.parse_program();
@@ -84,7 +84,7 @@ fn test_identifier() {
 fn test_int_literal() {
     let input = "5;";
 
-    let lexer = Lexer::new(input.chars().collect());
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
 
     let program = parser.parse_program();
@@ -108,7 +108,7 @@ fn test_prefix_expression() {
     let input = "!5;
 -15;";
 
-    let lexer = Lexer::new(input.chars().collect());
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
 
     let program = parser.parse_program();
@@ -146,7 +146,7 @@ fn test_infix_expression() {
 3 + 4 * 5 == 3 * 1 + 4 * 5;
 3 <= 4;";
 
-    let lexer = Lexer::new(input.chars().collect());
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
 
     let program = parser.parse_program();
@@ -175,7 +175,7 @@ fn test_infix_expression() {
 fn test_bool() {
     let input = "false;
 true;";
-    let lexer = Lexer::new(input.chars().collect());
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
 
     let program = parser.parse_program();
@@ -199,7 +199,7 @@ fn test_grouped_expression() {
 -(5 + 5);
 !(true == true);
 ";
-    let lexer = Lexer::new(input.chars().collect());
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
 
     let program = parser.parse_program();
@@ -212,9 +212,9 @@ fn test_grouped_expression() {
 
 #[test]
 fn test_if_expression() {
-    let input = "if (x < y) { x }".chars().collect();
+    let input = "if (x < y) { x }";
 
-    let lexer = Lexer::new(input);
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
 
     let program = parser.parse_program();
@@ -228,7 +228,7 @@ fn test_if_expression() {
                 operator: InfixOperator::LessThan,
                 rhs: Box::new(Expression::Identifier("y".into()))
             }),
-            consequence: vec![Statement::Expression(Expression::Identifier("x".into()))],
+            consequence: vec![Statement::Expression(Expression::Identifier("x".into()))].into(),
             alternative: None
         }))
     )
@@ -236,9 +236,9 @@ fn test_if_expression() {
 
 #[test]
 fn test_if_else_expression() {
-    let input = "if (x < y) { x } else { y }".chars().collect();
+    let input = "if (x < y) { x } else { y }";
 
-    let lexer = Lexer::new(input);
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
 
     let program = parser.parse_program();
@@ -252,19 +252,19 @@ fn test_if_else_expression() {
                 operator: InfixOperator::LessThan,
                 rhs: Box::new(Expression::Identifier("y".into()))
             }),
-            consequence: vec![Statement::Expression(Expression::Identifier("x".into()))],
-            alternative: Some(vec![Statement::Expression(Expression::Identifier(
-                "y".into()
-            ))])
+            consequence: vec![Statement::Expression(Expression::Identifier("x".into()))].into(),
+            alternative: Some(
+                vec![Statement::Expression(Expression::Identifier("y".into()))].into()
+            )
         }))
     )
 }
 
 #[test]
 fn test_function_literal() {
-    let input = "fn(x, y) { x + y;}".chars().collect();
+    let input = "fn(x, y) { x + y;}";
 
-    let lexer = Lexer::new(input);
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
 
     let program = parser.parse_program();
@@ -280,6 +280,7 @@ fn test_function_literal() {
                 operator: InfixOperator::Add,
                 rhs: Box::new(Expression::Identifier("y".into()))
             })]
+            .into()
         }))
     )
 }
@@ -293,10 +294,11 @@ fn test_function_parameters() {
             "fn(x, foo, bar) {};",
             vec!["x".to_string(), "foo".to_string(), "bar".to_string()],
         ),
+        ("fn(x, y,) {};", vec!["x".to_string(), "y".to_string()]),
     ];
 
     test_cases.iter().for_each(|(input, expected)| {
-        let lexer = Lexer::new(input.chars().collect());
+        let lexer = Lexer::from(*input);
         let mut parser = Parser::new(lexer);
 
         let program = parser.parse_program();
@@ -311,8 +313,8 @@ fn test_function_parameters() {
 
 #[test]
 fn test_call_expression() {
-    let input = "add(a,b)".chars().collect();
-    let lexer = Lexer::new(input);
+    let input = "add(a,b)";
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
 
     let program = parser.parse_program();
@@ -329,6 +331,31 @@ fn test_call_expression() {
     );
 }
 
+#[test]
+fn test_trailing_commas() {
+    let test_cases = [
+        ("[1, 2,]", "[1, 2]"),
+        ("add(a, b,)", "add(a, b)"),
+        ("fn(x,) { x }", "fn(x) { x }"),
+        ("{1: 2,}", "{1: 2}"),
+        ("[\n  1,\n  [2,],\n]", "[1, [2]]"),
+    ];
+
+    for (input, expected) in test_cases {
+        let mut parser = Parser::new(Lexer::from(input));
+        let program = parser.parse_program();
+        parser.check_errors();
+        let mut parser = Parser::new(Lexer::from(expected));
+        assert_eq!(program.statements, parser.parse_program().statements);
+    }
+
+    for input in ["[,]", "add(1,,)", "fn(,) {}", "{,}"] {
+        let mut parser = Parser::new(Lexer::from(input));
+        parser.parse_program();
+        assert!(!parser.errors().is_empty(), "{input} should not parse");
+    }
+}
+
 #[test]
 fn test_operator_precedence() {
     let test_cases = [
@@ -349,10 +376,11 @@ fn test_operator_precedence() {
             "add(a * b[2], b[1], 2 * [1, 2][1])",
             "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])))",
         ),
+        ("a + b % c * d", "(a + ((b % c) * d))"),
     ];
 
     test_cases.iter().for_each(|(input, expected)| {
-        let lexer = Lexer::new(input.chars().collect());
+        let lexer = Lexer::from(*input);
         let mut parser = Parser::new(lexer);
 
         let program = parser.parse_program();
@@ -366,7 +394,7 @@ fn test_string_expression() {
     let test_cases = [(r#""foobar""#, "foobar")];
 
     test_cases.iter().for_each(|(input, expected)| {
-        let lexer = Lexer::new(input.chars().collect());
+        let lexer = Lexer::from(*input);
         let mut parser = Parser::new(lexer);
 
         let program = parser.parse_program();
@@ -377,9 +405,9 @@ fn test_string_expression() {
 
 #[test]
 fn test_array() {
-    let input = "[1, 2 * 2, 3 + 3]".chars().collect();
+    let input = "[1, 2 * 2, 3 + 3]";
 
-    let lexer = Lexer::new(input);
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
 
     let program = parser.parse_program();
@@ -394,9 +422,9 @@ fn test_array() {
 
 #[test]
 fn test_index_expression() {
-    let input = "myArray[1 + 1]".chars().collect();
+    let input = "myArray[1 + 1]";
 
-    let lexer = Lexer::new(input);
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
 
     let program = parser.parse_program();
@@ -422,9 +450,9 @@ fn test_index_expression() {
 
 #[test]
 fn test_hash_string_keys() {
-    let input = r#"{"one": 1, "two": 2, "three": 3}"#.chars().collect();
+    let input = r#"{"one": 1, "two": 2, "three": 3}"#;
 
-    let lexer = Lexer::new(input);
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
 
     let program = parser.parse_program();
@@ -447,8 +475,8 @@ fn test_hash_string_keys() {
 
 #[test]
 fn test_empty_hash() {
-    let input = "{}".chars().collect();
-    let lexer = Lexer::new(input);
+    let input = "{}";
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
 
     let program = parser.parse_program();
@@ -463,10 +491,8 @@ fn test_empty_hash() {
 
 #[test]
 fn test_hash_with_expression() {
-    let input = r#"{"one": 0 + 1, "two": 10 - 8, "three": 15 / 5}"#
-        .chars()
-        .collect();
-    let lexer = Lexer::new(input);
+    let input = r#"{"one": 0 + 1, "two": 10 - 8, "three": 15 / 5}"#;
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
 
     let program = parser.parse_program();
@@ -510,8 +536,8 @@ fn test_hash_with_expression() {
 
 #[test]
 fn test_function_literal_with_name() {
-    let input = "let myFunction = fn() { };".chars().collect();
-    let lexer = Lexer::new(input);
+    let input = "let myFunction = fn() { };";
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
 
     let program = parser.parse_program();
@@ -522,7 +548,7 @@ fn test_function_literal_with_name() {
             &Expression::Fn {
                 name: "myFunction".into(),
                 parameters: vec![],
-                body: vec![]
+                body: vec![].into()
             },
             value
         )
@@ -533,3 +559,180 @@ fn test_function_literal_with_name() {
         )
     }
 }
+
+#[test]
+fn test_error_recovery() {
+    let tests = [
+        ("let = 5; let x = 1; x", 1, 2),
+        ("let x 5; let y = 2;", 1, 1),
+        ("1 + ; 2 * ; 3", 2, 1),
+        ("let f = fn(x) { let = 1; x + }; let y = 2; y", 1, 2),
+        ("if (true) { if (false) { ) } }; 1", 1, 1),
+        ("let h = {1 2}; h", 1, 1),
+        ("let x = 1 +", 1, 0),
+    ];
+
+    for (input, errors, statements) in tests {
+        let lexer = Lexer::from(input);
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program();
+
+        assert_eq!(
+            parser.errors().len(),
+            errors,
+            "{input}: {:?}",
+            parser.errors()
+        );
+        assert_eq!(program.statements.len(), statements, "{input}");
+    }
+}
+
+#[test]
+fn test_errors() {
+    let lexer = Lexer::from("let x 5;\nlet = 1;");
+    let mut parser = Parser::new(lexer);
+    parser.parse_program();
+
+    let errors = parser.errors();
+    assert_eq!(
+        errors,
+        [
+            ParserError::new(
+                "expected Assign, got Int: 5 instead",
+                Span {
+                    start: 6,
+                    end: 7,
+                    line: 1,
+                    column: 7
+                }
+            ),
+            ParserError::new(
+                "expected Token::Ident, got Assign instead",
+                Span {
+                    start: 13,
+                    end: 14,
+                    line: 2,
+                    column: 5
+                }
+            ),
+        ]
+    );
+    assert_eq!(
+        errors[0].to_string(),
+        "expected Assign, got Int: 5 instead 1:7"
+    );
+}
+
+#[test]
+fn test_keyword_suggestions() {
+    let tests = [
+        (
+            "lte x = 5;",
+            "unexpected Ident: x after `lte`, did you mean `let`?",
+        ),
+        (
+            "retrun 5;",
+            "unexpected Int: 5 after `retrun`, did you mean `return`?",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let mut parser = Parser::new(Lexer::from(input));
+        parser.parse_program();
+        assert_eq!(parser.errors()[0].msg, expected);
+    }
+
+    for input in ["lte\nx", "foo x", "retrun;"] {
+        let mut parser = Parser::new(Lexer::from(input));
+        parser.parse_program();
+        assert!(parser.errors().is_empty(), "{input} should parse");
+    }
+}
+
+#[test]
+fn test_illegal_characters() {
+    let mut parser = Parser::new(Lexer::from("let x = 1;\nlet y = @ + 2;\nlet $ = 3;"));
+    parser.parse_program();
+
+    let messages = parser
+        .errors()
+        .iter()
+        .map(|err| err.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "illegal character '@' 2:9",
+            "expected Token::Ident, got Illegal: '$' instead 3:5",
+        ]
+    );
+    let lexer_errors = parser
+        .lexer_errors()
+        .iter()
+        .map(|err| err.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lexer_errors,
+        ["illegal character '@' 2:9", "illegal character '$' 3:5"]
+    );
+}
+
+#[test]
+fn test_unterminated_string() {
+    let mut parser = Parser::new(Lexer::from("let s = \"abc;"));
+    parser.parse_program();
+
+    assert_eq!(
+        parser.errors(),
+        [ParserError::new(
+            "unterminated string",
+            Span {
+                start: 8,
+                end: 13,
+                line: 1,
+                column: 9
+            }
+        )]
+    );
+}
+
+#[test]
+fn test_comment_trivia() {
+    let input = "// first
+// second
+let x = 1; // one
+let f = fn() {
+  // inside
+  2
+};
+let = 3; // dropped
+x // last
+// the end";
+
+    let mut parser = Parser::new(Lexer::from(input));
+    let program = parser.parse_program();
+    assert_eq!(parser.errors().len(), 1);
+
+    fn text(comments: &[Comment]) -> Vec<&str> {
+        comments
+            .iter()
+            .map(|comment| comment.text.as_str())
+            .collect()
+    }
+    let trivia = &program.trivia;
+    assert_eq!(trivia.len(), program.statements.len());
+
+    assert_eq!(text(&trivia[0].leading), [" first", " second"]);
+    assert!(trivia[0].inner.is_empty());
+    assert_eq!(trivia[0].trailing.as_ref().unwrap().text, " one");
+
+    assert!(trivia[1].leading.is_empty());
+    assert_eq!(text(&trivia[1].inner), [" inside"]);
+    assert_eq!(trivia[1].trailing, None);
+
+    assert!(trivia[2].leading.is_empty());
+    assert_eq!(trivia[2].trailing.as_ref().unwrap().text, " last");
+
+    assert_eq!(text(&program.dangling), [" the end"]);
+}
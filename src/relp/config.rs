@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::eval::value::{HashKey, Value};
+
+use super::engine::Backend;
+
+pub const PROMPT: &str = ">> ";
+const RC_FILE: &str = ".monkeyrc";
+
+/// How the REPL starts, `main` fills it from the command line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplConfig {
+    pub engine: Backend,
+    /// Prints the compiler's report for every input, vm only.
+    pub report: bool,
+    /// Prints the vm's profile for every input.
+    pub profile: bool,
+    pub prompt: String,
+    /// Shown while brackets or a string are still open.
+    pub continuation_prompt: String,
+    /// Leaves out the banner, for scripts and tests driving the REPL.
+    pub quiet: bool,
+    /// Runs before the first input, `~/.monkeyrc` by default. A missing file is skipped.
+    pub rc_file: Option<PathBuf>,
+}
+
+impl Default for ReplConfig {
+    fn default() -> Self {
+        ReplConfig {
+            engine: Backend::Vm,
+            report: false,
+            profile: false,
+            prompt: PROMPT.to_string(),
+            continuation_prompt: continuation_prompt(PROMPT),
+            quiet: false,
+            rc_file: std::env::var_os("HOME").map(|home| PathBuf::from(home).join(RC_FILE)),
+        }
+    }
+}
+
+/// Dots as wide as `prompt`, so continued lines stay aligned.
+fn continuation_prompt(prompt: &str) -> String {
+    let text = prompt.trim_end();
+    let width = text.chars().count();
+    format!("{}{}", ".".repeat(width), &prompt[text.len()..])
+}
+
+impl ReplConfig {
+    pub fn new() -> Self {
+        ReplConfig::default()
+    }
+
+    pub fn engine(self, engine: Backend) -> Self {
+        ReplConfig { engine, ..self }
+    }
+
+    pub fn report(self, report: bool) -> Self {
+        ReplConfig { report, ..self }
+    }
+
+    pub fn profile(self, profile: bool) -> Self {
+        ReplConfig { profile, ..self }
+    }
+
+    /// Also sets the continuation prompt to dots of the same width.
+    pub fn prompt(self, prompt: impl Into<String>) -> Self {
+        let prompt = prompt.into();
+        ReplConfig {
+            continuation_prompt: continuation_prompt(&prompt),
+            prompt,
+            ..self
+        }
+    }
+
+    pub fn continuation_prompt(self, continuation_prompt: impl Into<String>) -> Self {
+        ReplConfig {
+            continuation_prompt: continuation_prompt.into(),
+            ..self
+        }
+    }
+
+    pub fn quiet(self, quiet: bool) -> Self {
+        ReplConfig { quiet, ..self }
+    }
+
+    pub fn rc_file(self, rc_file: Option<PathBuf>) -> Self {
+        ReplConfig { rc_file, ..self }
+    }
+}
+
+/// How `monkey run`, `watch` and `bench` run a file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScriptConfig {
+    pub engine: Backend,
+    /// What came after `--` on the command line, the script sees them as `ARGS`.
+    pub args: Vec<String>,
+    /// Leaves `ENV` empty, the script can't read the environment.
+    pub sandbox: bool,
+}
+
+impl ScriptConfig {
+    pub fn new() -> Self {
+        ScriptConfig::default()
+    }
+
+    pub fn engine(self, engine: Backend) -> Self {
+        ScriptConfig { engine, ..self }
+    }
+
+    pub fn args(self, args: Vec<String>) -> Self {
+        ScriptConfig { args, ..self }
+    }
+
+    pub fn sandbox(self, sandbox: bool) -> Self {
+        ScriptConfig { sandbox, ..self }
+    }
+
+    /// The globals a script starts with, in the order the compiler gives them their slots.
+    pub fn globals(&self) -> Vec<(String, Value)> {
+        let args = self
+            .args
+            .iter()
+            .map(|arg| Value::String(arg.as_str().into()))
+            .collect();
+        let env = match self.sandbox {
+            true => HashMap::new(),
+            // Variables that aren't UTF-8 are left out.
+            false => std::env::vars_os()
+                .filter_map(|(name, value)| {
+                    Some((name.into_string().ok()?, value.into_string().ok()?))
+                })
+                .map(|(name, value)| (HashKey::String(name.into()), Value::String(value.into())))
+                .collect(),
+        };
+        vec![
+            ("ARGS".to_string(), Value::Array(Rc::new(args))),
+            ("ENV".to_string(), Value::from(env)),
+        ]
+    }
+}
@@ -0,0 +1,426 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, stdin, stdout, BufRead, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use super::highlight::highlight;
+
+const HISTORY_FILE: &str = ".monkey_history";
+const MAX_HISTORY: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    /// Ctrl-W or Alt-Backspace, deletes the word before the cursor.
+    DeleteWord,
+    /// Ctrl-U, deletes everything before the cursor.
+    KillStart,
+    /// Ctrl-K, deletes everything after the cursor.
+    KillEnd,
+    /// Ctrl-D, ends the input on an empty line.
+    Eof,
+    /// Ctrl-C, drops the input being typed.
+    Interrupt,
+    Tab,
+    Other,
+}
+
+/// Reads one key press from the bytes a terminal in raw mode sends, `None` at the end of input.
+pub fn read_key(bytes: &mut impl Iterator<Item = io::Result<u8>>) -> io::Result<Option<Key>> {
+    let Some(byte) = bytes.next().transpose()? else {
+        return Ok(None);
+    };
+    let key = match byte {
+        b'\r' | b'\n' => Key::Enter,
+        127 | 8 => Key::Backspace,
+        1 => Key::Home,
+        3 => Key::Interrupt,
+        b'\t' => Key::Tab,
+        4 => Key::Eof,
+        5 => Key::End,
+        11 => Key::KillEnd,
+        21 => Key::KillStart,
+        23 => Key::DeleteWord,
+        27 => read_escape(bytes)?,
+        byte if byte < 32 => Key::Other,
+        byte => {
+            let len = byte.leading_ones().max(1) as usize;
+            let mut encoded = vec![byte];
+            for _ in 1..len {
+                match bytes.next().transpose()? {
+                    Some(byte) => encoded.push(byte),
+                    None => break,
+                }
+            }
+            std::str::from_utf8(&encoded)
+                .ok()
+                .and_then(|char| char.chars().next())
+                .map_or(Key::Other, Key::Char)
+        }
+    };
+    Ok(Some(key))
+}
+
+fn read_escape(bytes: &mut impl Iterator<Item = io::Result<u8>>) -> io::Result<Key> {
+    let key = match bytes.next().transpose()? {
+        Some(127) => Key::DeleteWord,
+        Some(b'[') | Some(b'O') => match bytes.next().transpose()? {
+            Some(b'A') => Key::Up,
+            Some(b'B') => Key::Down,
+            Some(b'C') => Key::Right,
+            Some(b'D') => Key::Left,
+            Some(b'H') => Key::Home,
+            Some(b'F') => Key::End,
+            Some(digit @ b'0'..=b'9') => {
+                let mut code = vec![digit];
+                loop {
+                    match bytes.next().transpose()? {
+                        Some(b'~') | None => break,
+                        Some(byte) => code.push(byte),
+                    }
+                }
+                match code.as_slice() {
+                    b"1" | b"7" => Key::Home,
+                    b"4" | b"8" => Key::End,
+                    b"3" => Key::Delete,
+                    _ => Key::Other,
+                }
+            }
+            _ => Key::Other,
+        },
+        _ => Key::Other,
+    };
+    Ok(key)
+}
+
+/// What `LineEditor::read_line` read.
+#[derive(Debug, PartialEq)]
+pub enum Input {
+    Line(String),
+    /// Ctrl-C, the line was dropped.
+    Cancelled,
+    /// The end of input, or Ctrl-D on an empty line.
+    End,
+}
+
+/// The line being edited and where the cursor is in it, in chars.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Line {
+    chars: Vec<char>,
+    cursor: usize,
+}
+
+impl Line {
+    pub fn new(text: &str) -> Self {
+        let chars = text.chars().collect::<Vec<_>>();
+        Line {
+            cursor: chars.len(),
+            chars,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
+    /// The identifier the cursor is at the end of, empty when there is none.
+    pub fn word(&self) -> String {
+        let is_ident = |char: &&char| char.is_alphanumeric() || **char == '_';
+        let word = self.chars[..self.cursor].iter().rev().take_while(is_ident);
+        let mut word = word.collect::<Vec<_>>();
+        word.reverse();
+        word.into_iter().collect()
+    }
+
+    pub fn insert(&mut self, text: &str) {
+        for char in text.chars() {
+            self.edit(Key::Char(char));
+        }
+    }
+
+    /// Applies an editing key, the ones that don't edit the line are ignored.
+    pub fn edit(&mut self, key: Key) {
+        match key {
+            Key::Char(char) => {
+                self.chars.insert(self.cursor, char);
+                self.cursor += 1;
+            }
+            Key::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.chars.remove(self.cursor);
+            }
+            Key::Delete | Key::Eof if self.cursor < self.chars.len() => {
+                self.chars.remove(self.cursor);
+            }
+            Key::Left => self.cursor = self.cursor.saturating_sub(1),
+            Key::Right => self.cursor = (self.cursor + 1).min(self.chars.len()),
+            Key::Home => self.cursor = 0,
+            Key::End => self.cursor = self.chars.len(),
+            Key::DeleteWord => {
+                let mut start = self.cursor;
+                while start > 0 && self.chars[start - 1].is_whitespace() {
+                    start -= 1;
+                }
+                while start > 0 && !self.chars[start - 1].is_whitespace() {
+                    start -= 1;
+                }
+                self.chars.drain(start..self.cursor);
+                self.cursor = start;
+            }
+            Key::KillStart => {
+                self.chars.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            Key::KillEnd => self.chars.truncate(self.cursor),
+            _ => {}
+        }
+    }
+}
+
+/// The `words` that start with `prefix`, sorted and without repeats.
+pub fn completions(prefix: &str, words: &[String]) -> Vec<String> {
+    let mut completions = words
+        .iter()
+        .filter(|word| word.starts_with(prefix))
+        .cloned()
+        .collect::<Vec<_>>();
+    completions.sort();
+    completions.dedup();
+    completions
+}
+
+/// The longest prefix all the `words` share.
+pub fn common_prefix(words: &[String]) -> String {
+    let Some((first, rest)) = words.split_first() else {
+        return String::new();
+    };
+    let mut prefix = first.as_str();
+    for word in rest {
+        let len = prefix
+            .char_indices()
+            .zip(word.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(prefix.len().min(word.len()), |((idx, _), _)| idx);
+        prefix = &prefix[..len];
+    }
+    prefix.to_string()
+}
+
+/// The lines entered so far, up and down walk through them while editing.
+#[derive(Debug, Default)]
+pub struct History {
+    entries: Vec<String>,
+    path: Option<PathBuf>,
+}
+
+impl History {
+    /// Loads `~/.monkey_history`, lines added later are appended to it.
+    pub fn load() -> Self {
+        let path = std::env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE));
+        let mut entries = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|history| history.lines().map(String::from).collect::<Vec<_>>())
+            .unwrap_or_default();
+        entries.drain(..entries.len().saturating_sub(MAX_HISTORY));
+        History { entries, path }
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Skips blank lines and repeats of the last one.
+    pub fn add(&mut self, line: &str) {
+        let line = line.trim_end();
+        if line.trim().is_empty() || self.entries.last().is_some_and(|last| last == line) {
+            return;
+        }
+        self.entries.push(line.to_string());
+        if self.entries.len() > MAX_HISTORY {
+            self.entries.remove(0);
+        }
+        if let Some(path) = &self.path {
+            // History is a convenience, failing to save it shouldn't stop the REPL.
+            let _ = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| writeln!(file, "{line}"));
+        }
+    }
+}
+
+/// Reads lines from the terminal with arrow keys, home/end, word deletion, history and tab
+/// completion. When stdin isn't a terminal it reads plain lines.
+#[derive(Debug, Default)]
+pub struct LineEditor {
+    history: History,
+    words: Vec<String>,
+    highlight: bool,
+}
+
+impl LineEditor {
+    pub fn new() -> Self {
+        LineEditor {
+            history: History::load(),
+            words: vec![],
+            highlight: false,
+        }
+    }
+
+    /// Colors the line being typed, see `highlight`.
+    pub fn with_highlight(self, highlight: bool) -> Self {
+        LineEditor { highlight, ..self }
+    }
+
+    /// The words tab completes to.
+    pub fn set_words(&mut self, words: Vec<String>) {
+        self.words = words;
+    }
+
+    pub fn read_line(&mut self, prompt: &str) -> io::Result<Input> {
+        print!("{prompt}");
+        stdout().flush()?;
+        let line = match RawMode::enable() {
+            Some(_raw) => self.edit(prompt)?,
+            None => {
+                let mut line = String::new();
+                match stdin().lock().read_line(&mut line)? {
+                    0 => Input::End,
+                    _ => Input::Line(line.trim_end_matches(['\n', '\r']).to_string()),
+                }
+            }
+        };
+        if let Input::Line(line) = &line {
+            self.history.add(line);
+        }
+        Ok(line)
+    }
+
+    fn edit(&mut self, prompt: &str) -> io::Result<Input> {
+        let mut line = Line::default();
+        // `entries.len()` is the line being typed, kept in `draft` while browsing.
+        let mut selected = self.history.entries().len();
+        let mut draft = Line::default();
+        let mut bytes = stdin().lock().bytes();
+        let mut last = None;
+        loop {
+            let Some(key) = read_key(&mut bytes)? else {
+                println!();
+                return Ok(Input::End);
+            };
+            match key {
+                Key::Enter => {
+                    print!("\r\n");
+                    stdout().flush()?;
+                    return Ok(Input::Line(line.text()));
+                }
+                Key::Eof if line.is_empty() => {
+                    print!("\r\n");
+                    stdout().flush()?;
+                    return Ok(Input::End);
+                }
+                Key::Interrupt => {
+                    print!("^C\r\n");
+                    stdout().flush()?;
+                    return Ok(Input::Cancelled);
+                }
+                Key::Up if selected > 0 => {
+                    if selected == self.history.entries().len() {
+                        draft = line;
+                    }
+                    selected -= 1;
+                    line = Line::new(&self.history.entries()[selected]);
+                }
+                Key::Down if selected < self.history.entries().len() => {
+                    selected += 1;
+                    line = match self.history.entries().get(selected) {
+                        Some(entry) => Line::new(entry),
+                        None => std::mem::take(&mut draft),
+                    };
+                }
+                Key::Tab => {
+                    let word = line.word();
+                    let completions = completions(&word, &self.words);
+                    let prefix = common_prefix(&completions);
+                    if prefix.len() > word.len() {
+                        line.insert(&prefix[word.len()..]);
+                    } else if last == Some(Key::Tab) && completions.len() > 1 {
+                        print!("\r\n{}\r\n", completions.join("  "));
+                    }
+                }
+                key => line.edit(key),
+            }
+            last = Some(key);
+            redraw(prompt, &line, self.highlight)?;
+        }
+    }
+}
+
+fn redraw(prompt: &str, line: &Line, color: bool) -> io::Result<()> {
+    let mut out = stdout().lock();
+    let text = match color {
+        true => highlight(&line.text()),
+        false => line.text(),
+    };
+    write!(out, "\r{prompt}{text}\x1b[K")?;
+    let back = line.text().chars().count() - line.cursor();
+    if back > 0 {
+        write!(out, "\x1b[{back}D")?;
+    }
+    out.flush()
+}
+
+/// Puts the terminal in raw mode through `stty` while it lives, Ctrl-C arrives as a key.
+struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    fn enable() -> Option<RawMode> {
+        if !stdin().is_terminal() {
+            return None;
+        }
+        let saved = stty(&["-g"])?;
+        stty(&["-icanon", "-echo", "-isig", "min", "1"])?;
+        Some(RawMode {
+            saved: saved.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        stty(&[&self.saved]);
+    }
+}
+
+fn stty(args: &[&str]) -> Option<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,88 @@
+use super::editor::*;
+
+fn keys(input: &[u8]) -> Vec<Key> {
+    let mut bytes = input.iter().map(|byte| Ok(*byte));
+    std::iter::from_fn(|| read_key(&mut bytes).unwrap()).collect()
+}
+
+#[test]
+fn test_read_key() {
+    assert_eq!(
+        keys(b"a\x1b[A\x1b[B\x1b[C\x1b[D\x1b[H\x1bOF\x1b[3~\x1b[1~\x7f\x17\x1b\x7f\r\x04\x03\t"),
+        vec![
+            Key::Char('a'),
+            Key::Up,
+            Key::Down,
+            Key::Right,
+            Key::Left,
+            Key::Home,
+            Key::End,
+            Key::Delete,
+            Key::Home,
+            Key::Backspace,
+            Key::DeleteWord,
+            Key::DeleteWord,
+            Key::Enter,
+            Key::Eof,
+            Key::Interrupt,
+            Key::Tab,
+        ]
+    );
+    assert_eq!(
+        keys("é日".as_bytes()),
+        vec![Key::Char('é'), Key::Char('日')]
+    );
+}
+
+#[test]
+fn test_line_editing() {
+    let mut line = Line::default();
+    for key in "let x = 1".chars().map(Key::Char) {
+        line.edit(key);
+    }
+    line.edit(Key::Home);
+    line.edit(Key::Delete);
+    line.edit(Key::Char('L'));
+    assert_eq!((line.text().as_str(), line.cursor()), ("Let x = 1", 1));
+
+    line.edit(Key::End);
+    line.edit(Key::Left);
+    line.edit(Key::DeleteWord);
+    assert_eq!((line.text().as_str(), line.cursor()), ("Let x 1", 6));
+
+    line.edit(Key::DeleteWord);
+    line.edit(Key::Backspace);
+    assert_eq!(line.text(), "Let1");
+
+    line.edit(Key::Left);
+    line.edit(Key::KillEnd);
+    line.edit(Key::Right);
+    line.edit(Key::KillStart);
+    assert_eq!((line.text().as_str(), line.cursor()), ("", 0));
+}
+
+#[test]
+fn test_history() {
+    let mut history = History::default();
+    for line in ["1 + 1", "", "1 + 1\n", "let x = 2;"] {
+        history.add(line);
+    }
+
+    assert_eq!(history.entries(), ["1 + 1", "let x = 2;"]);
+}
+
+#[test]
+fn test_completion() {
+    let words = ["len", "let", "last", "length", "puts", "let"].map(String::from);
+    let mut line = Line::new("let x = le");
+    assert_eq!(line.word(), "le");
+    assert_eq!(completions(&line.word(), &words), ["len", "length", "let"]);
+    assert_eq!(common_prefix(&completions("len", &words)), "len");
+    assert_eq!(common_prefix(&completions("p", &words)), "puts");
+    assert_eq!(common_prefix(&completions("x", &words)), "");
+
+    line.edit(Key::Char(' '));
+    assert_eq!(line.word(), "");
+    line.insert("fizz_2");
+    assert_eq!(line.word(), "fizz_2");
+}
@@ -0,0 +1,472 @@
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::compiler::session::Session;
+use crate::compiler::symbol_table::{SymbolScope, SymbolTable};
+use crate::compiler::Compiler;
+use crate::diagnostics::Diagnostic;
+use crate::eval::builtin::new_builtins;
+use crate::eval::environment::Environment;
+use crate::eval::value::Value;
+use crate::eval::Eval;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::vm::{Completion, Vm};
+
+use super::interrupt::INTERRUPTED;
+use super::{in_file, render_bytecode, render_parser_errors};
+
+/// The ways the REPL can run input.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Backend {
+    Eval,
+    #[default]
+    Vm,
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "eval" => Ok(Backend::Eval),
+            "vm" => Ok(Backend::Vm),
+            name => Err(format!("unknown engine: {name}, expected eval or vm")),
+        }
+    }
+}
+
+impl Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Backend::Eval => write!(f, "eval"),
+            Backend::Vm => write!(f, "vm"),
+        }
+    }
+}
+
+/// Whether `value` means the same to both engines. Functions only run on the engine that
+/// made them.
+pub fn is_portable(value: &Value) -> bool {
+    match value {
+        Value::Function { .. } | Value::CompiledFunction { .. } | Value::Closure { .. } => false,
+        Value::Array(values) => values.iter().all(is_portable),
+        Value::Hash(pairs) => pairs.values().all(is_portable),
+        Value::Memoized(memo) => is_portable(&memo.function),
+        _ => true,
+    }
+}
+
+/// What running one input left for the REPL.
+pub enum Outcome {
+    Value(Value),
+    /// Nothing to print, like after a `let`.
+    Nothing,
+    /// The rendered errors of input that doesn't parse or compile, nothing of it ran.
+    Invalid(String),
+    /// The rendered error that stopped the input while it ran.
+    Failed(String),
+    Exit(i64),
+}
+
+/// A name `:env` lists, `slot` is where the vm keeps its value.
+pub struct Binding {
+    pub name: String,
+    pub slot: Option<(SymbolScope, usize)>,
+    pub value: Value,
+}
+
+/// How long the phases of the last input took, the evaluator has no compile phase.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Timings {
+    pub parse: Duration,
+    pub compile: Option<Duration>,
+    pub execute: Duration,
+}
+
+impl Display for Timings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "parse {:?}", self.parse)?;
+        if let Some(compile) = self.compile {
+            write!(f, ", compile {compile:?}")?;
+        }
+        write!(f, ", execute {:?}", self.execute)
+    }
+}
+
+/// The spread of the times a program took over several runs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchStats {
+    pub iters: usize,
+    pub mean: Duration,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl BenchStats {
+    pub fn new(samples: &[Duration]) -> Option<Self> {
+        let min = *samples.iter().min()?;
+        let max = *samples.iter().max()?;
+        let mean = samples.iter().sum::<Duration>() / u32::try_from(samples.len()).ok()?;
+        Some(BenchStats {
+            iters: samples.len(),
+            mean,
+            min,
+            max,
+        })
+    }
+}
+
+impl Display for BenchStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} iters: mean {:?}, min {:?}, max {:?}",
+            self.iters, self.mean, self.min, self.max
+        )
+    }
+}
+
+/// An engine the REPL runs input with, the loop and the `:` commands work the same on each.
+pub trait Repl {
+    /// `file` is where `source` was read from, errors point into it.
+    fn run(&mut self, source: &str, file: Option<&str>) -> Outcome;
+
+    fn timings(&self) -> Timings;
+
+    /// Forgets everything the session has defined.
+    fn reset(&mut self);
+
+    /// The globals the session has defined, builtins left out.
+    fn bindings(&self) -> Vec<(String, Value)>;
+
+    fn define(&mut self, name: String, value: Value);
+
+    /// What `:env` lists, sorted.
+    fn env(&self) -> Vec<Binding> {
+        let mut bindings = self.bindings();
+        bindings.sort_by(|(a, _), (b, _)| a.cmp(b));
+        bindings
+            .into_iter()
+            .map(|(name, value)| Binding {
+                name,
+                slot: None,
+                value,
+            })
+            .collect()
+    }
+
+    /// A compiler that sees the session's globals, changes through it aren't kept.
+    fn compiler(&self) -> Compiler {
+        Compiler::new()
+    }
+
+    fn save_session(&self, _path: &str) -> Result<(), String> {
+        Err("sessions need the vm engine".into())
+    }
+
+    fn load_session(&mut self, _path: &str) -> Result<(), String> {
+        Err("sessions need the vm engine".into())
+    }
+
+    /// Prints the instructions each input compiles to.
+    fn set_emit(&mut self, _emit: bool) -> Result<(), String> {
+        Err("emitting bytecode needs the vm engine".into())
+    }
+}
+
+/// Runs input with the tree-walking evaluator.
+pub struct EvalRepl {
+    eval: Eval,
+    timings: Timings,
+}
+
+impl EvalRepl {
+    pub fn new() -> Self {
+        let env = Environment::from(new_builtins());
+        EvalRepl {
+            eval: Eval::new(Rc::new(RefCell::new(env))).with_interrupt(&INTERRUPTED),
+            timings: Timings::default(),
+        }
+    }
+}
+
+impl Default for EvalRepl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Repl for EvalRepl {
+    fn run(&mut self, source: &str, file: Option<&str>) -> Outcome {
+        let start = Instant::now();
+        let mut parser = Parser::new(Lexer::from(source));
+        let program = parser.parse_program();
+        self.timings = Timings {
+            parse: start.elapsed(),
+            ..Timings::default()
+        };
+        if let Some(errors) = render_parser_errors(&parser, file) {
+            return Outcome::Invalid(errors);
+        }
+        let checkpoint = self.eval.env.borrow().checkpoint();
+        let start = Instant::now();
+        let result = self.eval.eval_program(program);
+        self.timings.execute = start.elapsed();
+        match result {
+            Ok(Value::Let) => Outcome::Nothing,
+            Ok(Value::Exit(code)) => Outcome::Exit(code),
+            Ok(value) => Outcome::Value(value),
+            Err(err) => {
+                self.eval.env.borrow_mut().rollback(checkpoint);
+                let file = file.map_or(String::new(), |file| format!("{file}:"));
+                Outcome::Failed(match err.position() {
+                    Some((line, column)) => format!("Err at {file}{line}:{column}: {err}\n"),
+                    None => format!("Err: {err}\n"),
+                })
+            }
+        }
+    }
+
+    fn timings(&self) -> Timings {
+        self.timings
+    }
+
+    fn reset(&mut self) {
+        *self = EvalRepl::new();
+    }
+
+    fn bindings(&self) -> Vec<(String, Value)> {
+        self.eval
+            .env
+            .borrow()
+            .flatten()
+            .store
+            .into_iter()
+            .filter(|(_, value)| !matches!(value, Value::Builtin(_)))
+            .collect()
+    }
+
+    fn define(&mut self, name: String, value: Value) {
+        self.eval.set_global(&name, value);
+    }
+}
+
+/// Compiles input to bytecode and runs it on the vm, the globals stay between inputs.
+pub struct VmRepl {
+    symbol_table: Arc<RwLock<SymbolTable>>,
+    constants: Rc<RefCell<Vec<Value>>>,
+    globals: Rc<RefCell<Vec<Value>>>,
+    report: bool,
+    profile: bool,
+    emit: bool,
+    timings: Timings,
+}
+
+impl VmRepl {
+    pub fn new() -> Self {
+        VmRepl {
+            symbol_table: Arc::new(RwLock::new(SymbolTable::new_with_builtins())),
+            constants: Rc::new(RefCell::new(vec![])),
+            globals: Rc::new(RefCell::new(vec![])),
+            report: false,
+            profile: false,
+            emit: false,
+            timings: Timings::default(),
+        }
+    }
+
+    /// Prints the compiler's report for every input.
+    pub fn with_report(self, report: bool) -> Self {
+        VmRepl { report, ..self }
+    }
+
+    /// Prints the vm's profile for every input.
+    pub fn with_profile(self, profile: bool) -> Self {
+        VmRepl { profile, ..self }
+    }
+
+    /// Prints the instructions and new function constants of every input.
+    pub fn with_emit(self, emit: bool) -> Self {
+        VmRepl { emit, ..self }
+    }
+}
+
+impl Default for VmRepl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Repl for VmRepl {
+    fn run(&mut self, source: &str, file: Option<&str>) -> Outcome {
+        let start = Instant::now();
+        let mut parser = Parser::new(Lexer::from(source));
+        let program = parser.parse_program();
+        self.timings = Timings {
+            parse: start.elapsed(),
+            ..Timings::default()
+        };
+        if let Some(errors) = render_parser_errors(&parser, file) {
+            return Outcome::Invalid(errors);
+        }
+
+        // The input compiles and runs against copies of the session's state, they only replace
+        // it once the input ran, so a failing input leaves no symbols or globals behind.
+        let symbol_table = Arc::new(RwLock::new(self.symbol_table.read().unwrap().clone()));
+        let constants = Rc::new(RefCell::new(self.constants.borrow().clone()));
+        let globals = Rc::new(RefCell::new(self.globals.borrow().clone()));
+        let known_constants = constants.borrow().len();
+
+        let start = Instant::now();
+        let mut compiler = Compiler::new_with_state(symbol_table.clone(), constants.clone());
+        let compiled = compiler.compile_program(program);
+        self.timings.compile = Some(start.elapsed());
+        if let Err(err) = compiled {
+            return Outcome::Invalid(match err.position() {
+                Some((line, column)) => {
+                    let diagnostic =
+                        Diagnostic::new(format!("Compiler error: {err}"), line, column);
+                    in_file(diagnostic, file).render(source)
+                }
+                None => format!("Compiler error: {err}\n"),
+            });
+        }
+
+        if self.report {
+            print!("{}", compiler.report());
+        }
+        if self.emit {
+            print!("{}", render_bytecode(&compiler.bytecode(), known_constants));
+        }
+
+        let mut machine = Vm::new_with_global_store(compiler.bytecode(), globals.clone())
+            .with_interrupt(&INTERRUPTED);
+        if self.profile {
+            machine = machine.with_profiling();
+        }
+
+        let start = Instant::now();
+        let result = machine.run();
+        self.timings.execute = start.elapsed();
+        if let Some(report) = machine.profile_report() {
+            print!("{report}");
+        }
+        let outcome = match result {
+            Ok(Completion::Exit(code)) => Outcome::Exit(code),
+            Ok(Completion::Finished) => machine
+                .last_popped_element
+                .map_or(Outcome::Nothing, Outcome::Value),
+            Err(err) => {
+                let err = err.locate(&compiler.source_map(file.unwrap_or_default(), source));
+                return Outcome::Failed(match err.position() {
+                    Some((line, column)) => {
+                        let diagnostic = Diagnostic::new(
+                            format!("Executing bytecode error: {err}"),
+                            line,
+                            column,
+                        );
+                        in_file(diagnostic, file).render(source)
+                    }
+                    None => format!("Executing bytecode error: {err}\n"),
+                });
+            }
+        };
+        self.symbol_table = symbol_table;
+        self.constants = constants;
+        self.globals = globals;
+        outcome
+    }
+
+    fn timings(&self) -> Timings {
+        self.timings
+    }
+
+    fn reset(&mut self) {
+        *self = VmRepl::new()
+            .with_report(self.report)
+            .with_profile(self.profile)
+            .with_emit(self.emit);
+    }
+
+    fn bindings(&self) -> Vec<(String, Value)> {
+        let globals = self.globals.borrow();
+        self.symbol_table
+            .read()
+            .unwrap()
+            .store
+            .values()
+            .filter(|symbol| symbol.scope == SymbolScope::GlobalScope)
+            .map(|symbol| {
+                let value = globals.get(symbol.index).cloned();
+                (symbol.name.clone(), value.unwrap_or(Value::Null))
+            })
+            .collect()
+    }
+
+    fn define(&mut self, name: String, value: Value) {
+        let mut symbol_table = self.symbol_table.write().unwrap();
+        let symbol = match symbol_table.resolve(&name) {
+            Some(symbol) if symbol.scope == SymbolScope::GlobalScope => symbol,
+            _ => symbol_table.define(name),
+        };
+        let mut globals = self.globals.borrow_mut();
+        if globals.len() <= symbol.index {
+            globals.resize(symbol.index + 1, Value::Null);
+        }
+        globals[symbol.index] = value;
+    }
+
+    /// Walks the symbol table, every global with its index in the globals.
+    fn env(&self) -> Vec<Binding> {
+        let globals = self.globals.borrow();
+        let symbol_table = self.symbol_table.read().unwrap();
+        let mut symbols = symbol_table
+            .store
+            .values()
+            .filter(|symbol| symbol.scope != SymbolScope::BuiltinScope)
+            .collect::<Vec<_>>();
+        symbols.sort_by_key(|symbol| symbol.index);
+        symbols
+            .into_iter()
+            .map(|symbol| Binding {
+                name: symbol.name.clone(),
+                slot: Some((symbol.scope.clone(), symbol.index)),
+                value: globals.get(symbol.index).cloned().unwrap_or(Value::Null),
+            })
+            .collect()
+    }
+
+    fn compiler(&self) -> Compiler {
+        Compiler::new_with_state(
+            Arc::new(RwLock::new(self.symbol_table.read().unwrap().clone())),
+            Rc::new(RefCell::new(self.constants.borrow().clone())),
+        )
+    }
+
+    fn set_emit(&mut self, emit: bool) -> Result<(), String> {
+        self.emit = emit;
+        Ok(())
+    }
+
+    fn save_session(&self, path: &str) -> Result<(), String> {
+        let session = Session {
+            symbol_table: self.symbol_table.read().unwrap().clone(),
+            constants: self.constants.borrow().clone(),
+            globals: self.globals.borrow().clone(),
+        };
+        session.save(path).map_err(|err| err.to_string())
+    }
+
+    fn load_session(&mut self, path: &str) -> Result<(), String> {
+        let session = Session::load(path).map_err(|err| err.to_string())?;
+        *self.symbol_table.write().unwrap() = session.symbol_table;
+        *self.constants.borrow_mut() = session.constants;
+        *self.globals.borrow_mut() = session.globals;
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use crate::eval::value::Value;
+
+use super::engine::{Backend, BenchStats, EvalRepl, Outcome, Repl, VmRepl};
+
+fn run(repl: &mut dyn Repl, source: &str) -> Result<Option<Value>, String> {
+    match repl.run(source, None) {
+        Outcome::Value(value) => Ok(Some(value)),
+        Outcome::Nothing => Ok(None),
+        Outcome::Invalid(errors) | Outcome::Failed(errors) => Err(errors),
+        Outcome::Exit(code) => panic!("unexpected exit({code})"),
+    }
+}
+
+#[test]
+fn test_reset() {
+    let repls: [Box<dyn Repl>; 2] = [Box::new(EvalRepl::new()), Box::new(VmRepl::new())];
+    for mut repl in repls {
+        run(repl.as_mut(), "let x = 1; let f = fn() { x };").unwrap();
+        assert_eq!(run(repl.as_mut(), "f()"), Ok(Some(Value::Int(1))));
+        assert_eq!(repl.bindings().len(), 2);
+
+        repl.reset();
+        assert!(repl.bindings().is_empty());
+        assert!(run(repl.as_mut(), "x").is_err());
+        assert_eq!(run(repl.as_mut(), "len([1, 2])"), Ok(Some(Value::Int(2))));
+        run(repl.as_mut(), "let y = 2;").unwrap();
+        assert_eq!(run(repl.as_mut(), "y"), Ok(Some(Value::Int(2))));
+    }
+}
+
+#[test]
+fn test_failed_input_leaves_no_state() {
+    let repls: [Box<dyn Repl>; 2] = [Box::new(EvalRepl::new()), Box::new(VmRepl::new())];
+    for mut repl in repls {
+        run(repl.as_mut(), "let x = 1;").unwrap();
+        let failing = [
+            "let x = 2; let y = 3; y + true",
+            "let x = 2; let z = missing;",
+            "let x = 2; let f = fn() { 1 }; f(1)",
+        ];
+        for input in failing {
+            assert!(run(repl.as_mut(), input).is_err(), "{input}");
+        }
+        let mut names = repl
+            .bindings()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["x"]);
+        assert_eq!(run(repl.as_mut(), "x"), Ok(Some(Value::Int(1))));
+        assert_eq!(
+            run(repl.as_mut(), "let g = fn(a) { a * 2 }; g(x)"),
+            Ok(Some(Value::Int(2)))
+        );
+    }
+}
+
+#[test]
+fn test_engines_agree() {
+    assert_eq!("eval".parse(), Ok(Backend::Eval));
+    assert_eq!("vm".parse(), Ok(Backend::Vm));
+    assert!("jit".parse::<Backend>().is_err());
+
+    let programs = [
+        "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(15)",
+        "let map = fn(arr, f) { if (len(arr) == 0) { [] } else { push(map(rest(arr), f), f(first(arr))) } }; map([1, 2, 3], fn(x) { x * 2 })",
+        "let adder = fn(a) { fn(b) { a + b } }; adder(2)(3)",
+        "{\"a\": 1, true: 2}[true]",
+        "\"mon\" + \"key\"",
+    ];
+    for program in programs {
+        let expected = run(&mut EvalRepl::new(), program);
+        assert_eq!(run(&mut VmRepl::new(), program), expected, "{program}");
+    }
+}
+
+#[test]
+fn test_bench_stats() {
+    let samples = [3, 1, 2, 6].map(Duration::from_millis);
+    let stats = BenchStats::new(&samples).unwrap();
+    assert_eq!(
+        (stats.iters, stats.mean, stats.min, stats.max),
+        (
+            4,
+            Duration::from_millis(3),
+            Duration::from_millis(1),
+            Duration::from_millis(6)
+        )
+    );
+    assert_eq!(stats.to_string(), "4 iters: mean 3ms, min 1ms, max 6ms");
+    assert_eq!(BenchStats::new(&[]), None);
+}
+
+#[test]
+fn test_invalid_and_failed_input() {
+    let repls: [Box<dyn Repl>; 2] = [Box::new(EvalRepl::new()), Box::new(VmRepl::new())];
+    for mut repl in repls {
+        assert!(matches!(repl.run("let x = ;", None), Outcome::Invalid(_)));
+        assert!(matches!(repl.run("1 + true", None), Outcome::Failed(_)));
+        assert!(matches!(repl.run("exit(3)", None), Outcome::Exit(3)));
+    }
+    assert!(matches!(
+        VmRepl::new().run("missing", None),
+        Outcome::Invalid(_)
+    ));
+
+    let Outcome::Failed(errors) = VmRepl::new().run("let a = 1;\na + true", Some("a.mk")) else {
+        panic!("expected a runtime error");
+    };
+    assert_eq!(
+        errors,
+        "Executing bytecode error: unsupported values for binary operation: true 1\n --> a.mk:2:1\n  |\n2 | a + true\n  | ^\n"
+    );
+}
@@ -0,0 +1,79 @@
+use std::io::{stdout, IsTerminal};
+
+use crate::eval::value::Value;
+use crate::lexer::token::Token;
+use crate::lexer::Lexer;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Style {
+    Keyword,
+    String,
+    Number,
+    Comment,
+    Error,
+}
+
+impl Style {
+    fn code(self) -> &'static str {
+        match self {
+            Style::Keyword => "35",
+            Style::String => "32",
+            Style::Number => "33",
+            Style::Comment => "90",
+            Style::Error => "31",
+        }
+    }
+
+    fn of(token: &Token) -> Option<Style> {
+        match token {
+            Token::Function
+            | Token::Let
+            | Token::True
+            | Token::False
+            | Token::If
+            | Token::Else
+            | Token::Return => Some(Style::Keyword),
+            Token::String(_) | Token::UnterminatedString => Some(Style::String),
+            Token::Int(_) => Some(Style::Number),
+            Token::Comment(_) => Some(Style::Comment),
+            Token::Illegal(_) => Some(Style::Error),
+            _ => None,
+        }
+    }
+}
+
+/// Whether stdout takes colors, it has to be a terminal and `NO_COLOR` unset.
+pub fn color_enabled() -> bool {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    stdout().is_terminal() && !no_color
+}
+
+pub fn paint(text: &str, style: Style) -> String {
+    format!("\x1b[{}m{text}\x1b[0m", style.code())
+}
+
+/// Colors the keywords, strings, numbers and comments of `source`, the text stays the same.
+pub fn highlight(source: &str) -> String {
+    let mut out = String::new();
+    let mut end = 0;
+    for token in Lexer::from(source) {
+        let Some(style) = Style::of(&token.token) else {
+            continue;
+        };
+        let span = token.span.start..token.span.end.min(source.len());
+        out.push_str(&source[end..span.start]);
+        out.push_str(&paint(&source[span.clone()], style));
+        end = span.end;
+    }
+    out.push_str(&source[end..]);
+    out
+}
+
+/// Highlights `text`, the way `value` was printed. Strings are painted whole, a `"` inside one
+/// would throw the lexer off.
+pub fn highlight_value(value: &Value, text: &str) -> String {
+    match value {
+        Value::String(_) => paint(text, Style::String),
+        _ => highlight(text),
+    }
+}
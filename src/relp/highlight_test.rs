@@ -0,0 +1,50 @@
+use crate::eval::value::Value;
+
+use super::highlight::*;
+
+#[test]
+fn test_highlight() {
+    let tests = [
+        ("x + y", "x + y".to_string()),
+        (
+            "let s = \"hé\"; // é",
+            format!(
+                "{} s = {}; {}",
+                paint("let", Style::Keyword),
+                paint("\"hé\"", Style::String),
+                paint("// é", Style::Comment)
+            ),
+        ),
+        (
+            "if (true) { 10 } @",
+            format!(
+                "{} ({}) {{ {} }} {}",
+                paint("if", Style::Keyword),
+                paint("true", Style::Keyword),
+                paint("10", Style::Number),
+                paint("@", Style::Error)
+            ),
+        ),
+        ("\"open", paint("\"open", Style::String)),
+    ];
+
+    for (source, expected) in tests {
+        assert_eq!(highlight(source), expected, "{source}");
+    }
+}
+
+#[test]
+fn test_highlight_value() {
+    assert_eq!(
+        highlight_value(&Value::String("if".into()), "\"if\""),
+        paint("\"if\"", Style::String)
+    );
+    assert_eq!(
+        highlight_value(&Value::Array(vec![].into()), "[1,true]"),
+        format!(
+            "[{},{}]",
+            paint("1", Style::Number),
+            paint("true", Style::Keyword)
+        )
+    );
+}
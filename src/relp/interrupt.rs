@@ -0,0 +1,35 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set by Ctrl-C, the engines stop the running input once they see it.
+pub static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+mod unix {
+    const SIGINT: i32 = 2;
+
+    extern "C" {
+        fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+    }
+
+    extern "C" fn on_interrupt(_: i32) {
+        super::INTERRUPTED.store(true, super::Ordering::Relaxed);
+    }
+
+    pub fn install() {
+        // Storing to an atomic is all the handler does, which is safe inside a signal handler.
+        unsafe {
+            signal(SIGINT, on_interrupt);
+        }
+    }
+}
+
+/// Makes Ctrl-C set `INTERRUPTED` instead of killing the process.
+pub fn install() {
+    #[cfg(unix)]
+    unix::install();
+}
+
+/// Clears a Ctrl-C that came in before the next input runs.
+pub fn clear() {
+    INTERRUPTED.store(false, Ordering::Relaxed);
+}
@@ -0,0 +1,738 @@
+pub mod config;
+pub mod editor;
+#[cfg(test)]
+mod editor_test;
+pub mod engine;
+#[cfg(test)]
+mod engine_test;
+pub mod highlight;
+#[cfg(test)]
+mod highlight_test;
+pub mod interrupt;
+pub mod pretty;
+#[cfg(test)]
+mod pretty_test;
+#[cfg(test)]
+mod tests;
+
+use std::io;
+
+use crate::ast::dump::ast_dump;
+use crate::compiler::constant::Constant;
+use crate::compiler::ByteCode;
+use crate::diagnostics::{suggest, Diagnostic};
+use crate::eval::builtin::BUILTINS;
+use crate::eval::value::Value;
+use crate::lexer::keywords::Keywords;
+use crate::lexer::token::Token;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+
+use self::config::{ReplConfig, ScriptConfig};
+use self::editor::{Input, LineEditor};
+use self::engine::{is_portable, Backend, BenchStats, EvalRepl, Outcome, Repl, VmRepl};
+use self::highlight::{color_enabled, highlight_value, paint, Style};
+use self::pretty::{pretty, DEFAULT_WIDTH};
+
+fn in_file(diagnostic: Diagnostic, file: Option<&str>) -> Diagnostic {
+    match file {
+        Some(file) => diagnostic.with_file(file),
+        None => diagnostic,
+    }
+}
+
+/// `None` when the parser has no errors.
+fn render_parser_errors(parser: &Parser, file: Option<&str>) -> Option<String> {
+    if parser.errors().is_empty() {
+        return None;
+    }
+    let errors = parser
+        .errors()
+        .iter()
+        .map(|err| in_file(Diagnostic::from(err), file).render(parser.source()))
+        .collect();
+    Some(errors)
+}
+
+fn print_parser_errors(parser: &Parser) -> bool {
+    match render_parser_errors(parser, None) {
+        Some(errors) => {
+            print!("{errors}");
+            true
+        }
+        None => false,
+    }
+}
+
+/// The instructions of `bytecode` and of the functions among its constants from `known` on.
+fn render_bytecode(bytecode: &ByteCode, known: usize) -> String {
+    let mut out = format!("{}\n", bytecode.instructions);
+    for (idx, constant) in bytecode.constants.iter().enumerate().skip(known) {
+        if let Constant::CompiledFunction { instructions, .. } = constant {
+            out.push_str(&format!("constant {idx}: {instructions}\n"));
+        }
+    }
+    out
+}
+
+/// How many results `_1`, `_2`, ... go back, `_` is always the last one.
+const RESULT_HISTORY: usize = 10;
+
+/// Puts `value` in front of the `history` of printed results and returns the names to bind.
+fn remember_result(history: &mut Vec<Value>, value: Value) -> Vec<(String, Value)> {
+    history.insert(0, value.clone());
+    history.truncate(RESULT_HISTORY);
+    let mut bindings = vec![("_".to_string(), value)];
+    bindings.extend(
+        history
+            .iter()
+            .enumerate()
+            .map(|(idx, value)| (format!("_{}", idx + 1), value.clone())),
+    );
+    bindings
+}
+
+/// Whether `source` stops inside brackets or a string, the REPL then keeps reading lines.
+fn is_incomplete(source: &str) -> bool {
+    let mut depth = 0;
+    for token in Lexer::from(source) {
+        match token.token {
+            Token::Lparen | Token::Lbrace | Token::Lbracket => depth += 1,
+            Token::Rparen | Token::Rbrace | Token::Rbracket => depth -= 1,
+            Token::UnterminatedString => return true,
+            _ => {}
+        }
+    }
+    depth > 0
+}
+
+/// Cuts a file into inputs the way the REPL reads them, a line and the lines after it while the
+/// input is incomplete. Blank inputs are left out.
+fn split_inputs(source: &str) -> Vec<String> {
+    let mut inputs = vec![];
+    let mut buffer = String::new();
+    for line in source.lines() {
+        buffer.push_str(line);
+        buffer.push('\n');
+        if !is_incomplete(&buffer) {
+            inputs.push(std::mem::take(&mut buffer));
+        }
+    }
+    inputs.push(buffer);
+    inputs.retain(|input| !input.trim().is_empty());
+    inputs
+}
+
+/// Reads a line, and more lines behind the continuation prompt while the input is incomplete.
+/// Ctrl-C drops the whole input and starts over. Returns `None` once the input ends.
+fn read_input(editor: &mut LineEditor, config: &ReplConfig) -> io::Result<Option<String>> {
+    'input: loop {
+        let mut buffer = match editor.read_line(&config.prompt)? {
+            Input::Line(line) => line,
+            Input::Cancelled => continue,
+            Input::End => return Ok(None),
+        };
+        buffer.push('\n');
+        while is_incomplete(&buffer) {
+            match editor.read_line(&config.continuation_prompt)? {
+                Input::Line(line) => {
+                    buffer.push_str(&line);
+                    buffer.push('\n');
+                }
+                Input::Cancelled => continue 'input,
+                Input::End => break,
+            }
+        }
+        return Ok(Some(buffer));
+    }
+}
+
+/// What the REPL does after a command.
+enum Next {
+    Continue,
+    Quit,
+    /// A program called `exit`.
+    Exit(i64),
+}
+
+/// A `:name args` input, `run` gets the text after the name.
+struct Command {
+    name: &'static str,
+    args: &'static str,
+    help: &'static str,
+    run: fn(&mut Shell, &str) -> Next,
+}
+
+const COMMANDS: &[Command] = &[
+    Command {
+        name: "help",
+        args: "",
+        help: "list the commands",
+        run: help_command,
+    },
+    Command {
+        name: "quit",
+        args: "",
+        help: "leave the REPL",
+        run: |_, _| Next::Quit,
+    },
+    Command {
+        name: "mode",
+        args: "[eval|vm]",
+        help: "show or switch the engine, globals that aren't functions carry over",
+        run: mode_command,
+    },
+    Command {
+        name: "load",
+        args: "<path>",
+        help: "run a file and keep what it defines",
+        run: load_command,
+    },
+    Command {
+        name: "doc",
+        args: "[builtin]",
+        help: "show how to use a builtin, or list them",
+        run: doc_command,
+    },
+    Command {
+        name: "save",
+        args: "<path>",
+        help: "write the inputs that ran so far to a file",
+        run: save_command,
+    },
+    Command {
+        name: "replay",
+        args: "<path>",
+        help: "run a file input by input, like typing it",
+        run: replay_command,
+    },
+    Command {
+        name: "reset",
+        args: "",
+        help: "forget everything defined so far",
+        run: reset_command,
+    },
+    Command {
+        name: "env",
+        args: "",
+        help: "show the globals defined so far, with their slots on the vm",
+        run: env_command,
+    },
+    Command {
+        name: "ast",
+        args: "<code>",
+        help: "show the syntax tree of code",
+        run: ast_command,
+    },
+    Command {
+        name: "tokens",
+        args: "<code>",
+        help: "show the tokens of code",
+        run: tokens_command,
+    },
+    Command {
+        name: "bytecode",
+        args: "<code>",
+        help: "show the instructions code compiles to",
+        run: bytecode_command,
+    },
+    Command {
+        name: "emit",
+        args: "[on|off]",
+        help: "show the instructions of every input, vm only",
+        run: emit_command,
+    },
+    Command {
+        name: "time",
+        args: "[on|off]",
+        help: "show how long parsing, compiling and running each input took",
+        run: time_command,
+    },
+    Command {
+        name: "width",
+        args: "[columns]",
+        help: "show or set where results break over lines",
+        run: width_command,
+    },
+    Command {
+        name: "save-session",
+        args: "<path>",
+        help: "save the compiled globals to a file",
+        run: save_session_command,
+    },
+    Command {
+        name: "load-session",
+        args: "<path>",
+        help: "load globals saved with :save-session",
+        run: load_session_command,
+    },
+];
+
+fn help_command(_: &mut Shell, _: &str) -> Next {
+    for command in COMMANDS {
+        let usage = format!(":{} {}", command.name, command.args);
+        println!("{usage:<22} {}", command.help);
+    }
+    Next::Continue
+}
+
+fn mode_command(shell: &mut Shell, name: &str) -> Next {
+    if name.is_empty() {
+        println!("{}", shell.engine);
+        return Next::Continue;
+    }
+    let engine = match name.parse::<Backend>() {
+        Ok(engine) => engine,
+        Err(err) => {
+            println!("{err}");
+            return Next::Continue;
+        }
+    };
+    let mut repl = shell.new_repl(engine);
+    let mut left_out = vec![];
+    for (name, value) in shell.repl.bindings() {
+        if is_portable(&value) {
+            repl.define(name, value);
+        } else {
+            left_out.push(name);
+        }
+    }
+    shell.repl = repl;
+    shell.engine = engine;
+    if !left_out.is_empty() {
+        left_out.sort();
+        println!("left out functions: {}", left_out.join(", "));
+    }
+    Next::Continue
+}
+
+fn load_command(shell: &mut Shell, path: &str) -> Next {
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => {
+            println!("could not read {path}: {err}");
+            return Next::Continue;
+        }
+    };
+    interrupt::clear();
+    match shell.repl.run(&source, Some(path)) {
+        Outcome::Value(_) | Outcome::Nothing => println!("loaded {path}"),
+        Outcome::Invalid(errors) | Outcome::Failed(errors) => shell.print_errors(&errors),
+        Outcome::Exit(code) => return Next::Exit(code),
+    }
+    Next::Continue
+}
+
+fn doc_command(_: &mut Shell, name: &str) -> Next {
+    if name.is_empty() {
+        for builtin in BUILTINS.iter() {
+            println!("{:<44} {}", builtin.signature, builtin.doc);
+        }
+        return Next::Continue;
+    }
+    match BUILTINS.lookup(name) {
+        Some(builtin) => print!("{}", builtin.usage()),
+        None => match suggest(name, BUILTINS.iter().map(|builtin| builtin.name)) {
+            Some(suggestion) => println!("unknown builtin {name}, did you mean `{suggestion}`?"),
+            None => println!("unknown builtin {name}, see :doc"),
+        },
+    }
+    Next::Continue
+}
+
+fn save_command(shell: &mut Shell, path: &str) -> Next {
+    match std::fs::write(path, shell.transcript.concat()) {
+        Ok(()) => println!("saved {} inputs to {path}", shell.transcript.len()),
+        Err(err) => println!("could not write {path}: {err}"),
+    }
+    Next::Continue
+}
+
+fn replay_command(shell: &mut Shell, path: &str) -> Next {
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => {
+            println!("could not read {path}: {err}");
+            return Next::Continue;
+        }
+    };
+    for input in split_inputs(&source) {
+        print!("{}{input}", shell.config.prompt);
+        match shell.input(&input) {
+            Next::Continue => {}
+            next => return next,
+        }
+    }
+    Next::Continue
+}
+
+fn reset_command(shell: &mut Shell, _: &str) -> Next {
+    shell.repl.reset();
+    shell.results.clear();
+    shell.transcript.clear();
+    println!("session reset, builtins only");
+    Next::Continue
+}
+
+fn env_command(shell: &mut Shell, _: &str) -> Next {
+    for binding in shell.repl.env() {
+        match binding.slot {
+            Some((scope, index)) => {
+                let scope = scope.to_string();
+                println!(
+                    "{scope:<7} {index:>3}  {} = {}",
+                    binding.name, binding.value
+                )
+            }
+            None => println!("{} = {}", binding.name, binding.value),
+        }
+    }
+    Next::Continue
+}
+
+fn ast_command(_: &mut Shell, source: &str) -> Next {
+    let mut parser = Parser::new(Lexer::from(source));
+    let program = parser.parse_program();
+    if !print_parser_errors(&parser) {
+        print!("{}", ast_dump(&program));
+    }
+    Next::Continue
+}
+
+fn tokens_command(_: &mut Shell, source: &str) -> Next {
+    for token in Lexer::from(source) {
+        println!(
+            "{}:{} {:?}",
+            token.span.line, token.span.column, token.token
+        );
+    }
+    Next::Continue
+}
+
+fn bytecode_command(shell: &mut Shell, source: &str) -> Next {
+    let mut parser = Parser::new(Lexer::from(source));
+    let program = parser.parse_program();
+    if print_parser_errors(&parser) {
+        return Next::Continue;
+    }
+    let mut compiler = shell.repl.compiler();
+    let known = compiler.bytecode().constants.len();
+    if let Err(err) = compiler.compile_program(program) {
+        println!("Compiler error: {err}");
+        return Next::Continue;
+    }
+    print!("{}", render_bytecode(&compiler.bytecode(), known));
+    Next::Continue
+}
+
+/// The argument of an `on|off` command, `None` when there is none.
+fn toggle(arg: &str) -> Result<Option<bool>, String> {
+    match arg {
+        "" => Ok(None),
+        "on" => Ok(Some(true)),
+        "off" => Ok(Some(false)),
+        arg => Err(format!("expected on or off, got {arg}")),
+    }
+}
+
+fn emit_command(shell: &mut Shell, arg: &str) -> Next {
+    match toggle(arg) {
+        Ok(Some(emit)) => match shell.repl.set_emit(emit) {
+            Ok(()) => shell.emit = emit,
+            Err(err) => println!("{err}"),
+        },
+        Ok(None) => println!("emit is {}", if shell.emit { "on" } else { "off" }),
+        Err(err) => println!("{err}"),
+    }
+    Next::Continue
+}
+
+fn time_command(shell: &mut Shell, arg: &str) -> Next {
+    match toggle(arg) {
+        Ok(Some(time)) => shell.time = time,
+        Ok(None) => println!("time is {}", if shell.time { "on" } else { "off" }),
+        Err(err) => println!("{err}"),
+    }
+    Next::Continue
+}
+
+fn width_command(shell: &mut Shell, arg: &str) -> Next {
+    if arg.is_empty() {
+        println!("{}", shell.width);
+        return Next::Continue;
+    }
+    match arg.parse() {
+        Ok(width) => shell.width = width,
+        Err(_) => println!("invalid width: {arg}"),
+    }
+    Next::Continue
+}
+
+fn save_session_command(shell: &mut Shell, path: &str) -> Next {
+    match shell.repl.save_session(path) {
+        Ok(()) => println!("session saved to {path}"),
+        Err(err) => println!("Session error: {err}"),
+    }
+    Next::Continue
+}
+
+fn load_session_command(shell: &mut Shell, path: &str) -> Next {
+    match shell.repl.load_session(path) {
+        Ok(()) => println!("session loaded from {path}"),
+        Err(err) => println!("Session error: {err}"),
+    }
+    Next::Continue
+}
+
+/// The REPL loop on top of an engine.
+struct Shell {
+    engine: Backend,
+    repl: Box<dyn Repl>,
+    /// The printed results, newest first, see `remember_result`.
+    results: Vec<Value>,
+    /// The inputs that ran without errors, what `:save` writes.
+    transcript: Vec<String>,
+    config: ReplConfig,
+    emit: bool,
+    time: bool,
+    /// Whether input and results are colored, see `color_enabled`.
+    color: bool,
+    /// Where results start breaking over lines, see `pretty`.
+    width: usize,
+}
+
+impl Shell {
+    fn new(config: ReplConfig) -> Self {
+        let engine = config.engine;
+        let mut shell = Shell {
+            engine,
+            repl: Box::new(EvalRepl::new()),
+            results: vec![],
+            transcript: vec![],
+            config,
+            emit: false,
+            time: false,
+            color: color_enabled(),
+            width: DEFAULT_WIDTH,
+        };
+        shell.repl = shell.new_repl(engine);
+        shell
+    }
+
+    fn new_repl(&self, engine: Backend) -> Box<dyn Repl> {
+        match engine {
+            Backend::Eval => Box::new(EvalRepl::new()),
+            Backend::Vm => Box::new(
+                VmRepl::new()
+                    .with_report(self.config.report)
+                    .with_profile(self.config.profile)
+                    .with_emit(self.emit),
+            ),
+        }
+    }
+
+    /// What tab completes to, the keywords, the builtins and the globals defined so far.
+    fn words(&self) -> Vec<String> {
+        let keywords = Keywords::default();
+        let keywords = keywords.words().map(String::from);
+        let builtins = BUILTINS.iter().map(|builtin| builtin.name.to_string());
+        let globals = self.repl.bindings().into_iter().map(|(name, _)| name);
+        keywords.chain(builtins).chain(globals).collect()
+    }
+
+    /// Runs the rc file into the session, its errors don't stop the REPL from starting.
+    /// Returns the code if it called `exit`.
+    fn run_rc_file(&mut self) -> Option<i64> {
+        let path = self.config.rc_file.clone()?;
+        let source = std::fs::read_to_string(&path).ok()?;
+        let path = path.display().to_string();
+        match self.repl.run(&source, Some(&path)) {
+            Outcome::Value(_) | Outcome::Nothing => None,
+            Outcome::Invalid(errors) | Outcome::Failed(errors) => {
+                self.print_errors(&errors);
+                None
+            }
+            Outcome::Exit(code) => Some(code),
+        }
+    }
+
+    /// Returns the code passed to `exit`.
+    fn run(&mut self) -> io::Result<i64> {
+        if let Some(code) = self.run_rc_file() {
+            return Ok(code);
+        }
+        let mut editor = LineEditor::new().with_highlight(self.color);
+        loop {
+            editor.set_words(self.words());
+            let Some(buffer) = read_input(&mut editor, &self.config)? else {
+                return Ok(0);
+            };
+            match self.input(&buffer) {
+                Next::Continue => {}
+                Next::Quit => return Ok(0),
+                Next::Exit(code) => return Ok(code),
+            }
+        }
+    }
+
+    /// Runs a command or code.
+    fn input(&mut self, buffer: &str) -> Next {
+        if let Some(input) = buffer.trim().strip_prefix(':') {
+            return self.command(input);
+        }
+        interrupt::clear();
+        match self.repl.run(buffer, None) {
+            Outcome::Value(value) => {
+                let text = pretty(&value, self.width);
+                match self.color {
+                    true => println!("{}", highlight_value(&value, &text)),
+                    false => println!("{text}"),
+                }
+                for (name, value) in remember_result(&mut self.results, value) {
+                    self.repl.define(name, value);
+                }
+                self.transcript.push(buffer.to_string());
+            }
+            Outcome::Nothing => self.transcript.push(buffer.to_string()),
+            Outcome::Invalid(errors) | Outcome::Failed(errors) => self.print_errors(&errors),
+            Outcome::Exit(code) => return Next::Exit(code),
+        }
+        if self.time {
+            println!("{}", self.repl.timings());
+        }
+        Next::Continue
+    }
+
+    fn print_errors(&self, errors: &str) {
+        match self.color {
+            true => println!("{}", paint(errors.trim_end(), Style::Error)),
+            false => print!("{errors}"),
+        }
+    }
+
+    fn command(&mut self, input: &str) -> Next {
+        let (name, args) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+        match COMMANDS.iter().find(|command| command.name == name) {
+            Some(command) => (command.run)(self, args.trim()),
+            None => {
+                let names = COMMANDS.iter().map(|command| command.name);
+                match suggest(name, names) {
+                    Some(suggestion) => {
+                        println!("unknown command :{name}, did you mean `:{suggestion}`?")
+                    }
+                    None => println!("unknown command :{name}, see :help"),
+                }
+                Next::Continue
+            }
+        }
+    }
+}
+
+/// The exit code of a script that doesn't parse or compile, `EX_DATAERR` in sysexits.h.
+pub const EXIT_INVALID: i64 = 65;
+/// The exit code of a script that fails while it runs, `EX_SOFTWARE` in sysexits.h.
+pub const EXIT_FAILED: i64 = 70;
+
+/// A fresh engine with the script globals defined.
+fn script_repl(config: &ScriptConfig) -> Box<dyn Repl> {
+    let mut repl = Shell::new(ReplConfig::new().engine(config.engine)).repl;
+    for (name, value) in config.globals() {
+        repl.define(name, value);
+    }
+    repl
+}
+
+/// `monkey run file.mk` runs a file without a REPL. Errors go to stderr, and the exit code is
+/// 65 when it doesn't parse or compile and 70 when it fails running, see `EXIT_INVALID`.
+pub fn run_file(path: &str, config: &ScriptConfig) -> i64 {
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("could not read {path}: {err}");
+            return 1;
+        }
+    };
+    let mut repl = script_repl(config);
+    match repl.run(&source, Some(path)) {
+        Outcome::Value(_) | Outcome::Nothing => 0,
+        Outcome::Invalid(errors) => {
+            eprint!("{errors}");
+            EXIT_INVALID
+        }
+        Outcome::Failed(errors) => {
+            eprint!("{errors}");
+            EXIT_FAILED
+        }
+        Outcome::Exit(code) => code,
+    }
+}
+
+/// How often `watch_file` looks at the file.
+const WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// `monkey watch file.mk` runs a file on a fresh engine every time it changes, until Ctrl-C.
+pub fn watch_file(path: &str, config: &ScriptConfig) -> ! {
+    let mut last = None;
+    loop {
+        let modified = std::fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok();
+        if last != Some(modified) {
+            last = Some(modified);
+            println!("--- {path}");
+            match std::fs::read_to_string(path) {
+                Ok(source) => {
+                    let mut repl = script_repl(config);
+                    match repl.run(&source, Some(path)) {
+                        Outcome::Value(value) => println!("{value}"),
+                        Outcome::Nothing => {}
+                        Outcome::Invalid(errors) | Outcome::Failed(errors) => eprint!("{errors}"),
+                        Outcome::Exit(code) => println!("exit({code})"),
+                    }
+                }
+                Err(err) => eprintln!("could not read {path}: {err}"),
+            }
+        }
+        std::thread::sleep(WATCH_INTERVAL);
+    }
+}
+
+/// `monkey bench file.mk` runs a file `iters` times on a fresh copy of each engine and prints
+/// how long the runs took.
+pub fn bench_file(engines: &[Backend], path: &str, iters: usize, config: &ScriptConfig) -> i64 {
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("could not read {path}: {err}");
+            return 1;
+        }
+    };
+    for &engine in engines {
+        let mut samples = Vec::with_capacity(iters);
+        for _ in 0..iters {
+            let mut repl = script_repl(&config.clone().engine(engine));
+            let start = std::time::Instant::now();
+            let outcome = repl.run(&source, Some(path));
+            samples.push(start.elapsed());
+            if let Outcome::Invalid(errors) | Outcome::Failed(errors) = outcome {
+                eprint!("{errors}");
+                return 1;
+            }
+        }
+        if let Some(stats) = BenchStats::new(&samples) {
+            println!("{:<4} {stats}", engine.to_string());
+        }
+    }
+    0
+}
+
+/// Runs the REPL, returns the code passed to `exit`. Ctrl-C stops the running input instead
+/// of the REPL.
+pub fn start(config: ReplConfig) -> io::Result<i64> {
+    if !config.quiet {
+        println!("Hello! This is the Monkey programming language!");
+        println!("Feel free to type in commands");
+    }
+    interrupt::install();
+    Shell::new(config).run()
+}
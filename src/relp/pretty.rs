@@ -0,0 +1,75 @@
+use crate::eval::value::{HashKey, Value};
+
+/// Where the REPL starts breaking results over lines, `:width` changes it.
+pub const DEFAULT_WIDTH: usize = 80;
+const INDENT: usize = 2;
+
+/// Lays `value` out on one line when it fits in `width`, otherwise arrays and hashes get one
+/// element per line. Hash keys come out sorted either way.
+pub fn pretty(value: &Value, width: usize) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value, 0, 0, width);
+    out
+}
+
+fn sorted(hash: &std::collections::HashMap<HashKey, Value>) -> Vec<(&HashKey, &Value)> {
+    let mut pairs = hash.iter().collect::<Vec<_>>();
+    pairs.sort_by_key(|(key, _)| *key);
+    pairs
+}
+
+fn flat(value: &Value) -> String {
+    match value {
+        Value::Array(values) => {
+            let values = values.iter().map(flat).collect::<Vec<_>>();
+            format!("[{}]", values.join(", "))
+        }
+        Value::Hash(hash) => {
+            let pairs = sorted(hash)
+                .into_iter()
+                .map(|(key, value)| format!("{key}: {}", flat(value)))
+                .collect::<Vec<_>>();
+            format!("{{{}}}", pairs.join(", "))
+        }
+        value => value.to_string(),
+    }
+}
+
+/// `column` is where `value` starts on its line, `indent` where the line itself starts.
+fn write_value(out: &mut String, value: &Value, indent: usize, column: usize, width: usize) {
+    let flat = flat(value);
+    let fits = column + flat.chars().count() <= width;
+    let (open, items, close) = match value {
+        Value::Array(values) if !fits && !values.is_empty() => {
+            let items = values.iter().map(|value| (None, value)).collect::<Vec<_>>();
+            ("[", items, "]")
+        }
+        Value::Hash(hash) if !fits && !hash.is_empty() => {
+            let items = sorted(hash)
+                .into_iter()
+                .map(|(key, value)| (Some(key.to_string()), value))
+                .collect();
+            ("{", items, "}")
+        }
+        _ => {
+            out.push_str(&flat);
+            return;
+        }
+    };
+
+    out.push_str(open);
+    out.push('\n');
+    let inner = indent + INDENT;
+    for (key, value) in items {
+        out.push_str(&" ".repeat(inner));
+        let mut column = inner;
+        if let Some(key) = key {
+            out.push_str(&format!("{key}: "));
+            column += key.chars().count() + 2;
+        }
+        write_value(out, value, inner, column, width);
+        out.push_str(",\n");
+    }
+    out.push_str(&" ".repeat(indent));
+    out.push_str(close);
+}
@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+use crate::eval::value::{HashKey, Value};
+
+use super::pretty::pretty;
+
+fn hash(pairs: Vec<(HashKey, Value)>) -> Value {
+    Value::from(pairs.into_iter().collect::<HashMap<_, _>>())
+}
+
+#[test]
+fn test_pretty() {
+    let numbers = Value::Array(vec![Value::Int(1), Value::Int(2), Value::Int(3)].into());
+    let person = hash(vec![
+        (HashKey::String("name".into()), Value::String("Ann".into())),
+        (HashKey::String("age".into()), Value::Int(30)),
+        (HashKey::Int(1), numbers.clone()),
+    ]);
+    let tests = [
+        (numbers.clone(), 80, "[1, 2, 3]"),
+        (numbers.clone(), 8, "[\n  1,\n  2,\n  3,\n]"),
+        (Value::Array(vec![].into()), 1, "[]"),
+        (
+            person.clone(),
+            80,
+            r#"{1: [1, 2, 3], "age": 30, "name": "Ann"}"#,
+        ),
+        (
+            person.clone(),
+            20,
+            "{\n  1: [1, 2, 3],\n  \"age\": 30,\n  \"name\": \"Ann\",\n}",
+        ),
+        (
+            Value::Array(vec![person, numbers].into()),
+            14,
+            "[\n  {\n    1: [\n      1,\n      2,\n      3,\n    ],\n    \"age\": 30,\n    \"name\": \"Ann\",\n  },\n  [1, 2, 3],\n]",
+        ),
+    ];
+
+    for (value, width, expected) in tests {
+        assert_eq!(pretty(&value, width), expected, "width {width}");
+    }
+}
@@ -0,0 +1,58 @@
+use super::config::ScriptConfig;
+use super::engine::{Backend, Outcome};
+use super::{is_incomplete, script_repl, split_inputs};
+
+#[test]
+fn test_is_incomplete() {
+    let tests = [
+        ("1 + 2\n", false),
+        ("let f = fn(x) {\n", true),
+        ("[1, [2]\n", true),
+        ("\"open\n", true),
+        ("}\n", false),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(is_incomplete(input), expected, "{input:?}");
+    }
+}
+
+#[test]
+fn test_split_inputs() {
+    let source = "let f = fn(x) {\n  x\n};\n\nf(1)\n:env\n[1,\n";
+    assert_eq!(
+        split_inputs(source),
+        ["let f = fn(x) {\n  x\n};\n", "f(1)\n", ":env\n", "[1,\n"]
+    );
+}
+
+#[test]
+fn test_script_args() {
+    let config = ScriptConfig::new().args(vec!["a".to_string(), "b c".to_string()]);
+    for engine in [Backend::Eval, Backend::Vm] {
+        let mut repl = script_repl(&config.clone().engine(engine));
+        match repl.run("let n = len(ARGS); ARGS[n - 1]", None) {
+            Outcome::Value(value) => assert_eq!(value.to_string(), "\"b c\"", "{engine}"),
+            _ => panic!("ARGS is not defined on {engine}"),
+        }
+    }
+}
+
+#[test]
+fn test_script_env() {
+    std::env::set_var("MONKEY_TEST_ENV", "banana");
+    for engine in [Backend::Eval, Backend::Vm] {
+        let config = ScriptConfig::new().engine(engine);
+        let mut repl = script_repl(&config);
+        match repl.run("ENV[\"MONKEY_TEST_ENV\"]", None) {
+            Outcome::Value(value) => assert_eq!(value.to_string(), "\"banana\"", "{engine}"),
+            _ => panic!("ENV is not defined on {engine}"),
+        }
+
+        let mut repl = script_repl(&config.sandbox(true));
+        match repl.run("ENV", None) {
+            Outcome::Value(value) => assert_eq!(value.to_string(), "{}", "{engine}"),
+            _ => panic!("ENV is not defined on {engine}"),
+        }
+    }
+}
@@ -16,7 +16,7 @@ use crate::parser::Parser;
 pub fn bench_inter(b: &mut Bencher) {
     b.iter(|| {
         let input = String::from( "let fibonacci = fn(x) { if (x == 0) { 0 } else { if (x == 1) { return 1; } else { fibonacci(x - 1) + fibonacci(x - 2); } } }; fibonacci(35);");
-        let lexer = Lexer::new(input.chars().collect());
+        let lexer = Lexer::from(input);
         let mut parser = Parser::new(lexer);
         let program = parser.parse_program();
         let env = Environment::new();
@@ -30,7 +30,24 @@ pub fn bench_inter(b: &mut Bencher) {
 pub fn bench_comp(b: &mut Bencher) {
     b.iter(|| {
         let input = String::from( "let fibonacci = fn(x) { if (x == 0) { 0 } else { if (x == 1) { return 1; } else { fibonacci(x - 1) + fibonacci(x - 2); } } }; fibonacci(35);");
-        let lexer = Lexer::new(input.chars().collect());
+        let lexer = Lexer::from(input);
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program();
+        let mut compiler = Compiler::new();
+        let _ = compiler.compile_program(program);
+
+        let mut machine = Vm::new(compiler.bytecode());
+        let _ = machine.run();
+        println!("result: {:?}", machine.last_popped_element);
+    });
+}
+
+#[bench]
+pub fn bench_comp_arrays(b: &mut Bencher) {
+    b.iter(|| {
+        let input = String::from("let g = fn(n, acc) { if (n == 0) { acc } else { g(n - 1, acc + len([n, n, n, n]) + first(rest([n, n]))) } }; let go = fn(k, acc) { if (k == 0) { acc } else { go(k - 1, acc + g(150, 0)) } }; go(100, 0);");
+        let lexer = Lexer::from(input);
         let mut parser = Parser::new(lexer);
 
         let program = parser.parse_program();
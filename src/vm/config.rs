@@ -0,0 +1,43 @@
+use crate::compiler::MAX_GLOBALS;
+
+pub const STACK_SIZE: usize = 2048_usize;
+pub const MAX_FRAMES: usize = 1024_usize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VmConfig {
+    pub stack_size: usize,
+    pub max_frames: usize,
+    /// Can only lower the limit, `OpGetGlobal` and `OpSetGlobal` address at most `MAX_GLOBALS`.
+    pub max_globals: usize,
+}
+
+impl Default for VmConfig {
+    fn default() -> Self {
+        VmConfig {
+            stack_size: STACK_SIZE,
+            max_frames: MAX_FRAMES,
+            max_globals: MAX_GLOBALS,
+        }
+    }
+}
+
+impl VmConfig {
+    pub fn new() -> Self {
+        VmConfig::default()
+    }
+
+    pub fn stack_size(self, stack_size: usize) -> Self {
+        VmConfig { stack_size, ..self }
+    }
+
+    pub fn max_frames(self, max_frames: usize) -> Self {
+        VmConfig { max_frames, ..self }
+    }
+
+    pub fn max_globals(self, max_globals: usize) -> Self {
+        VmConfig {
+            max_globals: max_globals.min(MAX_GLOBALS),
+            ..self
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use crate::code::Instructions;
 use crate::eval::value::Value;
 
@@ -17,10 +19,10 @@ impl Frame {
         }
     }
 
-    pub fn instructions(&mut self) -> Instructions {
+    pub fn instructions(&self) -> Arc<Instructions> {
         if let Value::Closure { fun, .. } = &self.cl {
-            if let Value::CompiledFunction { instructions, .. } = *fun.clone() {
-                instructions
+            if let Value::CompiledFunction { instructions, .. } = fun.as_ref() {
+                Arc::clone(instructions)
             } else {
                 panic!("this shoudl be a CompiledFunction")
             }
@@ -1,24 +1,78 @@
-use crate::code::OpCode;
-use crate::compiler::ByteCode;
-use crate::eval::value::{Builtin, BuiltinFuncion, Value};
+use crate::code::{read_operands, read_u16, read_u8, OpCode};
+use crate::compiler::source_map::SourceMap;
+use crate::compiler::symbol_table::SymbolTable;
+use crate::compiler::{ByteCode, Compiler};
+use crate::eval::builtin::io::{Io, StdIo};
+use crate::eval::builtin::memo::Memo;
+use crate::eval::builtin::random::Rng;
+use crate::eval::builtin::{Builtin, Context, BUILTINS};
+use crate::eval::value::{HashKey, Value};
+use crate::lexer::Lexer;
+use crate::parser::Parser;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
 
+use self::config::{VmConfig, MAX_FRAMES};
 use self::frame::Frame;
+use self::pool::ArrayPool;
+use self::profile::{ProfileReport, Profiler};
+use self::snapshot::{StackSnapshot, VmSnapshot};
+use self::trace::{TraceEvent, TraceHook};
 
+pub mod config;
 mod frame;
+mod pool;
+pub mod profile;
+pub mod snapshot;
 #[cfg(test)]
 mod tests;
+pub mod trace;
 
+#[derive(Debug)]
 pub struct VmError {
     pub msg: String,
+    offset: Option<usize>,
+    position: Option<(usize, usize)>,
 }
 
 impl VmError {
     pub fn new(msg: impl Into<String>) -> Self {
-        VmError { msg: msg.into() }
+        VmError {
+            msg: msg.into(),
+            offset: None,
+            position: None,
+        }
+    }
+
+    fn at(self, offset: Option<usize>) -> Self {
+        VmError {
+            offset: self.offset.or(offset),
+            ..self
+        }
+    }
+
+    /// Where in the main instructions the run failed, an error inside a call points at the call.
+    pub fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+
+    /// Looks up the line and column of the failed statement in the program's source map.
+    pub fn locate(self, source_map: &SourceMap) -> Self {
+        let entry = self.offset.and_then(|offset| source_map.lookup(offset));
+        VmError {
+            position: entry.map(|entry| (entry.line, entry.column)),
+            ..self
+        }
+    }
+
+    /// The line and column `locate` found.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        self.position
     }
 }
 
@@ -28,8 +82,21 @@ impl Display for VmError {
     }
 }
 
-const STACK_SIZE: usize = 2048_usize;
-const MAX_FRAMES: usize = 1024_usize;
+impl std::error::Error for VmError {}
+
+#[derive(Debug)]
+pub enum StepResult {
+    Continue,
+    Halted,
+    Exit(i64),
+    Error(VmError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Completion {
+    Finished,
+    Exit(i64),
+}
 
 #[derive(Debug)]
 pub struct Vm {
@@ -39,6 +106,62 @@ pub struct Vm {
     pub last_popped_element: Option<Value>,
     globals: Rc<RefCell<Vec<Value>>>,
     frames: Vec<Frame>,
+    /// The instruction the main function is at, for the offset of errors.
+    main_ip: Option<usize>,
+    pool: ArrayPool,
+    fuel: Option<u64>,
+    interrupt: Option<&'static AtomicBool>,
+    config: VmConfig,
+    trace: Option<TraceHook>,
+    profiler: Option<Profiler>,
+    rng: Rng,
+    io: Box<dyn Io>,
+    exit: Option<i64>,
+    symbol_table: Option<Arc<RwLock<SymbolTable>>>,
+}
+
+impl Context for Vm {
+    /// Runs `function` to completion on top of the current frames, so builtins can call closures.
+    fn apply(&mut self, function: Value, args: Vec<Value>) -> Result<Value, String> {
+        let depth = self.frames.len();
+        let num_args = args.len();
+        self.push(function.clone()).map_err(|err| err.msg)?;
+        for arg in args {
+            self.push(arg).map_err(|err| err.msg)?;
+        }
+        match function {
+            Value::Closure { fun, free } => {
+                self.call_closure(num_args, *fun, free)
+                    .and_then(|_| self.execute(None, depth))
+                    .map_err(|err| err.msg)?;
+            }
+            Value::Builtin(builtin) => {
+                self.call_builtin(num_args, builtin)
+                    .map_err(|err| err.msg)?;
+            }
+            Value::Memoized(memo) => {
+                self.call_memoized(num_args, &memo).map_err(|err| err.msg)?;
+            }
+            value => return Err(format!("calling non-function and non-built-in: {value}")),
+        }
+        self.pop().map_err(|err| err.msg)
+    }
+
+    fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+
+    fn io(&mut self) -> &mut dyn Io {
+        self.io.as_mut()
+    }
+
+    fn exit(&mut self, code: i64) {
+        self.exit = Some(code);
+    }
+
+    fn eval(&mut self, source: &str) -> Result<Value, String> {
+        self.eval_source(source).map_err(|err| err.msg)
+    }
 }
 
 impl Vm {
@@ -48,8 +171,15 @@ impl Vm {
             .ok_or(VmError::new("the frames are empty"))
     }
 
-    fn push_frame(&mut self, frame: Frame) {
+    fn push_frame(&mut self, frame: Frame) -> Result<(), VmError> {
+        if self.frames.len() >= self.config.max_frames {
+            return Err(VmError::new(format!(
+                "maximum call depth exceeded: {}",
+                self.config.max_frames
+            )));
+        }
         self.frames.push(frame);
+        Ok(())
     }
 
     fn pop_frame(&mut self) -> Option<Frame> {
@@ -57,6 +187,10 @@ impl Vm {
     }
 
     pub fn new(byte_code: ByteCode) -> Self {
+        Vm::with_config(byte_code, VmConfig::default())
+    }
+
+    pub fn with_config(byte_code: ByteCode, config: VmConfig) -> Self {
         let main_fn = Value::CompiledFunction {
             instructions: byte_code.instructions,
             num_locals: 0,
@@ -66,15 +200,100 @@ impl Vm {
             fun: Box::new(main_fn),
             free: vec![],
         };
-        let mut frames: Vec<Frame> = Vec::with_capacity(MAX_FRAMES);
+        let mut frames: Vec<Frame> = Vec::with_capacity(config.max_frames.min(MAX_FRAMES));
         frames.push(Frame::new(main_closure, 0));
         Vm {
-            constans: byte_code.constants.clone(),
+            constans: byte_code.constants.iter().map(Value::from).collect(),
             frames,
-            stack: Vec::with_capacity(STACK_SIZE),
+            main_ip: None,
+            pool: ArrayPool::default(),
+            stack: vec![Value::Null; config.stack_size],
             last_popped_element: None,
             sp: 0,
-            globals: Rc::new(RefCell::new(Vec::with_capacity(65536))),
+            globals: Rc::new(RefCell::new(Vec::with_capacity(config.max_globals))),
+            fuel: None,
+            interrupt: None,
+            config,
+            trace: None,
+            profiler: None,
+            rng: Rng::default(),
+            io: Box::new(StdIo::new()),
+            exit: None,
+            symbol_table: byte_code.symbol_table,
+        }
+    }
+
+    pub fn with_fuel(self, fuel: u64) -> Self {
+        Vm {
+            fuel: Some(fuel),
+            ..self
+        }
+    }
+
+    /// Stops the run with an error once `interrupt` is set, like from a Ctrl-C handler.
+    pub fn with_interrupt(self, interrupt: &'static AtomicBool) -> Self {
+        Vm {
+            interrupt: Some(interrupt),
+            ..self
+        }
+    }
+
+    pub fn with_trace(self, trace: TraceHook) -> Self {
+        Vm {
+            trace: Some(trace),
+            ..self
+        }
+    }
+
+    pub fn with_io(self, io: Box<dyn Io>) -> Self {
+        Vm { io, ..self }
+    }
+
+    pub fn with_profiling(self) -> Self {
+        Vm {
+            profiler: Some(Profiler::default()),
+            ..self
+        }
+    }
+
+    pub fn profile_report(&self) -> Option<ProfileReport> {
+        let main = self.frames.first()?.instructions();
+        self.profiler
+            .as_ref()
+            .map(|profiler| profiler.report(&main, &self.constans))
+    }
+
+    pub fn snapshot(&self) -> VmSnapshot {
+        VmSnapshot {
+            globals: self.globals.borrow().clone(),
+            constants: self.constans.clone(),
+            stack: None,
+        }
+    }
+
+    pub fn snapshot_with_stack(&self) -> VmSnapshot {
+        VmSnapshot {
+            stack: Some(StackSnapshot {
+                stack: self.stack[..self.sp].to_vec(),
+                frames: self.frames.clone(),
+                last_popped_element: self.last_popped_element.clone(),
+            }),
+            ..self.snapshot()
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: VmSnapshot) {
+        *self.globals.borrow_mut() = snapshot.globals;
+        self.constans = snapshot.constants;
+        if let Some(saved) = snapshot.stack {
+            let sp = saved.stack.len();
+            self.stack[..sp].clone_from_slice(&saved.stack);
+            for value in &mut self.stack[sp..self.sp.max(sp)] {
+                *value = Value::Null;
+            }
+            self.sp = sp;
+            self.frames = saved.frames;
+            self.last_popped_element = saved.last_popped_element;
         }
     }
 
@@ -84,28 +303,123 @@ impl Vm {
         vm
     }
 
-    pub fn run(&mut self) -> Result<(), VmError> {
-        while self.current_frame()?.ip < self.current_frame()?.instructions().len() {
-            let ip = self.current_frame()?.ip;
-            let instructions = self.current_frame()?.instructions().clone();
+    /// Calls a closure or builtin from the host on top of the current frames, `Value::Exit` when
+    /// it calls `exit`.
+    pub fn call(&mut self, function: Value, args: Vec<Value>) -> Result<Value, VmError> {
+        match self.apply(function, args) {
+            Ok(value) => Ok(value),
+            Err(msg) => self.exit.take().map(Value::Exit).ok_or(VmError::new(msg)),
+        }
+    }
+
+    pub fn run(&mut self) -> Result<Completion, VmError> {
+        let result = self.execute(None, 0);
+        self.pool.clear();
+        match result {
+            Ok(_) => Ok(Completion::Finished),
+            Err(err) => self.exit.take().map(Completion::Exit).ok_or(err),
+        }
+    }
+
+    pub fn step(&mut self) -> StepResult {
+        self.run_steps(1)
+    }
+
+    pub fn run_steps(&mut self, steps: usize) -> StepResult {
+        match self.execute(Some(steps), 0) {
+            Ok(true) => StepResult::Halted,
+            Ok(false) => StepResult::Continue,
+            Err(err) => match self.exit.take() {
+                Some(code) => StepResult::Exit(code),
+                None => StepResult::Error(err),
+            },
+        }
+    }
+
+    /// Returns whether the program ran to completion, stops early once `steps` instructions ran.
+    /// A return that brings the frames back down to `depth` also ends the run.
+    fn execute(&mut self, steps: Option<usize>, depth: usize) -> Result<bool, VmError> {
+        let result = self.execute_instructions(steps, depth);
+        result.map_err(|err| err.at(self.main_ip))
+    }
+
+    fn execute_instructions(
+        &mut self,
+        mut steps: Option<usize>,
+        depth: usize,
+    ) -> Result<bool, VmError> {
+        let mut instructions = self.current_frame()?.instructions();
+        let mut ip = self.current_frame()?.ip;
+
+        while ip < instructions.len() {
+            if let Some(steps) = &mut steps {
+                if *steps == 0 {
+                    self.current_frame()?.ip = ip;
+                    return Ok(false);
+                }
+                *steps -= 1;
+            }
+            if let Some(fuel) = &mut self.fuel {
+                if *fuel == 0 {
+                    self.current_frame()?.ip = ip;
+                    return Err(VmError::new("fuel exhausted"));
+                }
+                *fuel -= 1;
+            }
+            if self
+                .interrupt
+                .is_some_and(|interrupt| interrupt.load(Ordering::Relaxed))
+            {
+                self.current_frame()?.ip = ip;
+                return Err(VmError::new("interrupted"));
+            }
+            if self.frames.len() == 1 {
+                self.main_ip = Some(ip);
+            }
             let op = match OpCode::try_from(instructions[ip]) {
                 Ok(op) => op,
                 Err(_) => return Err(VmError::new("the u8 isnt a valid OpCode")),
             };
-            println!("op: {op:?}");
+            if let Some(trace) = self.trace {
+                let (operands, _) = read_operands(&op.into(), &instructions[ip + 1..]);
+                trace(&TraceEvent {
+                    ip,
+                    op,
+                    operands,
+                    top: self.sp.checked_sub(1).map(|top| &self.stack[top]),
+                });
+            }
+            let started = self
+                .profiler
+                .as_ref()
+                .map(|_| (Instant::now(), Arc::as_ptr(&instructions)));
+            ip += 1;
 
             match op {
                 OpCode::OpConstant => {
-                    let const_idx =
-                        u16::from_be_bytes(instructions[ip + 1..ip + 3].try_into().unwrap());
-                    self.current_frame()?.ip += 2;
+                    let const_idx = read_u16(&instructions[ip..]);
+                    ip += 2;
                     self.push(self.constans[const_idx as usize].clone())?;
                 }
-                OpCode::OpAdd | OpCode::OpSub | OpCode::OpMul | OpCode::OpDiv => {
+                OpCode::OpPushInt => {
+                    let int = read_u16(&instructions[ip..]) as i16;
+                    ip += 2;
+                    self.push(Value::Int(int as i64))?;
+                }
+                OpCode::OpConst0 => {
+                    self.push(Value::Int(0))?;
+                }
+                OpCode::OpConst1 => {
+                    self.push(Value::Int(1))?;
+                }
+                OpCode::OpAdd | OpCode::OpSub | OpCode::OpMul | OpCode::OpDiv | OpCode::OpMod => {
                     self.execute_binary_operation(op)?;
                 }
                 OpCode::OpPop => {
-                    self.pop()?;
+                    let value = self.pop()?;
+                    if let Some(last) = self.last_popped_element.replace(value) {
+                        self.pool.recycle(last);
+                    }
                 }
                 OpCode::OpTrue => {
                     self.push(true)?;
@@ -121,167 +435,219 @@ impl Vm {
                 }
                 OpCode::OpMinus => self.execute_minus_operator()?,
                 OpCode::OpJump => {
-                    let position =
-                        u16::from_be_bytes(instructions[ip + 1..ip + 3].try_into().unwrap());
-                    self.current_frame()?.ip = (position - 1) as usize;
+                    ip = read_u16(&instructions[ip..]) as usize;
                 }
                 OpCode::OpJumpNotTruthy => {
-                    let position =
-                        u16::from_be_bytes(instructions[ip + 1..ip + 3].try_into().unwrap());
-
-                    self.current_frame()?.ip += 2;
+                    let position = read_u16(&instructions[ip..]) as usize;
+                    ip += 2;
                     let condition = self.pop()?;
                     if !self.is_truthy(condition) {
-                        self.current_frame()?.ip = (position - 1) as usize;
+                        ip = position;
                     }
                 }
                 OpCode::OpNull => {
                     self.push(Value::Null)?;
                 }
                 OpCode::OpSetGlobal => {
-                    let global_idx =
-                        u16::from_be_bytes(instructions[ip + 1..ip + 3].try_into().unwrap());
-                    self.current_frame()?.ip += 2;
+                    let global_idx = read_u16(&instructions[ip..]) as usize;
+                    ip += 2;
+                    if global_idx >= self.config.max_globals {
+                        return Err(VmError::new(format!(
+                            "too many globals: the limit is {}",
+                            self.config.max_globals
+                        )));
+                    }
                     let value = self.pop()?;
-                    if self.globals.borrow().len() == global_idx as usize {
-                        self.globals.borrow_mut().push(value);
-                    } else {
-                        self.globals.borrow_mut()[global_idx as usize] = value;
+                    let mut globals = self.globals.borrow_mut();
+                    if globals.len() <= global_idx {
+                        globals.resize(global_idx + 1, Value::Null);
                     }
+                    globals[global_idx] = value;
                 }
                 OpCode::OpGetGlobal => {
-                    let global_idx =
-                        u16::from_be_bytes(instructions[ip + 1..ip + 3].try_into().unwrap());
-                    self.current_frame()?.ip += 2;
-                    let value = self.globals.borrow_mut()[global_idx as usize].clone();
+                    let global_idx = read_u16(&instructions[ip..]) as usize;
+                    ip += 2;
+                    let value = self
+                        .globals
+                        .borrow()
+                        .get(global_idx)
+                        .cloned()
+                        .unwrap_or(Value::Null);
                     self.push(value)?;
                 }
                 OpCode::OpArray => {
-                    let len = u16::from_be_bytes(instructions[ip + 1..ip + 3].try_into().unwrap());
-                    self.current_frame()?.ip += 2;
-                    let start = self.sp - len as usize;
-                    let array = self.build_array(start, self.sp);
-                    for _ in start..self.sp {
-                        self.pop()?;
-                    }
+                    let len = read_u16(&instructions[ip..]) as usize;
+                    ip += 2;
+
+                    let array = self.build_array(self.stack_start(len)?);
                     self.push(array)?;
                 }
                 OpCode::OpHash => {
-                    let len = u16::from_be_bytes(instructions[ip + 1..ip + 3].try_into().unwrap());
-
-                    self.current_frame()?.ip += 2;
-
-                    let hash = self.build_hash(self.sp - len as usize, self.sp);
-
-                    for _ in self.sp - len as usize..self.sp {
-                        self.pop()?;
-                    }
+                    let len = read_u16(&instructions[ip..]) as usize;
+                    ip += 2;
 
+                    let hash = self.build_hash(self.stack_start(len)?)?;
                     self.push(hash)?;
                 }
                 OpCode::OpIndex => {
                     let idx = self.pop()?;
                     let lhs = self.pop()?;
 
-                    let value = self.execute_index_expression(idx, lhs);
+                    let value = self.execute_index_expression(idx, lhs)?;
                     self.push(value)?;
                 }
                 OpCode::OpCall => {
-                    let num_args =
-                        u8::from_be_bytes(instructions[ip + 1..ip + 2].try_into().unwrap())
-                            as usize;
-                    self.current_frame()?.ip += 1;
-                    let call = &self.stack[self.sp - 1 - num_args];
+                    let num_args = read_u8(&instructions[ip..]) as usize;
+                    ip += 1;
+                    let call = &self.stack[self.stack_start(num_args + 1)?];
 
                     match call {
                         Value::Closure { fun, free } => {
-                            self.call_closure(num_args, *fun.clone(), free.to_vec())?;
-                            continue;
+                            let (fun, free) = (*fun.clone(), free.to_vec());
+                            self.current_frame()?.ip = ip;
+                            self.call_closure(num_args, fun, free)?;
+                            instructions = self.current_frame()?.instructions();
+                            ip = 0;
                         }
-                        Value::Builtin(builtin_fn) => self.call_builtin(num_args, *builtin_fn),
-                        value => Err(VmError::new(format!(
-                            "calling non-function and non-built-in: {value}"
-                        ))),
-                    }?
-                }
-                OpCode::OpReturn => {
-                    if let Some(frame) = self.pop_frame() {
-                        for _ in 0..self.sp - frame.base_pointer {
-                            self.pop()?;
+                        Value::Builtin(builtin) => self.call_builtin(num_args, builtin)?,
+                        Value::Memoized(memo) => self.call_memoized(num_args, &memo.clone())?,
+                        value => {
+                            return Err(VmError::new(format!(
+                                "calling non-function and non-built-in: {value}"
+                            )))
                         }
-                    };
-                    self.push(Value::Null)?;
+                    }
                 }
-                OpCode::OpReturnValue => {
-                    let return_value = self.pop()?;
+                OpCode::OpReturn | OpCode::OpReturnValue => {
+                    let return_value = match op {
+                        OpCode::OpReturnValue => self.pop()?,
+                        _ => Value::Null,
+                    };
+                    // A return outside any function ends the program with its value.
+                    if self.frames.len() == 1 {
+                        self.sp = 0;
+                        self.last_popped_element = Some(return_value);
+                        self.current_frame()?.ip = instructions.len();
+                        return Ok(true);
+                    }
                     if let Some(frame) = self.pop_frame() {
-                        for _ in 0..self.sp - frame.base_pointer {
-                            self.pop()?;
-                        }
+                        self.sp = frame.base_pointer - 1;
                     }
-                    self.pop()?;
                     self.push(return_value)?;
+                    if self.frames.len() == depth {
+                        return Ok(true);
+                    }
+                    instructions = self.current_frame()?.instructions();
+                    ip = self.current_frame()?.ip;
                 }
                 OpCode::OpSetLocal => {
-                    let local_idx =
-                        u8::from_be_bytes(instructions[ip + 1..ip + 2].try_into().unwrap());
-                    self.current_frame()?.ip += 1;
+                    let local_idx = read_u8(&instructions[ip..]) as usize;
+                    ip += 1;
                     let base_pointer = self.current_frame()?.base_pointer;
                     let value = self.pop()?;
-                    self.stack[base_pointer + local_idx as usize] = value;
+                    self.stack[base_pointer + local_idx] = value;
                 }
                 OpCode::OpGetLocal => {
-                    let local_idx =
-                        u8::from_be_bytes(instructions[ip + 1..ip + 2].try_into().unwrap());
-                    self.current_frame()?.ip += 1;
+                    let local_idx = read_u8(&instructions[ip..]) as usize;
+                    ip += 1;
                     let base_pointer = self.current_frame()?.base_pointer;
-                    if let Some(value) = self.stack.get(base_pointer + local_idx as usize).cloned()
-                    {
-                        self.push(value)?;
-                    }
+                    let value = self.stack[base_pointer + local_idx].clone();
+                    self.push(value)?;
                 }
                 OpCode::OpGetBuiltin => {
-                    let builtin_idx =
-                        u8::from_be_bytes(instructions[ip + 1..ip + 2].try_into().unwrap());
-                    self.current_frame()?.ip += 1;
-                    let builtin =
-                        Builtin::try_from(builtin_idx).expect("should get the builtin fn");
-                    let builtin_fn = Builtin::get_builtin_fn(builtin);
-                    self.push(Value::Builtin(builtin_fn))?;
+                    let builtin_idx = read_u8(&instructions[ip..]);
+                    ip += 1;
+                    let builtin = BUILTINS
+                        .get(builtin_idx as usize)
+                        .ok_or(VmError::new(format!("undefined builtin: {builtin_idx}")))?;
+                    self.push(Value::Builtin(builtin))?;
                 }
                 OpCode::OpClosure => {
-                    let const_idx =
-                        u16::from_be_bytes(instructions[ip + 1..ip + 3].try_into().unwrap());
-                    let num_free =
-                        u8::from_be_bytes(instructions[ip + 3..ip + 4].try_into().unwrap());
-                    self.current_frame()?.ip += 3;
-                    self.push_closure(const_idx as usize, num_free as usize)?;
+                    let const_idx = read_u16(&instructions[ip..]) as usize;
+                    let num_free = read_u8(&instructions[ip + 2..]) as usize;
+                    ip += 3;
+                    self.push_closure(const_idx, num_free)?;
                 }
                 OpCode::OpGetFree => {
-                    let free_idx =
-                        u8::from_be_bytes(instructions[ip + 1..ip + 2].try_into().unwrap());
-                    self.current_frame()?.ip += 1;
-                    let current_closure = self.current_frame()?.cl.clone();
-
-                    if let Value::Closure { free, .. } = current_closure {
-                        self.push(free[free_idx as usize].clone())?;
-                    }
+                    let free_idx = read_u8(&instructions[ip..]) as usize;
+                    ip += 1;
+                    let value = match &self.current_frame()?.cl {
+                        Value::Closure { free, .. } => {
+                            free.get(free_idx).cloned().ok_or_else(|| {
+                                VmError::new(format!("missing free variable {free_idx}"))
+                            })?
+                        }
+                        _ => return Err(VmError::new("the current frame isn't a closure")),
+                    };
+                    self.push(value)?;
                 }
                 OpCode::OpCurrentClosure => {
                     let current_closure = self.current_frame()?.cl.clone();
                     self.push(current_closure)?;
                 }
             };
-            self.current_frame()?.ip += 1;
+            if let (Some(profiler), Some((started, function))) = (&mut self.profiler, started) {
+                profiler.record(op, function, started.elapsed());
+            }
         }
+        self.current_frame()?.ip = ip;
 
-        Ok(())
+        Ok(true)
+    }
+
+    /// Compiles `source` into the global symbol table and runs it in a frame above the current one.
+    fn eval_source(&mut self, source: &str) -> Result<Value, VmError> {
+        let symbol_table = self
+            .symbol_table
+            .clone()
+            .ok_or(VmError::new("eval needs the symbol table of the program"))?;
+        let mut parser = Parser::new(Lexer::from(source));
+        let program = parser.parse_program();
+        if let Some(err) = parser.errors().first() {
+            return Err(VmError::new(format!("parser error: {err}")));
+        }
+
+        let saved_symbol_table = symbol_table.read().unwrap().clone();
+        let constants = Rc::new(RefCell::new(self.constans.clone()));
+        let mut compiler = Compiler::new_with_state(Arc::clone(&symbol_table), constants.clone());
+        if let Err(err) = compiler.compile_program(program) {
+            *symbol_table.write().unwrap() = saved_symbol_table;
+            return Err(VmError::new(err.to_string()));
+        }
+        self.constans = constants.take();
+
+        let main = Value::Closure {
+            fun: Box::new(Value::CompiledFunction {
+                instructions: Arc::new(compiler.current_instructions()),
+                num_locals: 0,
+                num_parameters: 0,
+            }),
+            free: vec![],
+        };
+        // The closure sits below the frame like any other call, so a top level `return` pops it.
+        let depth = self.frames.len();
+        self.push(main.clone())?;
+        let base_pointer = self.sp;
+        self.push_frame(Frame::new(main, base_pointer))?;
+
+        let previous = self.last_popped_element.take();
+        let result = self.execute(None, depth);
+        let last_popped = std::mem::replace(&mut self.last_popped_element, previous);
+        if result.is_ok() && self.frames.len() == depth {
+            return self.pop();
+        }
+        self.frames.truncate(depth);
+        for value in &mut self.stack[base_pointer - 1..self.sp.max(base_pointer - 1)] {
+            *value = Value::Null;
+        }
+        self.sp = base_pointer - 1;
+        result.map(|_| last_popped.unwrap_or(Value::Null))
     }
 
     fn push_closure(&mut self, const_idx: usize, num_free: usize) -> Result<(), VmError> {
         let constant = self.constans[const_idx].clone();
         if let Value::CompiledFunction { .. } = &constant {
-            let free = (self.sp - num_free..self.sp)
+            let free = (self.stack_start(num_free)?..self.sp)
                 .map(|idx| self.stack[idx].clone())
                 .collect::<Vec<_>>();
             for _ in 0..num_free {
@@ -299,28 +665,6 @@ impl Vm {
         }
     }
 
-    // fn call_funtion(
-    //     &mut self,
-    //     num_args: usize,
-    //     function: Instructions,
-    //     num_locals: usize,
-    //     num_parameters: usize,
-    // ) -> Result<(), VmError> {
-    //     if num_parameters != num_args {
-    //         return Err(VmError::new(format!(
-    //             "wrong number of arguments: want={}, got={}",
-    //             num_parameters, num_args
-    //         )));
-    //     }
-    //     let frame = Frame::new(function.clone(), self.sp - num_args);
-    //     self.push_frame(frame);
-    //     for _ in 0..num_locals {
-    //         self.stack.push(Value::Null);
-    //     }
-    //     self.sp += num_locals;
-    //     Ok(())
-    // }
-
     fn call_closure(
         &mut self,
         num_args: usize,
@@ -347,9 +691,9 @@ impl Vm {
                 self.sp - num_args,
             );
 
-            self.push_frame(frame);
-            for _ in 0..*num_locals {
-                self.stack.push(Value::Null);
+            self.push_frame(frame)?;
+            if self.sp + num_locals >= self.config.stack_size {
+                return Err(VmError::new("Stack Overflow"));
             }
             self.sp += num_locals;
 
@@ -361,10 +705,22 @@ impl Vm {
         }
     }
 
-    fn call_builtin(&mut self, num_args: usize, builtin_fn: BuiltinFuncion) -> Result<(), VmError> {
-        let args = &self.stack[self.sp - num_args..self.sp];
+    fn call_builtin(&mut self, num_args: usize, builtin: &Builtin) -> Result<(), VmError> {
+        let args = self.stack[self.sp - num_args..self.sp].to_vec();
 
-        let result = builtin_fn(args.to_vec()).map_err(VmError::new)?;
+        let result = builtin.call(args, self).map_err(VmError::new)?;
+        for _ in 0..num_args + 1 {
+            let value = self.pop()?;
+            self.pool.recycle(value);
+        }
+        self.push(result)?;
+        Ok(())
+    }
+
+    fn call_memoized(&mut self, num_args: usize, memo: &Memo) -> Result<(), VmError> {
+        let args = self.stack[self.sp - num_args..self.sp].to_vec();
+
+        let result = memo.call(args, self).map_err(VmError::new)?;
         for _ in 0..num_args + 1 {
             self.pop()?;
         }
@@ -372,8 +728,8 @@ impl Vm {
         Ok(())
     }
 
-    fn execute_index_expression(&mut self, idx: Value, lhs: Value) -> Value {
-        match lhs {
+    fn execute_index_expression(&mut self, idx: Value, lhs: Value) -> Result<Value, VmError> {
+        Ok(match lhs {
             Value::Array(arr) => {
                 if let Value::Int(idx) = idx {
                     arr.get(idx as usize).unwrap_or(&Value::Null).clone()
@@ -381,32 +737,43 @@ impl Vm {
                     Value::Null
                 }
             }
-            Value::Hash(hash) => hash.get(&idx).unwrap_or(&Value::Null).clone(),
+            Value::Hash(hash) => {
+                let key = HashKey::try_from(idx).map_err(VmError::new)?;
+                hash.get(&key).unwrap_or(&Value::Null).clone()
+            }
+            Value::String(string) => match idx {
+                Value::Int(idx) => usize::try_from(idx)
+                    .ok()
+                    .and_then(|idx| string.chars().nth(idx))
+                    .map_or(Value::Null, |char| Value::String(char.to_string().into())),
+                _ => Value::Null,
+            },
             _ => Value::Null,
-        }
+        })
     }
 
-    #[allow(clippy::mutable_key_type)]
-    fn build_hash(&mut self, start_idx: usize, end_idx: usize) -> Value {
-        let mut hash = HashMap::new();
-        let mut idx = start_idx;
-        while idx < end_idx {
-            let key = self.stack[idx].clone();
-            let value = self.stack[idx + 1].clone();
-
+    fn build_hash(&mut self, start_idx: usize) -> Result<Value, VmError> {
+        let mut hash = HashMap::with_capacity((self.sp - start_idx) / 2);
+        let mut elements = self.drain_stack(start_idx);
+        while let (Some(key), Some(value)) = (elements.next(), elements.next()) {
+            let key = HashKey::try_from(key).map_err(VmError::new)?;
             hash.insert(key, value);
-
-            idx += 2;
         }
-        Value::Hash(hash)
+        Ok(Value::Hash(Rc::new(hash)))
     }
 
-    fn build_array(&mut self, start_idx: usize, end_idx: usize) -> Value {
-        Value::Array(
-            (start_idx..end_idx)
-                .map(|idx| self.stack[idx].clone())
-                .collect::<Vec<Value>>(),
-        )
+    fn build_array(&mut self, start_idx: usize) -> Value {
+        let mut array = self.pool.take(self.sp - start_idx);
+        array.extend(self.drain_stack(start_idx));
+        Value::Array(Rc::new(array))
+    }
+
+    fn drain_stack(&mut self, start_idx: usize) -> impl Iterator<Item = Value> + '_ {
+        let end_idx = self.sp;
+        self.sp = start_idx;
+        self.stack[start_idx..end_idx]
+            .iter_mut()
+            .map(|value| std::mem::replace(value, Value::Null))
     }
 
     fn is_truthy(&mut self, value: Value) -> bool {
@@ -511,28 +878,40 @@ impl Vm {
             OpCode::OpAdd => self.push(left + right)?,
             OpCode::OpSub => self.push(left - right)?,
             OpCode::OpMul => self.push(left * right)?,
-            OpCode::OpDiv => self.push(left / right)?,
+            OpCode::OpDiv | OpCode::OpMod if right == 0 => {
+                return Err(VmError::new("division by zero"))
+            }
+            OpCode::OpDiv => self.push(left.wrapping_div(right))?,
+            OpCode::OpMod => self.push(left.wrapping_rem(right))?,
             _ => unreachable!(),
         };
         Ok(())
     }
 
+    /// Where the top `len` values of the stack start, bytecode that wasn't compiled here can
+    /// ask for more than there are.
+    fn stack_start(&self, len: usize) -> Result<usize, VmError> {
+        self.sp
+            .checked_sub(len)
+            .ok_or(VmError::new("not enough values on the stack"))
+    }
+
     fn pop(&mut self) -> Result<Value, VmError> {
-        if let Some(element) = self.stack.pop() {
-            self.sp -= 1;
-            self.last_popped_element = Some(element.clone());
-            Ok(element)
-        } else {
-            Err(VmError::new("You try to pop on an empty stack"))
+        if self.sp == 0 {
+            return Err(VmError::new("You try to pop on an empty stack"));
         }
+        self.sp -= 1;
+        Ok(std::mem::replace(&mut self.stack[self.sp], Value::Null))
     }
 
     fn push<V: Into<Value>>(&mut self, value: V) -> Result<(), VmError> {
-        if self.sp >= STACK_SIZE {
+        if self.sp >= self.config.stack_size {
             return Err(VmError::new("Stack Overflow"));
         }
 
-        self.stack.push(value.into());
+        // Slots above `sp` still hold what returning frames left there.
+        let old = std::mem::replace(&mut self.stack[self.sp], value.into());
+        self.pool.recycle(old);
         self.sp += 1;
         Ok(())
     }
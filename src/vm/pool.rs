@@ -0,0 +1,43 @@
+use std::rc::Rc;
+
+use crate::eval::value::Value;
+
+const MAX_BUFFERS: usize = 64;
+const MAX_CAPACITY: usize = 256;
+
+/// The buffers of arrays that died during a run, `OpArray` fills one instead of allocating.
+/// Large buffers aren't kept, and the pool is emptied when the run ends.
+#[derive(Debug, Default)]
+pub struct ArrayPool {
+    buffers: Vec<Vec<Value>>,
+}
+
+impl ArrayPool {
+    pub fn take(&mut self, len: usize) -> Vec<Value> {
+        match self.buffers.pop() {
+            Some(mut buffer) => {
+                buffer.reserve(len);
+                buffer
+            }
+            None => Vec::with_capacity(len),
+        }
+    }
+
+    /// Keeps the buffer of `value` when it's an array nothing else holds.
+    pub fn recycle(&mut self, value: Value) {
+        let Value::Array(array) = value else {
+            return;
+        };
+        if self.buffers.len() >= MAX_BUFFERS || array.capacity() > MAX_CAPACITY {
+            return;
+        }
+        if let Ok(mut buffer) = Rc::try_unwrap(array) {
+            buffer.clear();
+            self.buffers.push(buffer);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.buffers = vec![];
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::code::{Instructions, OpCode};
+use crate::eval::value::Value;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ProfileEntry {
+    pub count: u64,
+    pub time: Duration,
+}
+
+impl ProfileEntry {
+    fn add(&mut self, time: Duration) {
+        self.count += 1;
+        self.time += time;
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Profiler {
+    opcodes: HashMap<OpCode, ProfileEntry>,
+    functions: HashMap<*const Instructions, ProfileEntry>,
+}
+
+impl Profiler {
+    pub fn record(&mut self, op: OpCode, function: *const Instructions, time: Duration) {
+        self.opcodes.entry(op).or_default().add(time);
+        self.functions.entry(function).or_default().add(time);
+    }
+
+    pub fn report(&self, main: &Arc<Instructions>, constants: &[Value]) -> ProfileReport {
+        let name = |function: *const Instructions| {
+            if function == Arc::as_ptr(main) {
+                return "main".to_string();
+            }
+            constants
+                .iter()
+                .position(|constant| {
+                    matches!(constant, Value::CompiledFunction { instructions, .. } if Arc::as_ptr(instructions) == function)
+                })
+                .map_or("unknown".to_string(), |idx| format!("constant {idx}"))
+        };
+
+        let mut opcodes = self
+            .opcodes
+            .iter()
+            .map(|(op, entry)| (*op, *entry))
+            .collect::<Vec<_>>();
+        opcodes.sort_by(|(_, a), (_, b)| b.time.cmp(&a.time).then(b.count.cmp(&a.count)));
+
+        let mut functions = self
+            .functions
+            .iter()
+            .map(|(function, entry)| (name(*function), *entry))
+            .collect::<Vec<_>>();
+        functions.sort_by(|(_, a), (_, b)| b.time.cmp(&a.time).then(b.count.cmp(&a.count)));
+
+        ProfileReport { opcodes, functions }
+    }
+}
+
+#[derive(Debug)]
+pub struct ProfileReport {
+    pub opcodes: Vec<(OpCode, ProfileEntry)>,
+    pub functions: Vec<(String, ProfileEntry)>,
+}
+
+impl Display for ProfileReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "opcodes:")?;
+        for (op, entry) in &self.opcodes {
+            writeln!(
+                f,
+                "  {:<20} {:>10} {:?}",
+                format!("{op:?}"),
+                entry.count,
+                entry.time
+            )?;
+        }
+
+        writeln!(f, "functions:")?;
+        for (name, entry) in &self.functions {
+            writeln!(f, "  {:<20} {:>10} {:?}", name, entry.count, entry.time)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+use crate::eval::value::Value;
+
+use super::frame::Frame;
+
+#[derive(Debug, Clone)]
+pub struct VmSnapshot {
+    pub globals: Vec<Value>,
+    pub constants: Vec<Value>,
+    pub(super) stack: Option<StackSnapshot>,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct StackSnapshot {
+    pub stack: Vec<Value>,
+    pub frames: Vec<Frame>,
+    pub last_popped_element: Option<Value>,
+}
+
+impl VmSnapshot {
+    pub fn has_stack(&self) -> bool {
+        self.stack.is_some()
+    }
+}
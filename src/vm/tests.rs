@@ -1,14 +1,23 @@
 use crate::ast::program::Program;
-use crate::compiler::Compiler;
-use crate::eval::value::Value;
+use crate::code::{Instructions, OpCode};
+use crate::compiler::{ByteCode, Compiler};
+use crate::engine::run_source;
+use crate::eval::builtin::io::{Io, StdIo};
+use crate::eval::value::{HashKey, Value};
 use crate::lexer::Lexer;
 use crate::parser::Parser;
-use crate::vm::Vm;
+use crate::vm::config::VmConfig;
+use crate::vm::pool::ArrayPool;
+use crate::vm::trace::TraceEvent;
+use crate::vm::{Completion, StepResult, Vm};
 use core::panic;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 fn parse(input: String) -> Program {
-    let lexer = Lexer::new(input.chars().collect());
+    let lexer = Lexer::from(input);
     let mut parser = Parser::new(lexer);
     parser.parse_program()
 }
@@ -29,21 +38,10 @@ impl VmTestCase {
 
 fn run_vm_test(tests: Vec<VmTestCase>) {
     for test in tests {
-        let program = parse(test.input);
-
-        let mut compiler = Compiler::new();
-        if compiler.compile_program(program).is_err() {
-            panic!("Compiler pogram fail");
+        match run_source(&test.input) {
+            Ok(value) => assert_eq!(value, test.expected, "{}", test.input),
+            Err(err) => panic!("{}: {err}", test.input),
         }
-
-        let mut vm = Vm::new(compiler.bytecode());
-        if let Err(err) = vm.run() {
-            panic!("{}", err);
-        }
-
-        let stack_element = vm.last_popped_element;
-
-        assert_eq!(stack_element, Some(test.expected))
     }
 }
 
@@ -66,6 +64,10 @@ fn test_integer_arithmetic() {
         VmTestCase::new("-10", -10),
         VmTestCase::new("-50 + 100 + -50", 0),
         VmTestCase::new("(5 + 10 * 2 + 15 / 3) * 2 + -10", 50),
+        VmTestCase::new("0", 0),
+        VmTestCase::new("32767 + 32768", 65535),
+        VmTestCase::new("7 % 3", 1),
+        VmTestCase::new("-7 % 3", -1),
     ];
 
     run_vm_test(tests);
@@ -134,6 +136,18 @@ fn test_global_let_statements() {
     run_vm_test(tests);
 }
 
+#[test]
+fn test_comments() {
+    let tests = vec![
+        VmTestCase::new("// one\nlet one = 1; // the first\none // last", 1),
+        VmTestCase::new("let f = fn(x) {\n  // double it\n  x * 2\n};\nf(2)", 4),
+        VmTestCase::new("4 / 2 // not a division", 2),
+        VmTestCase::new("1 //", 1),
+    ];
+
+    run_vm_test(tests);
+}
+
 #[test]
 fn test_string_expression() {
     let tests = vec![
@@ -161,19 +175,19 @@ fn test_array_literals() {
 #[test]
 fn test_hash_literals() {
     let tests = vec![
-        VmTestCase::new("{}", HashMap::new()),
+        VmTestCase::new("{}", HashMap::<HashKey, Value>::new()),
         VmTestCase::new(
             "{1:2, 2:3}",
             HashMap::from([
-                (Value::Int(1), Value::Int(2)),
-                (Value::Int(2), Value::Int(3)),
+                (HashKey::Int(1), Value::Int(2)),
+                (HashKey::Int(2), Value::Int(3)),
             ]),
         ),
         VmTestCase::new(
             "{1+1:2*2, 3+3:4*4}",
             HashMap::from([
-                (Value::Int(2), Value::Int(4)),
-                (Value::Int(6), Value::Int(16)),
+                (HashKey::Int(2), Value::Int(4)),
+                (HashKey::Int(6), Value::Int(16)),
             ]),
         ),
     ];
@@ -193,11 +207,60 @@ fn test_index_expression() {
         VmTestCase::new("{ 1:2,3:4 }[1 + 2]", 4),
         VmTestCase::new("{ 1: 2 }[0]", Value::Null),
         VmTestCase::new("{  }[0]", Value::Null),
+        VmTestCase::new(r#""héllo"[1]"#, "é"),
+        VmTestCase::new(r#""héllo"[5]"#, Value::Null),
+        VmTestCase::new(r#""héllo"[-1]"#, Value::Null),
     ];
 
     run_vm_test(tests);
 }
 
+#[test]
+fn test_unusable_hash_keys() {
+    let tests = vec![
+        ("{[1]: 2}", "unusable as hash key: ARRAY"),
+        ("{1: 2}[fn() { 1 }]", "unusable as hash key: CLOSURE"),
+        ("{1: 2}[{}]", "unusable as hash key: HASH"),
+    ];
+
+    for (input, expected) in tests {
+        let program = parse(input.to_string());
+        let mut compiler = Compiler::new();
+
+        if let Err(err) = compiler.compile_program(program) {
+            panic!("compiler error: {err}");
+        }
+
+        let mut vm = Vm::new(compiler.bytecode());
+
+        if let Err(err) = vm.run() {
+            assert_eq!(err.msg, expected);
+        } else {
+            panic!("expected a Vm error")
+        };
+    }
+}
+
+#[test]
+fn test_division_by_zero() {
+    for input in ["1 / 0", "let a = 0; 10 % a", "fn(x) { 100 / x }(0)"] {
+        let program = parse(input.to_string());
+        let mut compiler = Compiler::new();
+
+        if let Err(err) = compiler.compile_program(program) {
+            panic!("compiler error: {err}");
+        }
+
+        let mut vm = Vm::new(compiler.bytecode());
+
+        if let Err(err) = vm.run() {
+            assert_eq!(err.msg, "division by zero");
+        } else {
+            panic!("expected a Vm error")
+        };
+    }
+}
+
 #[test]
 fn test_calling_functions_without_arguments() {
     let tests = vec![
@@ -242,6 +305,11 @@ fn test_calling_functions_without_arguments() {
         noReturnTwo();"#,
             Value::Null,
         ),
+        VmTestCase::new(
+            r#"let noReturn = fn() { };
+        [noReturn(), noReturn()];"#,
+            vec![Value::Null, Value::Null],
+        ),
         VmTestCase::new(
             r#"let returnsOne = fn() { 1; };
         let returnsOneReturner = fn() { returnsOne; };
@@ -381,6 +449,7 @@ fn test_builtin_functions() {
         VmTestCase::new(r#"len("")"#, 0),
         VmTestCase::new(r#"len("four")"#, 4),
         VmTestCase::new(r#"len("hello world")"#, 11),
+        VmTestCase::new(r#"len("héllo wörld")"#, 11),
         VmTestCase::new("len([1, 2, 3])", 3),
         VmTestCase::new("len([])", 0),
         VmTestCase::new(r#"puts("hello", "world!")"#, Value::Null),
@@ -396,6 +465,323 @@ fn test_builtin_functions() {
     run_vm_test(tests);
 }
 
+#[test]
+fn test_higher_order_builtins() {
+    let tests = vec![
+        VmTestCase::new("map([1, 2, 3], fn(x) { x * 2 })", vec![2, 4, 6]),
+        VmTestCase::new("map([], fn(x) { x })", Vec::<i64>::new()),
+        VmTestCase::new("map([[1], [2, 3]], len)", vec![1, 2]),
+        VmTestCase::new(
+            "let n = 10; let add = fn(x) { x + n }; map([1, 2], add)",
+            vec![11, 12],
+        ),
+        VmTestCase::new("filter([1, 2, 3, 4], fn(x) { x > 2 })", vec![3, 4]),
+        VmTestCase::new(
+            "reduce([1, 2, 3, 4], 0, fn(acc, x) { acc + x })",
+            10,
+        ),
+        VmTestCase::new("reduce([], 5, fn(acc, x) { acc + x })", 5),
+        VmTestCase::new(
+            "let sum = fn(arr) { reduce(arr, 0, fn(acc, x) { acc + x }) }; map([[1, 2], [3, 4]], sum)",
+            vec![3, 7],
+        ),
+        VmTestCase::new(
+            "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; map([5, 10], fib)",
+            vec![5, 55],
+        ),
+        VmTestCase::new(
+            "let f = fn() { let doubled = map([1, 2], fn(x) { x * 2 }); len(doubled) + 1 }; f()",
+            3,
+        ),
+    ];
+    run_vm_test(tests);
+}
+
+#[test]
+fn test_string_builtins() {
+    let tests = vec![
+        VmTestCase::new(r#"split("a,b,c", ",")"#, vec!["a", "b", "c"]),
+        VmTestCase::new(r#"split("abc", "")"#, vec!["a", "b", "c"]),
+        VmTestCase::new(r#"slice("日本語", 1)"#, "本語"),
+        VmTestCase::new(r#"slice("héllo", 1, 3)"#, "él"),
+        VmTestCase::new(r#"slice("héllo", -2, 99)"#, "héllo"),
+        VmTestCase::new(r#"slice("héllo", 3, 1)"#, ""),
+        VmTestCase::new("slice([1, 2, 3, 4], 1, 3)", vec![2, 3]),
+        VmTestCase::new("slice([1, 2, 3], 5)", Vec::<i64>::new()),
+        VmTestCase::new(r#"split("", ",")"#, vec![""]),
+        VmTestCase::new(r#"join(["a", "b", "c"], "-")"#, "a-b-c"),
+        VmTestCase::new(r#"join([], "-")"#, ""),
+        VmTestCase::new(r#"join(split("a b", " "), "_")"#, "a_b"),
+        VmTestCase::new(r#"trim("  monkey ")"#, "monkey"),
+        VmTestCase::new(r#"replace("banana", "a", "o")"#, "bonono"),
+        VmTestCase::new(r#"upper("Monkey")"#, "MONKEY"),
+        VmTestCase::new(r#"lower("Monkey")"#, "monkey"),
+        VmTestCase::new(r#"starts_with("monkey", "mon")"#, true),
+        VmTestCase::new(r#"starts_with("monkey", "key")"#, false),
+        VmTestCase::new(r#"ends_with("monkey", "key")"#, true),
+        VmTestCase::new(r#"ends_with("monkey", "mon")"#, false),
+    ];
+    run_vm_test(tests);
+}
+
+#[test]
+fn test_hash_builtins() {
+    let tests = vec![
+        VmTestCase::new(
+            r#"keys({"b": 2, "a": 1, 3: 0})"#,
+            vec![Value::Int(3), Value::from("a"), Value::from("b")],
+        ),
+        VmTestCase::new(r#"values({"b": 2, "a": 1, 3: 0})"#, vec![0, 1, 2]),
+        VmTestCase::new("keys({})", Vec::<i64>::new()),
+        VmTestCase::new(
+            "delete({1: 2, 3: 4}, 1)",
+            HashMap::from([(HashKey::Int(3), Value::Int(4))]),
+        ),
+        VmTestCase::new(
+            "delete({1: 2}, 5)",
+            HashMap::from([(HashKey::Int(1), Value::Int(2))]),
+        ),
+        VmTestCase::new("let h = {1: 2}; let d = delete(h, 1); h[1]", 2),
+        VmTestCase::new(r#"has_key({"a": 1}, "a")"#, true),
+        VmTestCase::new(r#"has_key({"a": 1}, "b")"#, false),
+    ];
+    run_vm_test(tests);
+}
+
+#[test]
+fn test_search_builtins() {
+    let tests = vec![
+        VmTestCase::new("contains([1, 2, 3], 2)", true),
+        VmTestCase::new(r#"contains([1, 2, 3], "2")"#, false),
+        VmTestCase::new("contains([[1], [2]], [2])", true),
+        VmTestCase::new(r#"contains("monkey", "key")"#, true),
+        VmTestCase::new(r#"contains("monkey", "")"#, true),
+        VmTestCase::new(r#"contains("monkey", "ape")"#, false),
+        VmTestCase::new(r#"contains({"a": 1}, "a")"#, true),
+        VmTestCase::new(r#"contains({"a": 1}, 1)"#, false),
+        VmTestCase::new("index_of([1, 2, 3, 2], 2)", 1),
+        VmTestCase::new("index_of([], 1)", -1),
+        VmTestCase::new(r#"index_of("banana", "na")"#, 2),
+        VmTestCase::new(r#"index_of("äbc", "c")"#, 2),
+        VmTestCase::new(r#"index_of("banana", "x")"#, -1),
+    ];
+    run_vm_test(tests);
+}
+
+#[test]
+fn test_conversion_builtins() {
+    let tests = vec![
+        VmTestCase::new(r#"int("12")"#, 12),
+        VmTestCase::new(r#"int(" -7 ")"#, -7),
+        VmTestCase::new("int(true)", 1),
+        VmTestCase::new("int(3)", 3),
+        VmTestCase::new("str(42)", "42"),
+        VmTestCase::new(r#"str("monkey")"#, "monkey"),
+        VmTestCase::new("str(false)", "false"),
+        VmTestCase::new("str([1, 2])", "[1,2]"),
+        VmTestCase::new(r#"int(str(42)) == 42"#, true),
+        VmTestCase::new(r#"bool("true")"#, true),
+        VmTestCase::new(r#"bool("false")"#, false),
+        VmTestCase::new("bool(0)", false),
+        VmTestCase::new("bool(2)", true),
+    ];
+    run_vm_test(tests);
+}
+
+#[test]
+fn test_math_builtins() {
+    let tests = vec![
+        VmTestCase::new("abs(-5)", 5),
+        VmTestCase::new("abs(5)", 5),
+        VmTestCase::new("min(3, 1, 2)", 1),
+        VmTestCase::new("max(3, 1, 2)", 3),
+        VmTestCase::new("max(-1)", -1),
+        VmTestCase::new("pow(2, 10)", 1024),
+        VmTestCase::new("pow(5, 0)", 1),
+        VmTestCase::new("sqrt(17)", 4),
+        VmTestCase::new("floor(7)", 7),
+        VmTestCase::new("ceil(-7)", -7),
+    ];
+    run_vm_test(tests);
+}
+
+#[test]
+fn test_random_builtins() {
+    let tests = vec![
+        VmTestCase::new("seed(42)", Value::Null),
+        VmTestCase::new("seed(7); let a = rand(); seed(7); a == rand()", true),
+        VmTestCase::new("seed(7); let a = rand(); a == rand()", false),
+        VmTestCase::new("rand() > -1", true),
+        VmTestCase::new(
+            "let xs = map(rest(split(\"0123456789\", \"\")), fn(x) { rand_int(-2, 3) }); len(filter(xs, fn(x) { if (x < -2) { true } else { x > 2 } }))",
+            0,
+        ),
+        VmTestCase::new("rand_int(5, 6)", 5),
+    ];
+    run_vm_test(tests);
+}
+
+#[test]
+fn test_range_builtin() {
+    let tests = vec![
+        VmTestCase::new("range(4)", vec![0, 1, 2, 3]),
+        VmTestCase::new("range(0)", Vec::<i64>::new()),
+        VmTestCase::new("range(-2)", Vec::<i64>::new()),
+        VmTestCase::new("range(2, 5)", vec![2, 3, 4]),
+        VmTestCase::new("range(0, 10, 3)", vec![0, 3, 6, 9]),
+        VmTestCase::new("range(5, 0, -2)", vec![5, 3, 1]),
+        VmTestCase::new("range(0, 5, -1)", Vec::<i64>::new()),
+        VmTestCase::new("reduce(range(1, 11), 0, fn(acc, x) { acc + x })", 55),
+    ];
+    run_vm_test(tests);
+}
+
+#[test]
+fn test_format_builtin() {
+    let tests = vec![
+        VmTestCase::new(r#"format("x={} y={}", 1, true)"#, "x=1 y=true"),
+        VmTestCase::new(r#"format("hi {}!", "monkey")"#, "hi monkey!"),
+        VmTestCase::new(r#"format("{}", [1, "a"])"#, r#"[1,"a"]"#),
+        VmTestCase::new(r#"format("{{}} {}", 1)"#, "{} 1"),
+        VmTestCase::new(r#"format("none")"#, "none"),
+        VmTestCase::new(r#"printf("{}", 1)"#, Value::Null),
+    ];
+    run_vm_test(tests);
+}
+
+#[test]
+fn test_eval_builtin() {
+    let tests = vec![
+        VmTestCase::new(r#"eval("1 + 2")"#, 3),
+        VmTestCase::new(r#"let a = 5; eval("a * 2")"#, 10),
+        VmTestCase::new(r#"eval("let b = 4"); eval("b + 1")"#, 5),
+        VmTestCase::new(r#"eval("let b = 4")"#, Value::Null),
+        VmTestCase::new(r#"eval("return 7; 8")"#, 7),
+        VmTestCase::new(r#"[1, eval("fn(x) { x * 2 }(1)"), 3]"#, vec![1, 2, 3]),
+        VmTestCase::new(r#"let g = 3; let f = fn() { eval("g + 1") }; f()"#, 4),
+        VmTestCase::new(r#"eval("y"); let y = 1;"#, Value::Null),
+        VmTestCase::new(
+            r#"let inc = eval("fn(x) { x + 1 }"); map([1, 2], inc)"#,
+            vec![2, 3],
+        ),
+    ];
+    run_vm_test(tests);
+}
+
+#[test]
+fn test_json_builtins() {
+    let tests = vec![
+        VmTestCase::new(
+            r#"json_parse(" [1, -2, true, null] ")"#,
+            vec![
+                Value::Int(1),
+                Value::Int(-2),
+                Value::Bool(true),
+                Value::Null,
+            ],
+        ),
+        VmTestCase::new(r#"json_parse("{}")"#, HashMap::<HashKey, Value>::new()),
+        VmTestCase::new(
+            r#"json_stringify({"b": [1, []], "a": {"c": true}})"#,
+            r#"{"a":{"c":true},"b":[1,[]]}"#,
+        ),
+        VmTestCase::new(r#"json_stringify("a")"#, r#""a""#),
+        VmTestCase::new(r#"json_stringify(json_parse("null"))"#, "null"),
+        VmTestCase::new(
+            r#"let v = {"k": [1, "two", false], "n": {}}; json_parse(json_stringify(v)) == v"#,
+            true,
+        ),
+    ];
+    run_vm_test(tests);
+
+    let program = parse(r#"json_parse(input())"#.to_string());
+    let mut compiler = Compiler::new();
+    if let Err(err) = compiler.compile_program(program) {
+        panic!("compiler error: {err}");
+    }
+    let io = BufferIo {
+        input: vec![r#"{ "b" : 1, "a": "x\u0041\n\"", "e": "\ud83d\ude00" }"#],
+        ..BufferIo::default()
+    };
+    let mut vm = Vm::new(compiler.bytecode()).with_io(Box::new(io));
+    if let Err(err) = vm.run() {
+        panic!("{err}");
+    }
+    assert_eq!(
+        vm.last_popped_element,
+        Some(Value::from(HashMap::from([
+            (HashKey::from("a"), Value::from("xA\n\"")),
+            (HashKey::from("b"), Value::Int(1)),
+            (HashKey::from("e"), Value::from("\u{1F600}")),
+        ])))
+    );
+
+    let program = parse(r#"json_stringify(json_parse(input()))"#.to_string());
+    let mut compiler = Compiler::new();
+    if let Err(err) = compiler.compile_program(program) {
+        panic!("compiler error: {err}");
+    }
+    let io = BufferIo {
+        input: vec![r#""a\"b\\\n\u0001""#],
+        ..BufferIo::default()
+    };
+    let mut vm = Vm::new(compiler.bytecode()).with_io(Box::new(io));
+    if let Err(err) = vm.run() {
+        panic!("{err}");
+    }
+    assert_eq!(
+        vm.last_popped_element,
+        Some(Value::from(r#""a\"b\\\n\u0001""#))
+    );
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_regex_builtins() {
+    let tests = vec![
+        VmTestCase::new(
+            r#"regex_match("(\w+)@(\w+)", "mail monkey@banana now")"#,
+            vec!["monkey@banana", "monkey", "banana"],
+        ),
+        VmTestCase::new(
+            r#"regex_match("(a)|(b)", "b")"#,
+            vec![Value::from("b"), Value::Null, Value::from("b")],
+        ),
+        VmTestCase::new(r#"regex_match("\d", "abc")"#, Value::Null),
+        VmTestCase::new(
+            r#"regex_find_all("\d+", "1 22 333")"#,
+            vec!["1", "22", "333"],
+        ),
+        VmTestCase::new(
+            r#"regex_find_all("(\w)=(\d)", "a=1 b=2")"#,
+            vec![vec!["a=1", "a", "1"], vec!["b=2", "b", "2"]],
+        ),
+        VmTestCase::new(
+            r#"regex_replace("(\w+)@(\w+)", "monkey@banana", "$2 at $1")"#,
+            "banana at monkey",
+        ),
+    ];
+    run_vm_test(tests);
+}
+
+#[cfg(not(feature = "regex"))]
+#[test]
+fn test_regex_builtins_disabled() {
+    let program = parse(r#"regex_match("a", "a")"#.to_string());
+    let mut compiler = Compiler::new();
+    if let Err(err) = compiler.compile_program(program) {
+        panic!("compiler error: {err}");
+    }
+    let mut vm = Vm::new(compiler.bytecode());
+    match vm.run() {
+        Err(err) => assert_eq!(
+            err.msg,
+            "'regex_match' needs the interpreter built with the regex feature"
+        ),
+        Ok(_) => panic!("expected a Vm error"),
+    }
+}
+
 #[test]
 fn test_builtin_functions_with_wrong_arguments() {
     let tests = vec![
@@ -410,6 +796,139 @@ fn test_builtin_functions_with_wrong_arguments() {
             "argument to 'push' must be ARRAY, got: INTEGER",
         ),
         ("last(1)", "argument to 'last' must be ARRAY, got INTEGER"),
+        (
+            "map(1, len)",
+            "argument to 'map' must be ARRAY, got INTEGER",
+        ),
+        (
+            "reduce([1], fn(acc, x) { acc })",
+            "wrong number of arguments, got=2, want=3",
+        ),
+        ("map([1], 1)", "calling non-function and non-built-in: 1"),
+        (
+            "map([1], fn(a, b) { a })",
+            "wrong number of arguments: want=2, got=1",
+        ),
+        (
+            r#"split(1, ",")"#,
+            "argument to 'split' must be STRING, got INTEGER",
+        ),
+        (
+            r#"join([1], ",")"#,
+            "elements of 'join' must be STRING, got INTEGER",
+        ),
+        (
+            r#"join("a", ",")"#,
+            "argument to 'join' must be ARRAY, got STRING",
+        ),
+        (
+            r#"replace("a", "b", 1)"#,
+            "argument to 'replace' must be STRING, got INTEGER",
+        ),
+        (
+            "upper(true)",
+            "argument to 'upper' must be STRING, got BOOLEAN",
+        ),
+        (
+            r#"ends_with("a", [])"#,
+            "argument to 'ends_with' must be STRING, got ARRAY",
+        ),
+        ("keys([])", "argument to 'keys' must be HASH, got ARRAY"),
+        ("has_key({}, [])", "unusable as hash key: ARRAY"),
+        (
+            "contains(1, 1)",
+            "argument to 'contains' not supported: got INTEGER",
+        ),
+        (
+            r#"contains("a", 1)"#,
+            "argument to 'contains' must be STRING, got INTEGER",
+        ),
+        ("contains({}, [])", "unusable as hash key: ARRAY"),
+        (
+            "index_of({}, 1)",
+            "argument to 'index_of' not supported: got HASH",
+        ),
+        (
+            "delete(1, 1)",
+            "argument to 'delete' must be HASH, got INTEGER",
+        ),
+        (r#"int("12a")"#, r#"could not parse "12a" as INTEGER"#),
+        ("int([])", "argument to 'int' not supported: got ARRAY"),
+        (r#"bool("yes")"#, r#"could not parse "yes" as BOOLEAN"#),
+        ("str(1, 2)", "wrong number of arguments, got=2, want=1"),
+        (
+            "abs(true)",
+            "argument to 'abs' must be INTEGER, got BOOLEAN",
+        ),
+        ("min()", "wrong number of arguments, got=0, want at least 1"),
+        (
+            r#"max(1, "2")"#,
+            "argument to 'max' must be INTEGER, got STRING",
+        ),
+        ("pow(2, -1)", "invalid exponent to 'pow': -1"),
+        ("pow(2, 64)", "integer overflow in 'pow'"),
+        ("sqrt(-4)", "invalid argument to 'sqrt': -4"),
+        ("rand(1)", "wrong number of arguments, got=1, want=0"),
+        ("rand_int(3, 3)", "empty range for 'rand_int': 3..3"),
+        (
+            "seed(true)",
+            "argument to 'seed' must be INTEGER, got BOOLEAN",
+        ),
+        (
+            "input(1)",
+            "argument to 'input' must be STRING, got INTEGER",
+        ),
+        ("range()", "wrong number of arguments, got=0, want=1..3"),
+        ("input(1, 2)", "wrong number of arguments, got=2, want=0..1"),
+        ("exit(1, 2)", "wrong number of arguments, got=2, want=0..1"),
+        ("range(0, 5, 0)", "'range' step must not be zero"),
+        (
+            r#"range("5")"#,
+            "argument to 'range' must be INTEGER, got STRING",
+        ),
+        (r#"format("{} {}", 1)"#, "not enough arguments for 'format'"),
+        (r#"printf("{}", 1, 2)"#, "too many arguments for 'printf'"),
+        (
+            "format(1)",
+            "argument to 'format' must be STRING, got INTEGER",
+        ),
+        (
+            r#"exit("1")"#,
+            "argument to 'exit' must be INTEGER, got STRING",
+        ),
+        (
+            r#"eval("1 +")"#,
+            "parser error: i dont now what is this: Eof 1:4",
+        ),
+        (
+            r#"let f = fn(x) { eval("x") }; f(1)"#,
+            "undefined variable: x",
+        ),
+        (
+            r#"eval("1 + true")"#,
+            "unsupported values for binary operation: true 1",
+        ),
+        (r#"json_parse("[1,")"#, "invalid json: unexpected end"),
+        (
+            r#"json_parse("1.5")"#,
+            "invalid json: floats are not supported: 1.5",
+        ),
+        (
+            r#"json_parse("1 2")"#,
+            "invalid json: unexpected '2' after the value",
+        ),
+        (
+            "json_stringify({1: 2})",
+            "json object keys must be STRING, got INTEGER",
+        ),
+        (
+            "json_stringify(fn() {})",
+            "CLOSURE can't be converted to json",
+        ),
+        (
+            "map([1, 2], fn(x) { x + true })",
+            "unsupported values for binary operation: true 1",
+        ),
     ];
     for (input, expected) in tests {
         let program = parse(input.to_string());
@@ -442,6 +961,11 @@ fn test_closures() {
         VmTestCase::new("let countDown = fn(x) { if (x == 0) { return 0; } else { countDown(x - 1); } }; countDown(1);", 0),
         VmTestCase::new("let countDown = fn(x) { if (x == 0) { return 0; } else { countDown(x - 1); } }; let wrapper = fn() { countDown(1); }; wrapper();", 0),
         VmTestCase::new("let wrapper = fn() { let countDown = fn(x) { if (x == 0) { return 0; } else { countDown(x - 1); } }; countDown(1); }; wrapper();", 0),
+        VmTestCase::new("let outer = fn() { let f = fn(x) { if (x == 0) { 7 } else { f(x - 1) } }; fn() { f(3) } }; outer()()", 7),
+        VmTestCase::new("let f = fn(n) { let r = fn(k) { if (k == 0) { n } else { r(k - 1) } }; r }; f(5)(3)", 5),
+        VmTestCase::new("let make = fn(x) { let h = fn() { x }; h }; let k = make(4); let j = make(5); k() + j()", 9),
+        VmTestCase::new("let f = fn() { let a = 1; let g = fn() { let b = 2; fn() { a + b } }; g()() }; f()", 3),
+        VmTestCase::new("let f = fn(x) { let x = x + 1; fn() { x } }; f(1)()", 2),
     ];
 
     run_vm_test(tests);
@@ -454,3 +978,522 @@ fn test_recursive_fibonacci() {
     ];
     run_vm_test(tests);
 }
+
+#[test]
+fn test_memoize() {
+    let tests = vec![
+        VmTestCase::new("let fib = memoize(fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }); fib(80);", 23416728348467685),
+        VmTestCase::new("let calls = memoize(fn(x) { puts(x); x * 2 }); calls(2) + calls(2) + memoize(len)([1])", 9),
+    ];
+    run_vm_test(tests);
+}
+
+#[test]
+fn test_undefined_builtin() {
+    let byte_code = ByteCode {
+        instructions: Instructions::from(vec![
+            (OpCode::OpGetBuiltin, vec![99]),
+            (OpCode::OpPop, vec![]),
+        ])
+        .into(),
+        constants: Default::default(),
+        interner: Default::default(),
+        symbol_table: None,
+    };
+
+    let mut vm = Vm::new(byte_code);
+    match vm.run() {
+        Err(err) => assert_eq!(err.msg, "undefined builtin: 99"),
+        Ok(_) => panic!("expected a Vm error"),
+    }
+}
+
+#[test]
+fn test_push_int_operand_is_signed() {
+    let byte_code = ByteCode {
+        instructions: Instructions::from(vec![
+            (OpCode::OpPushInt, vec![-2]),
+            (OpCode::OpPop, vec![]),
+        ])
+        .into(),
+        constants: Default::default(),
+        interner: Default::default(),
+        symbol_table: None,
+    };
+
+    let mut vm = Vm::new(byte_code);
+    if let Err(err) = vm.run() {
+        panic!("{err}");
+    }
+    assert_eq!(vm.last_popped_element, Some(Value::Int(-2)));
+}
+
+#[test]
+fn test_interrupt() {
+    static INTERRUPT: AtomicBool = AtomicBool::new(false);
+    let program = parse("let f = fn(x) { f(x + 1) }; f(1)".to_string());
+    let mut compiler = Compiler::new();
+    if let Err(err) = compiler.compile_program(program) {
+        panic!("compiler error: {err}");
+    }
+    INTERRUPT.store(true, Ordering::Relaxed);
+    let mut vm = Vm::new(compiler.bytecode()).with_interrupt(&INTERRUPT);
+    match vm.run() {
+        Err(err) => assert_eq!(err.msg, "interrupted"),
+        Ok(_) => panic!("expected the run to be interrupted"),
+    }
+}
+
+#[test]
+fn test_fuel() {
+    let tests = vec![
+        ("1 + 2", 4, Ok(Value::Int(3))),
+        ("1 + 2", 3, Err("fuel exhausted")),
+        ("let f = fn(x) { f(x) }; f(1)", 100, Err("fuel exhausted")),
+    ];
+
+    for (input, fuel, expected) in tests {
+        let program = parse(input.to_string());
+        let mut compiler = Compiler::new();
+        if let Err(err) = compiler.compile_program(program) {
+            panic!("compiler error: {err}");
+        }
+
+        let mut vm = Vm::new(compiler.bytecode()).with_fuel(fuel);
+        match (vm.run(), expected) {
+            (Ok(_), Ok(expected)) => assert_eq!(vm.last_popped_element, Some(expected)),
+            (Err(err), Err(expected)) => assert_eq!(err.msg, expected),
+            (result, _) => panic!(
+                "unexpected result for {input}: {:?}",
+                result.err().map(|err| err.msg)
+            ),
+        }
+    }
+}
+
+#[test]
+fn test_config_limits() {
+    let countdown = "let f = fn(n) { if (n == 0) { 0 } else { f(n - 1) } }; f(1000)";
+    let tests = vec![
+        (countdown, VmConfig::new(), Err("Stack Overflow")),
+        (
+            countdown,
+            VmConfig::new().stack_size(4096),
+            Ok(Value::Int(0)),
+        ),
+        (
+            countdown,
+            VmConfig::new().stack_size(4096).max_frames(100),
+            Err("maximum call depth exceeded: 100"),
+        ),
+        (
+            "let a = 1; let b = 2; a + b",
+            VmConfig::new().max_globals(1),
+            Err("too many globals: the limit is 1"),
+        ),
+    ];
+
+    for (input, config, expected) in tests {
+        let program = parse(input.to_string());
+        let mut compiler = Compiler::new();
+        if let Err(err) = compiler.compile_program(program) {
+            panic!("compiler error: {err}");
+        }
+
+        let mut vm = Vm::with_config(compiler.bytecode(), config);
+        match (vm.run(), expected) {
+            (Ok(_), Ok(expected)) => assert_eq!(vm.last_popped_element, Some(expected)),
+            (Err(err), Err(expected)) => assert_eq!(err.msg, expected),
+            (result, _) => panic!(
+                "unexpected result for {input}: {:?}",
+                result.err().map(|err| err.msg)
+            ),
+        }
+    }
+}
+
+#[test]
+fn test_stepping() {
+    let program = parse("let add = fn(a, b) { a + b }; add(1, 2)".to_string());
+    let mut compiler = Compiler::new();
+    if let Err(err) = compiler.compile_program(program) {
+        panic!("compiler error: {err}");
+    }
+    let mut vm = Vm::new(compiler.bytecode());
+
+    assert!(matches!(vm.step(), StepResult::Continue));
+    assert!(matches!(vm.run_steps(3), StepResult::Continue));
+    assert_eq!(vm.last_popped_element, None);
+
+    let mut steps = 4;
+    loop {
+        match vm.step() {
+            StepResult::Continue => steps += 1,
+            StepResult::Halted => {
+                steps += 1;
+                break;
+            }
+            StepResult::Exit(code) => panic!("unexpected exit({code})"),
+            StepResult::Error(err) => panic!("{err}"),
+        }
+    }
+    // OpClosure, OpSetGlobal, OpGetGlobal, OpConst1, OpPushInt, OpCall, OpGetLocal,
+    // OpGetLocal, OpAdd, OpReturnValue, OpPop
+    assert_eq!(steps, 11);
+    assert_eq!(vm.last_popped_element, Some(Value::Int(3)));
+    assert!(matches!(vm.step(), StepResult::Halted));
+
+    let program = parse("1 + true".to_string());
+    let mut compiler = Compiler::new();
+    if let Err(err) = compiler.compile_program(program) {
+        panic!("compiler error: {err}");
+    }
+    let mut vm = Vm::new(compiler.bytecode());
+    match vm.run_steps(10) {
+        StepResult::Error(err) => {
+            assert_eq!(err.msg, "unsupported values for binary operation: true 1")
+        }
+        result => panic!("expected a Vm error, got {result:?}"),
+    }
+}
+
+#[test]
+fn test_trace_hook() {
+    thread_local! {
+        static EVENTS: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
+    }
+    fn trace(event: &TraceEvent) {
+        let top = event.top.map(|value| value.to_string());
+        EVENTS.with(|events| {
+            events.borrow_mut().push(format!(
+                "{:04} {:?} {:?} {top:?}",
+                event.ip, event.op, event.operands
+            ))
+        });
+    }
+
+    let program = parse("let a = 300; a - 1".to_string());
+    let mut compiler = Compiler::new();
+    if let Err(err) = compiler.compile_program(program) {
+        panic!("compiler error: {err}");
+    }
+    let mut vm = Vm::new(compiler.bytecode()).with_trace(trace);
+    if let Err(err) = vm.run() {
+        panic!("{err}");
+    }
+
+    EVENTS.with(|events| {
+        assert_eq!(
+            *events.borrow(),
+            vec![
+                "0000 OpPushInt [300] None",
+                "0003 OpSetGlobal [0] Some(\"300\")",
+                "0006 OpGetGlobal [0] None",
+                "0009 OpConst1 [] Some(\"300\")",
+                "0010 OpSub [] Some(\"1\")",
+                "0011 OpPop [] Some(\"299\")",
+            ]
+        )
+    });
+}
+
+#[test]
+fn test_profile_report() {
+    let input = "let fibonacci = fn(x) { if (x < 2) { x } else { fibonacci(x - 1) + fibonacci(x - 2) } }; fibonacci(10);";
+    let program = parse(input.to_string());
+    let mut compiler = Compiler::new();
+    if let Err(err) = compiler.compile_program(program) {
+        panic!("compiler error: {err}");
+    }
+
+    let mut vm = Vm::new(compiler.bytecode());
+    if let Err(err) = vm.run() {
+        panic!("{err}");
+    }
+    assert!(vm.profile_report().is_none());
+
+    let mut vm = Vm::new(compiler.bytecode()).with_profiling();
+    if let Err(err) = vm.run() {
+        panic!("{err}");
+    }
+    let report = vm.profile_report().expect("the vm should be profiling");
+
+    let calls = report
+        .opcodes
+        .iter()
+        .find(|(op, _)| *op == OpCode::OpCall)
+        .map(|(_, entry)| entry.count);
+    assert_eq!(calls, Some(177));
+    let mut functions = report
+        .functions
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    functions.sort();
+    assert_eq!(functions, vec!["constant 0", "main"]);
+    let total: u64 = report.functions.iter().map(|(_, entry)| entry.count).sum();
+    assert_eq!(
+        total,
+        report
+            .opcodes
+            .iter()
+            .map(|(_, entry)| entry.count)
+            .sum::<u64>()
+    );
+}
+
+#[test]
+fn test_snapshot_restore() {
+    let program = parse("let a = 5; let b = a + true;".to_string());
+    let mut compiler = Compiler::new();
+    if let Err(err) = compiler.compile_program(program) {
+        panic!("compiler error: {err}");
+    }
+    let globals = Rc::new(RefCell::new(vec![]));
+    let mut vm = Vm::new_with_global_store(compiler.bytecode(), globals.clone());
+
+    let snapshot = vm.snapshot();
+    assert!(!snapshot.has_stack());
+    assert!(vm.run().is_err());
+    assert_eq!(*globals.borrow(), vec![Value::Int(5)]);
+    vm.restore(snapshot);
+    assert!(globals.borrow().is_empty());
+
+    let program = parse("let add = fn(a, b) { a + b }; add(20, 22)".to_string());
+    let mut compiler = Compiler::new();
+    if let Err(err) = compiler.compile_program(program) {
+        panic!("compiler error: {err}");
+    }
+    let mut vm = Vm::new(compiler.bytecode());
+    assert!(matches!(vm.run_steps(7), StepResult::Continue));
+
+    let snapshot = vm.snapshot_with_stack();
+    assert!(snapshot.has_stack());
+    if let Err(err) = vm.run() {
+        panic!("{err}");
+    }
+    assert_eq!(vm.last_popped_element, Some(Value::Int(42)));
+
+    vm.restore(snapshot);
+    assert_eq!(vm.last_popped_element, None);
+    if let Err(err) = vm.run() {
+        panic!("{err}");
+    }
+    assert_eq!(vm.last_popped_element, Some(Value::Int(42)));
+}
+
+#[test]
+fn test_concatenation_isnt_interned() {
+    let program = parse(
+        r#"let grow = fn(s, n) { if (n == 0) { s } else { grow(s + "x", n - 1) } }; len(grow("", 100))"#
+            .to_string(),
+    );
+    let mut compiler = Compiler::new();
+    if let Err(err) = compiler.compile_program(program) {
+        panic!("compiler error: {err}");
+    }
+    let byte_code = compiler.bytecode();
+    let interner = byte_code.interner.clone();
+    let interned = interner.lock().unwrap().len();
+    let mut vm = Vm::new(byte_code);
+    if let Err(err) = vm.run() {
+        panic!("{err}");
+    }
+
+    assert_eq!(vm.last_popped_element, Some(Value::Int(100)));
+    assert_eq!(interner.lock().unwrap().len(), interned);
+}
+
+#[test]
+fn test_array_pool() {
+    let mut pool = ArrayPool::default();
+    pool.recycle(Value::from(vec![1, 2, 3]));
+    let buffer = pool.take(1);
+    assert!(buffer.is_empty());
+    assert!(buffer.capacity() >= 3);
+
+    let shared = Value::from(vec![1, 2, 3]);
+    pool.recycle(shared.clone());
+    assert_eq!(pool.take(0).capacity(), 0);
+    assert_eq!(shared, Value::from(vec![1, 2, 3]));
+}
+
+#[test]
+fn test_recycled_arrays() {
+    let tests = vec![
+        VmTestCase::new("let a = [1, 2]; len([3, 4, 5]); [6]; a", vec![1, 2]),
+        VmTestCase::new(
+            "let f = fn(n) { if (n == 0) { [] } else { let a = [n, n]; len(a); rest(a) } }; [f(1), f(2), [7]]",
+            Value::from(vec![Value::from(vec![1]), Value::from(vec![2]), Value::from(vec![7])]),
+        ),
+    ];
+    run_vm_test(tests);
+}
+
+#[test]
+fn test_error_position() {
+    let tests = [
+        ("let a = 1;\nlet b = a + true;", Some((2, 1))),
+        (
+            "let f = fn() { [1][0] + true };\n\n  puts(f());",
+            Some((3, 3)),
+        ),
+        ("let f = fn(x) { x - 1 };\nmap([1, true], f)", Some((2, 1))),
+    ];
+    for (input, expected) in tests {
+        let mut compiler = Compiler::new();
+        if let Err(err) = compiler.compile_program(parse(input.to_string())) {
+            panic!("compiler error: {err}");
+        }
+        let source_map = compiler.source_map("test.mk", input);
+        let mut vm = Vm::new(compiler.bytecode());
+        match vm.run() {
+            Err(err) => {
+                assert!(err.offset().is_some());
+                assert_eq!(err.locate(&source_map).position(), expected, "{input}");
+            }
+            Ok(_) => panic!("expected a Vm error for {input}"),
+        }
+    }
+}
+
+#[test]
+fn test_top_level_return() {
+    let tests = vec![
+        VmTestCase::new("return 5; 10", 5),
+        VmTestCase::new("let f = fn() { 1 }; if (true) { return f() + 1; } 9", 2),
+        VmTestCase::new("let a = [1, 2]; return a; a", vec![1, 2]),
+    ];
+    run_vm_test(tests);
+
+    let mut compiler = Compiler::new();
+    if let Err(err) = compiler.compile_program(parse("1; return 2; 3".to_string())) {
+        panic!("compiler error: {err}");
+    }
+    let mut vm = Vm::new(compiler.bytecode());
+    assert!(matches!(vm.run_steps(100), StepResult::Halted));
+    assert!(matches!(vm.step(), StepResult::Halted));
+    assert_eq!(vm.last_popped_element, Some(Value::Int(2)));
+}
+
+#[test]
+fn test_exit() {
+    let tests = [
+        ("exit(3); 1", Completion::Exit(3)),
+        ("exit()", Completion::Exit(0)),
+        (
+            "let f = fn() { map([1], fn(x) { exit(x + 1) }) }; f(); 1",
+            Completion::Exit(2),
+        ),
+        ("1", Completion::Finished),
+    ];
+    for (input, expected) in tests {
+        let program = parse(input.to_string());
+        let mut compiler = Compiler::new();
+        if let Err(err) = compiler.compile_program(program) {
+            panic!("compiler error: {err}");
+        }
+        let mut vm = Vm::new(compiler.bytecode());
+        match vm.run() {
+            Ok(completion) => assert_eq!(completion, expected),
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    let program = parse("exit(7)".to_string());
+    let mut compiler = Compiler::new();
+    if let Err(err) = compiler.compile_program(program) {
+        panic!("compiler error: {err}");
+    }
+    let mut vm = Vm::new(compiler.bytecode());
+    assert!(matches!(vm.run_steps(10), StepResult::Exit(7)));
+}
+
+#[derive(Debug, Default)]
+struct BufferIo {
+    input: Vec<&'static str>,
+    output: Rc<RefCell<String>>,
+}
+
+impl Io for BufferIo {
+    fn write(&mut self, text: &str) -> Result<(), String> {
+        self.output.borrow_mut().push_str(text);
+        Ok(())
+    }
+
+    fn read_line(&mut self) -> Result<Option<String>, String> {
+        Ok((!self.input.is_empty()).then(|| self.input.remove(0).to_string()))
+    }
+}
+
+#[test]
+fn test_io() {
+    let program = parse(
+        r#"let name = input("name? "); puts("hi " + name); printf("{}!", name); let rest = input(); [input(), rest]"#
+            .to_string(),
+    );
+    let mut compiler = Compiler::new();
+    if let Err(err) = compiler.compile_program(program) {
+        panic!("compiler error: {err}");
+    }
+    let output = Rc::new(RefCell::new(String::new()));
+    let io = BufferIo {
+        input: vec!["monkey\n", "banana\r\n"],
+        output: Rc::clone(&output),
+    };
+    let mut vm = Vm::new(compiler.bytecode()).with_io(Box::new(io));
+    if let Err(err) = vm.run() {
+        panic!("{err}");
+    }
+
+    assert_eq!(*output.borrow(), "name? \"hi monkey\"\nmonkey!");
+    assert_eq!(
+        vm.last_popped_element,
+        Some(Value::from(vec![Value::Null, Value::from("banana")]))
+    );
+
+    let program = parse("input()".to_string());
+    let mut compiler = Compiler::new();
+    if let Err(err) = compiler.compile_program(program) {
+        panic!("compiler error: {err}");
+    }
+    let mut vm = Vm::new(compiler.bytecode()).with_io(Box::new(StdIo::without_input()));
+    match vm.run() {
+        Err(err) => assert_eq!(err.msg, "input is disabled"),
+        Ok(_) => panic!("expected a Vm error"),
+    }
+}
+
+#[test]
+fn test_help() {
+    let program = parse(r#"help(push); help("len"); help()"#.to_string());
+    let mut compiler = Compiler::new();
+    if let Err(err) = compiler.compile_program(program) {
+        panic!("compiler error: {err}");
+    }
+    let output = Rc::new(RefCell::new(String::new()));
+    let io = BufferIo {
+        output: Rc::clone(&output),
+        ..BufferIo::default()
+    };
+    let mut vm = Vm::new(compiler.bytecode()).with_io(Box::new(io));
+    if let Err(err) = vm.run() {
+        panic!("{err}");
+    }
+
+    let output = output.borrow();
+    assert!(output.starts_with(
+        "push(array, value)\n  A new array with value added at the end.\n  push([1, 2], 3) // [1, 2, 3]\nlen(value)\n"
+    ));
+    assert!(output.ends_with("memoize(fn)\nhelp(builtin?)\n"));
+
+    let program = parse(r#"help("nope")"#.to_string());
+    let mut compiler = Compiler::new();
+    if let Err(err) = compiler.compile_program(program) {
+        panic!("compiler error: {err}");
+    }
+    match Vm::new(compiler.bytecode()).run() {
+        Err(err) => assert_eq!(err.msg, "unknown builtin: nope"),
+        Ok(_) => panic!("expected a Vm error"),
+    }
+}
@@ -0,0 +1,12 @@
+use crate::code::OpCode;
+use crate::eval::value::Value;
+
+pub type TraceHook = fn(&TraceEvent);
+
+#[derive(Debug)]
+pub struct TraceEvent<'a> {
+    pub ip: usize,
+    pub op: OpCode,
+    pub operands: Vec<i64>,
+    pub top: Option<&'a Value>,
+}